@@ -1,4 +1,5 @@
-use crate::model::{PartOfSpeech,Release};
+use crate::model::{PartOfSpeech,Release,TagMapping};
+use crate::ids::IdStrategy;
 use std::collections::HashMap;
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
@@ -6,17 +7,142 @@ use std::collections::HashMap;
 pub struct Config {
     pub pos_property : Option<String>,
     pub pos_mapping : Option<HashMap<String, PartOfSpeech>>,
+    /// Tags mapped to a UD part of speech, a lexinfo part of speech and UD
+    /// features. These take precedence over `pos_mapping` and the defaults
+    pub tagset : Option<HashMap<String, TagMapping>>,
     pub default_release : Option<Release>,
-    pub default_id : Option<String>
+    pub default_id : Option<String>,
+    pub jsonld_contexts : Option<HashMap<String, String>>,
+    /// How entries without an identifier are identified
+    pub id_strategy : Option<IdStrategy>,
+    /// The identifier of an entry without one, where `{lemma}`, `{pos}`,
+    /// `{n}` (the homograph number) and `{hash}` are replaced
+    pub id_template : Option<String>,
+    /// A directory that local media files may be read from, besides the
+    /// directory of the data file
+    pub media_root : Option<String>
 }
 
+const LEXINFO : &str = "http://www.lexinfo.net/ontology/2.0/lexinfo#";
+
+/// The default mapping of the lexinfo parts of speech and of the tags
+/// commonly used in TEI dictionaries: the tag, the UD part of speech, the
+/// lexinfo part of speech and UD features
+static DEFAULT_TAGS : &[(&str, PartOfSpeech, &str, &str)] = &[
+    ("adjective", PartOfSpeech::ADJ, "adjective", ""),
+    ("adposition", PartOfSpeech::ADP, "adposition", ""),
+    ("adverb", PartOfSpeech::ADV, "adverb", ""),
+    ("auxiliary", PartOfSpeech::AUX, "auxiliary", ""),
+    ("coordinatingConjunction", PartOfSpeech::CCONJ, "coordinatingConjunction", ""),
+    ("determiner", PartOfSpeech::DET, "determiner", ""),
+    ("interjection", PartOfSpeech::INTJ, "interjection", ""),
+    ("commonNoun", PartOfSpeech::NOUN, "commonNoun", ""),
+    ("numeral", PartOfSpeech::NUM, "numeral", ""),
+    ("particle", PartOfSpeech::PART, "particle", ""),
+    ("pronoun", PartOfSpeech::PRON, "pronoun", ""),
+    ("properNoun", PartOfSpeech::PROPN, "properNoun", ""),
+    ("punctuation", PartOfSpeech::PUNCT, "punctuation", ""),
+    ("subordinatingConjunction", PartOfSpeech::SCONJ, "subordinatingConjunction", ""),
+    ("symbol", PartOfSpeech::SYM, "symbol", ""),
+    ("verb", PartOfSpeech::VERB, "verb", ""),
+    ("other", PartOfSpeech::X, "other", ""),
+    ("n", PartOfSpeech::NOUN, "commonNoun", ""),
+    ("n.", PartOfSpeech::NOUN, "commonNoun", ""),
+    ("noun", PartOfSpeech::NOUN, "commonNoun", ""),
+    ("s.", PartOfSpeech::NOUN, "commonNoun", ""),
+    ("sb.", PartOfSpeech::NOUN, "commonNoun", ""),
+    ("subst", PartOfSpeech::NOUN, "commonNoun", ""),
+    ("subst.", PartOfSpeech::NOUN, "commonNoun", ""),
+    ("nm", PartOfSpeech::NOUN, "commonNoun", "Gender=Masc"),
+    ("n.m.", PartOfSpeech::NOUN, "commonNoun", "Gender=Masc"),
+    ("sm", PartOfSpeech::NOUN, "commonNoun", "Gender=Masc"),
+    ("nf", PartOfSpeech::NOUN, "commonNoun", "Gender=Fem"),
+    ("n.f.", PartOfSpeech::NOUN, "commonNoun", "Gender=Fem"),
+    ("sf", PartOfSpeech::NOUN, "commonNoun", "Gender=Fem"),
+    ("nn", PartOfSpeech::NOUN, "commonNoun", "Gender=Neut"),
+    ("n.n.", PartOfSpeech::NOUN, "commonNoun", "Gender=Neut"),
+    ("npl", PartOfSpeech::NOUN, "commonNoun", "Number=Plur"),
+    ("n.pl.", PartOfSpeech::NOUN, "commonNoun", "Number=Plur"),
+    ("pn", PartOfSpeech::PROPN, "properNoun", ""),
+    ("prop.", PartOfSpeech::PROPN, "properNoun", ""),
+    ("v", PartOfSpeech::VERB, "verb", ""),
+    ("v.", PartOfSpeech::VERB, "verb", ""),
+    ("vb", PartOfSpeech::VERB, "verb", ""),
+    ("vt", PartOfSpeech::VERB, "verb", ""),
+    ("v.t.", PartOfSpeech::VERB, "verb", ""),
+    ("vi", PartOfSpeech::VERB, "verb", ""),
+    ("v.i.", PartOfSpeech::VERB, "verb", ""),
+    ("aux", PartOfSpeech::AUX, "auxiliary", ""),
+    ("adj", PartOfSpeech::ADJ, "adjective", ""),
+    ("adj.", PartOfSpeech::ADJ, "adjective", ""),
+    ("a.", PartOfSpeech::ADJ, "adjective", ""),
+    ("adv", PartOfSpeech::ADV, "adverb", ""),
+    ("adv.", PartOfSpeech::ADV, "adverb", ""),
+    ("prep", PartOfSpeech::ADP, "preposition", ""),
+    ("prep.", PartOfSpeech::ADP, "preposition", ""),
+    ("conj", PartOfSpeech::CCONJ, "conjunction", ""),
+    ("conj.", PartOfSpeech::CCONJ, "conjunction", ""),
+    ("pron", PartOfSpeech::PRON, "pronoun", ""),
+    ("pron.", PartOfSpeech::PRON, "pronoun", ""),
+    ("art", PartOfSpeech::DET, "article", ""),
+    ("art.", PartOfSpeech::DET, "article", ""),
+    ("det", PartOfSpeech::DET, "determiner", ""),
+    ("num", PartOfSpeech::NUM, "numeral", ""),
+    ("num.", PartOfSpeech::NUM, "numeral", ""),
+    ("interj", PartOfSpeech::INTJ, "interjection", ""),
+    ("interj.", PartOfSpeech::INTJ, "interjection", ""),
+    ("int.", PartOfSpeech::INTJ, "interjection", ""),
+    ("part", PartOfSpeech::PART, "particle", ""),
+    ("ptcl", PartOfSpeech::PART, "particle", "")
+];
+
 impl Config {
     pub fn blank() -> Config {
         Config {
             pos_property: None,
             pos_mapping: None,
+            tagset: None,
             default_release: None,
-            default_id: None
+            default_id: None,
+            jsonld_contexts: None,
+            id_strategy: None,
+            id_template: None,
+            media_root: None
+        }
+    }
+
+    /// Map a tag (the content of a TEI element or an RDF URI) by the
+    /// `tagset`, then by the `pos_mapping` or, if there is none, by the
+    /// default mapping
+    pub fn map_tag(&self, tag : &str) -> Option<TagMapping> {
+        if let Some(mapping) = self.tagset.as_ref().and_then(|t| t.get(tag)) {
+            return Some(mapping.clone());
+        }
+        if let Some(ref mapping) = self.pos_mapping {
+            return mapping.get(tag).map(|pos| TagMapping { pos : Some(pos.clone()), ..TagMapping::default() });
         }
+        let local = tag.strip_prefix(LEXINFO).unwrap_or(tag);
+        DEFAULT_TAGS.iter().find(|t| t.0 == local)
+            .or_else(|| DEFAULT_TAGS.iter().find(|t| t.0.eq_ignore_ascii_case(local)))
+            .map(|(_, pos, lexinfo, features)| TagMapping {
+                pos : Some(pos.clone()),
+                lexinfo : Some(lexinfo.to_string()),
+                features : if features.is_empty() { None } else { Some(features.to_string()) }
+            })
     }
 }
+
+#[test]
+fn test_map_tag() {
+    let config = Config::blank();
+    assert_eq!(config.map_tag("subst.").unwrap().pos, Some(PartOfSpeech::NOUN));
+    assert_eq!(config.map_tag("nf").unwrap().features, Some("Gender=Fem".to_string()));
+    assert_eq!(config.map_tag("http://www.lexinfo.net/ontology/2.0/lexinfo#commonNoun").unwrap().lexinfo, Some("commonNoun".to_string()));
+    assert_eq!(config.map_tag("Adj").unwrap().pos, Some(PartOfSpeech::ADJ));
+    assert!(config.map_tag("foo").is_none());
+    let config : Config = serde_json::from_str("{\"posMapping\":{\"substantive\":\"NOUN\"},
+        \"tagset\":{\"sostantivo femminile\":{\"pos\":\"NOUN\",\"features\":\"Gender=Fem\"}}}").unwrap();
+    assert_eq!(config.map_tag("substantive").unwrap().pos, Some(PartOfSpeech::NOUN));
+    assert_eq!(config.map_tag("sostantivo femminile").unwrap().features, Some("Gender=Fem".to_string()));
+    assert!(config.map_tag("n").is_none());
+}
@@ -0,0 +1,131 @@
+//! Identifiers for entries that are not identified in the source: TEI
+//! entries without an `xml:id` and OntoLex entries that are blank nodes.
+//! With the `hash` strategy the identifier depends only on the entry, so it
+//! is the same when a dictionary is loaded again after other entries have
+//! been added. The `lemma` strategy is stable in the same way only if the
+//! source gives the homograph numbers of the entries
+use crate::config::Config;
+use crate::model::PartOfSpeech;
+use std::collections::{HashMap, HashSet};
+
+/// How the identifiers of unidentified entries are made
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IdStrategy {
+    /// `unidentified_entry_N`, numbered in the order the entries are read
+    Sequential,
+    /// A hash of the content of the entry
+    Hash,
+    /// The lemma, part of speech and homograph number, e.g., `bank-noun-2`.
+    /// Without a homograph number in the source, the entries with the same
+    /// lemma and part of speech are numbered in the order they are read
+    Lemma,
+    /// The `idTemplate` of the configuration
+    Template
+}
+
+/// Makes identifiers for entries, avoiding those already used
+pub struct IdGenerator {
+    template : Option<String>,
+    used : HashSet<String>,
+    homographs : HashMap<String, u32>,
+    count : u32
+}
+
+impl IdGenerator {
+    /// A generator by the strategy of the configuration, which avoids the
+    /// identifiers in `used`
+    pub fn new(config : &Config, used : HashSet<String>) -> IdGenerator {
+        let strategy = config.id_strategy.clone().unwrap_or(
+            if config.id_template.is_some() { IdStrategy::Template } else { IdStrategy::Sequential });
+        let template = match strategy {
+            IdStrategy::Sequential => None,
+            IdStrategy::Hash => Some("{hash}".to_string()),
+            IdStrategy::Lemma => Some("{lemma}-{pos}-{n}".to_string()),
+            IdStrategy::Template => Some(config.id_template.clone().unwrap_or_else(|| "{hash}".to_string()))
+        };
+        IdGenerator { template, used, homographs : HashMap::new(), count : 0 }
+    }
+
+    /// Whether the identifiers are numbered in the order entries are read
+    pub fn is_sequential(&self) -> bool {
+        self.template.is_none()
+    }
+
+    /// Make the identifier of an entry. `homograph` is the homograph number
+    /// given in the source, which is used for `{n}` if there is one, and
+    /// `content` is what is hashed for `{hash}`. If the identifier is already
+    /// used, a number is added to it and the identifier that collided is also
+    /// returned
+    pub fn generate(&mut self, lemma : &str, pos : Option<&PartOfSpeech>, homograph : Option<u32>,
+                    content : &str) -> (String, Option<String>) {
+        self.count += 1;
+        let id = match self.template {
+            None => format!("unidentified_entry_{}", self.count),
+            Some(ref template) => {
+                let pos = pos.map(|p| format!("{:?}", p).to_lowercase()).unwrap_or_else(|| "x".to_string());
+                let lemma = sanitize(lemma);
+                let n = match homograph {
+                    Some(n) => n,
+                    None => {
+                        let n = self.homographs.entry(format!("{}\u{0}{}", lemma, pos)).or_insert(0);
+                        *n += 1;
+                        *n
+                    }
+                };
+                template.replace("{lemma}", &lemma)
+                    .replace("{pos}", &pos)
+                    .replace("{n}", &n.to_string())
+                    .replace("{hash}", &content_hash(content))
+            }
+        };
+        if self.used.insert(id.clone()) {
+            return (id, None);
+        }
+        let mut k = 2;
+        while !self.used.insert(format!("{}_{}", id, k)) {
+            k += 1;
+        }
+        (format!("{}_{}", id, k), Some(id))
+    }
+}
+
+/// Replace the characters that are not safe in a URL path segment
+fn sanitize(s : &str) -> String {
+    s.trim().chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' }).collect()
+}
+
+/// A 64-bit FNV-1a hash, which unlike the standard hasher is fixed
+pub fn content_hash(s : &str) -> String {
+    let mut hash : u64 = 0xcbf29ce484222325;
+    for b in s.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[test]
+fn test_generate() {
+    let mut config = Config::blank();
+    let mut ids = IdGenerator::new(&config, HashSet::new());
+    assert_eq!(ids.generate("cat", None, None, "").0, "unidentified_entry_1");
+    assert_eq!(ids.generate("cat", None, None, "").0, "unidentified_entry_2");
+    config.id_strategy = Some(IdStrategy::Lemma);
+    let mut used = HashSet::new();
+    used.insert("bank-noun-2".to_string());
+    let mut ids = IdGenerator::new(&config, used);
+    assert_eq!(ids.generate("bank", Some(&PartOfSpeech::NOUN), None, ""), ("bank-noun-1".to_string(), None));
+    assert_eq!(ids.generate("bank", Some(&PartOfSpeech::NOUN), None, ""), ("bank-noun-2_2".to_string(), Some("bank-noun-2".to_string())));
+    assert_eq!(ids.generate("bank", Some(&PartOfSpeech::NOUN), Some(5), ""), ("bank-noun-5".to_string(), None));
+    assert_eq!(ids.generate("bank", Some(&PartOfSpeech::NOUN), None, ""), ("bank-noun-3".to_string(), None));
+    assert_eq!(ids.generate("hand over fist", None, None, "").0, "hand_over_fist-x-1");
+    config.id_strategy = Some(IdStrategy::Hash);
+    let mut ids = IdGenerator::new(&config, HashSet::new());
+    assert_eq!(ids.generate("cat", None, None, "<entry/>").0, content_hash("<entry/>"));
+    assert_eq!(content_hash(""), "cbf29ce484222325");
+    config.id_strategy = None;
+    config.id_template = Some("{lemma}_{hash}".to_string());
+    let mut ids = IdGenerator::new(&config, HashSet::new());
+    assert!(ids.generate("cat", None, None, "x").0.starts_with("cat_"));
+}
@@ -0,0 +1,257 @@
+//! Normalization of IPA transcriptions and an approximate phonetic distance
+//! between them. Each segment (a base symbol with its diacritics) is
+//! described by its articulatory features, and transcriptions are compared
+//! by an edit distance where substituting one segment for another costs
+//! the proportion of features in which they differ.
+
+/// Normalize an IPA transcription: the enclosing `/…/` or `[…]`, syllable
+/// breaks, tie bars and optional brackets are removed, common ASCII
+/// substitutes are replaced by their IPA symbols and precomposed nasal
+/// vowels are decomposed
+pub fn normalize(ipa : &str) -> String {
+    let mut result = String::new();
+    for c in ipa.chars() {
+        match c {
+            '/' | '[' | ']' | '(' | ')' | '.' | '\u{361}' | '\u{35c}' | '‿' => {},
+            'g' => result.push('ɡ'),
+            ':' => result.push('ː'),
+            '\'' => result.push('ˈ'),
+            'ã' => result.push_str("a\u{303}"),
+            'ẽ' => result.push_str("e\u{303}"),
+            'ĩ' => result.push_str("i\u{303}"),
+            'õ' => result.push_str("o\u{303}"),
+            'ũ' => result.push_str("u\u{303}"),
+            'ỹ' => result.push_str("y\u{303}"),
+            c if c.is_whitespace() => {
+                if !result.is_empty() && !result.ends_with(' ') {
+                    result.push(' ');
+                }
+            },
+            c => result.push(c)
+        }
+    }
+    result.trim_end().to_string()
+}
+
+/// The number of segments in a normalized transcription
+pub fn length(ipa : &str) -> usize {
+    segments(ipa).len()
+}
+
+/// The phonetic distance between two normalized transcriptions. Inserting
+/// or deleting a segment costs 1 and substituting a segment costs between
+/// 0 and 1, so the distance is at least the difference in their lengths
+pub fn distance(ipa1 : &str, ipa2 : &str) -> f64 {
+    let s1 = segments(ipa1);
+    let s2 = segments(ipa2);
+    let mut row : Vec<f64> = (0..=s2.len()).map(|j| j as f64).collect();
+    for i in 1..=s1.len() {
+        let mut prev = row[0];
+        row[0] = i as f64;
+        for j in 1..=s2.len() {
+            let d = (prev + segment_distance(&s1[i-1], &s2[j-1]))
+                .min(row[j] + 1.0)
+                .min(row[j-1] + 1.0);
+            prev = row[j];
+            row[j] = d;
+        }
+    }
+    row[s2.len()]
+}
+
+/// A segment: a base symbol with the diacritics that modify it
+#[derive(Debug,PartialEq)]
+struct Segment {
+    base : char,
+    modifiers : Vec<char>
+}
+
+fn is_modifier(c : char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c) ||
+        "ːˑʰʷʲˠˤⁿˡ˞ʼ".contains(c)
+}
+
+fn is_prosodic(c : char) -> bool {
+    "ˈˌ|‖ ".contains(c)
+}
+
+fn segments(ipa : &str) -> Vec<Segment> {
+    let mut segments : Vec<Segment> = Vec::new();
+    for c in ipa.chars() {
+        if is_prosodic(c) {
+            continue;
+        }
+        match segments.last_mut() {
+            Some(s) if is_modifier(c) => s.modifiers.push(c),
+            _ => segments.push(Segment { base : c, modifiers : Vec::new() })
+        }
+    }
+    segments
+}
+
+#[derive(Debug,PartialEq)]
+enum Features {
+    /// Voicing, place (from bilabial to glottal), manner (from plosive to
+    /// approximant) and whether it is lateral
+    Consonant(bool, u8, u8, bool),
+    /// Height (from close to open), backness (from front to back) and rounding
+    Vowel(u8, u8, bool)
+}
+
+const PLOSIVE : u8 = 0;
+const FRICATIVE : u8 = 1;
+const NASAL : u8 = 2;
+const TRILL : u8 = 3;
+const APPROXIMANT : u8 = 4;
+
+fn features(c : char) -> Option<Features> {
+    use self::Features::*;
+    Some(match c {
+        'p' => Consonant(false, 0, PLOSIVE, false),
+        'b' => Consonant(true, 0, PLOSIVE, false),
+        't' => Consonant(false, 3, PLOSIVE, false),
+        'd' => Consonant(true, 3, PLOSIVE, false),
+        'ʈ' => Consonant(false, 5, PLOSIVE, false),
+        'ɖ' => Consonant(true, 5, PLOSIVE, false),
+        'c' => Consonant(false, 6, PLOSIVE, false),
+        'ɟ' => Consonant(true, 6, PLOSIVE, false),
+        'k' => Consonant(false, 7, PLOSIVE, false),
+        'ɡ' => Consonant(true, 7, PLOSIVE, false),
+        'q' => Consonant(false, 8, PLOSIVE, false),
+        'ɢ' => Consonant(true, 8, PLOSIVE, false),
+        'ʔ' => Consonant(false, 10, PLOSIVE, false),
+        'm' => Consonant(true, 0, NASAL, false),
+        'ɱ' => Consonant(true, 1, NASAL, false),
+        'n' => Consonant(true, 3, NASAL, false),
+        'ɳ' => Consonant(true, 5, NASAL, false),
+        'ɲ' => Consonant(true, 6, NASAL, false),
+        'ŋ' => Consonant(true, 7, NASAL, false),
+        'ɴ' => Consonant(true, 8, NASAL, false),
+        'ʙ' => Consonant(true, 0, TRILL, false),
+        'r' => Consonant(true, 3, TRILL, false),
+        'ʀ' => Consonant(true, 8, TRILL, false),
+        'ⱱ' => Consonant(true, 1, TRILL, false),
+        'ɾ' => Consonant(true, 3, TRILL, false),
+        'ɽ' => Consonant(true, 5, TRILL, false),
+        'ɸ' => Consonant(false, 0, FRICATIVE, false),
+        'β' => Consonant(true, 0, FRICATIVE, false),
+        'f' => Consonant(false, 1, FRICATIVE, false),
+        'v' => Consonant(true, 1, FRICATIVE, false),
+        'θ' => Consonant(false, 2, FRICATIVE, false),
+        'ð' => Consonant(true, 2, FRICATIVE, false),
+        's' => Consonant(false, 3, FRICATIVE, false),
+        'z' => Consonant(true, 3, FRICATIVE, false),
+        'ʃ' | 'ɕ' => Consonant(false, 4, FRICATIVE, false),
+        'ʒ' | 'ʑ' => Consonant(true, 4, FRICATIVE, false),
+        'ʂ' => Consonant(false, 5, FRICATIVE, false),
+        'ʐ' => Consonant(true, 5, FRICATIVE, false),
+        'ç' => Consonant(false, 6, FRICATIVE, false),
+        'ʝ' => Consonant(true, 6, FRICATIVE, false),
+        'x' | 'ʍ' => Consonant(false, 7, FRICATIVE, false),
+        'ɣ' => Consonant(true, 7, FRICATIVE, false),
+        'χ' => Consonant(false, 8, FRICATIVE, false),
+        'ʁ' => Consonant(true, 8, FRICATIVE, false),
+        'ħ' => Consonant(false, 9, FRICATIVE, false),
+        'ʕ' => Consonant(true, 9, FRICATIVE, false),
+        'h' => Consonant(false, 10, FRICATIVE, false),
+        'ɦ' => Consonant(true, 10, FRICATIVE, false),
+        'ɬ' => Consonant(false, 3, FRICATIVE, true),
+        'ɮ' => Consonant(true, 3, FRICATIVE, true),
+        'w' => Consonant(true, 0, APPROXIMANT, false),
+        'ʋ' => Consonant(true, 1, APPROXIMANT, false),
+        'ɹ' => Consonant(true, 3, APPROXIMANT, false),
+        'ɻ' => Consonant(true, 5, APPROXIMANT, false),
+        'j' | 'ɥ' => Consonant(true, 6, APPROXIMANT, false),
+        'ɰ' => Consonant(true, 7, APPROXIMANT, false),
+        'l' | 'ɫ' => Consonant(true, 3, APPROXIMANT, true),
+        'ɭ' => Consonant(true, 5, APPROXIMANT, true),
+        'ʎ' => Consonant(true, 6, APPROXIMANT, true),
+        'ʟ' => Consonant(true, 7, APPROXIMANT, true),
+        'i' => Vowel(0, 0, false),
+        'y' => Vowel(0, 0, true),
+        'ɨ' => Vowel(0, 1, false),
+        'ʉ' => Vowel(0, 1, true),
+        'ɯ' => Vowel(0, 2, false),
+        'u' => Vowel(0, 2, true),
+        'ɪ' => Vowel(1, 0, false),
+        'ʏ' => Vowel(1, 0, true),
+        'ʊ' => Vowel(1, 2, true),
+        'e' => Vowel(2, 0, false),
+        'ø' => Vowel(2, 0, true),
+        'ɘ' => Vowel(2, 1, false),
+        'ɵ' => Vowel(2, 1, true),
+        'ɤ' => Vowel(2, 2, false),
+        'o' => Vowel(2, 2, true),
+        'ə' | 'ɚ' => Vowel(3, 1, false),
+        'ɛ' => Vowel(4, 0, false),
+        'œ' => Vowel(4, 0, true),
+        'ɜ' | 'ɝ' => Vowel(4, 1, false),
+        'ɞ' => Vowel(4, 1, true),
+        'ʌ' => Vowel(4, 2, false),
+        'ɔ' => Vowel(4, 2, true),
+        'æ' => Vowel(5, 0, false),
+        'ɐ' => Vowel(5, 1, false),
+        'a' => Vowel(6, 0, false),
+        'ɶ' => Vowel(6, 0, true),
+        'ɑ' => Vowel(6, 2, false),
+        'ɒ' => Vowel(6, 2, true),
+        _ => return None
+    })
+}
+
+fn diff(x : u8, y : u8) -> f64 {
+    (x as f64 - y as f64).abs()
+}
+
+fn segment_distance(s1 : &Segment, s2 : &Segment) -> f64 {
+    let base = if s1.base == s2.base {
+        0.0
+    } else {
+        match (features(s1.base), features(s2.base)) {
+            (Some(Features::Consonant(v1, p1, m1, l1)), Some(Features::Consonant(v2, p2, m2, l2))) => {
+                (if v1 != v2 { 0.2 } else { 0.0 }) +
+                    0.35 * diff(p1, p2).min(4.0) / 4.0 +
+                    0.35 * diff(m1, m2) / 4.0 +
+                    (if l1 != l2 { 0.1 } else { 0.0 })
+            },
+            (Some(Features::Vowel(h1, b1, r1)), Some(Features::Vowel(h2, b2, r2))) => {
+                0.5 * diff(h1, h2) / 6.0 +
+                    0.3 * diff(b1, b2) / 2.0 +
+                    (if r1 != r2 { 0.2 } else { 0.0 })
+            },
+            _ => 1.0
+        }
+    };
+    let modifiers = s1.modifiers.iter().filter(|m| !s2.modifiers.contains(m)).count() +
+        s2.modifiers.iter().filter(|m| !s1.modifiers.contains(m)).count();
+    (base + 0.1 * modifiers as f64).min(1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("/ˈkæt/"), "ˈkæt");
+        assert_eq!(normalize("[ˈɡɜː.l]"), "ˈɡɜːl");
+        assert_eq!(normalize("'gə:l"), "ˈɡəːl");
+        assert_eq!(normalize(" t͡ʃɪp  ʃɒp "), "tʃɪp ʃɒp");
+        assert_eq!(normalize("bõ"), "bo\u{303}");
+        assert_eq!(length("ˈtʃʰɪːp"), 4);
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("ˈkæt", "kæt"), 0.0);
+        assert_eq!(distance("kæt", "kæts"), 1.0);
+        // Voicing alone is a small difference
+        assert!(distance("kæt", "ɡæt") < 0.25);
+        // Closer vowels and consonants are more similar
+        assert!(distance("bɪt", "bit") < distance("bɪt", "bɑt"));
+        assert!(distance("pæt", "bæt") < distance("pæt", "næt"));
+        assert!(distance("kæt", "kæt") < distance("kæt", "kʰæt"));
+        assert_eq!(distance("ka", "ak"), 2.0);
+        assert_eq!(distance("", "kæt"), 3.0);
+    }
+}
@@ -10,9 +10,7 @@ extern crate serde_json;
 extern crate gotham_derive;
 extern crate clap;
 extern crate xml;
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
+extern crate regex;
 #[macro_use]
 extern crate quick_error;
 
@@ -23,6 +21,10 @@ mod rdf;
 mod sqlite;
 mod ontolex;
 mod config;
+mod ipa;
+mod ud;
+mod ids;
+mod media;
 
 use gotham::state::State;
 use gotham::router::Router;
@@ -41,12 +43,15 @@ use hyper::Body;
 use clap::{App, Arg, SubCommand, ArgMatches};
 
 use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use crate::model::{EDSState, Dictionary, JsonEntry, PartOfSpeech, EntryContent, BackendError, Entry};
+use crate::model::{EDSState, Dictionary, JsonEntry, PartOfSpeech, EntryContent, BackendError, Entry, Etymology, PronMatch, TranslationMatch, FrequencyFilter, LookupQuery, Paradigm, FormAnalysis, TagUsage};
+use crate::tei::{TeiSense, TeiRelations};
 use crate::sqlite::RusqliteState;
 use crate::config::Config;
+use crate::media::Media;
 
 fn router(model : BackendImpl) -> Router {
     let middleware = StateMiddleware::new(model);
@@ -59,6 +64,12 @@ fn router(model : BackendImpl) -> Router {
         route.get("/about/:dictionary")
             .with_path_extractor::<AboutParams>()
             .to(rest::about);
+        route.get("/tagset/:dictionary")
+            .with_path_extractor::<AboutParams>()
+            .to(rest::tagset);
+        route.get("/tei-header/:dictionary")
+            .with_path_extractor::<AboutParams>()
+            .to(rest::about_tei);
         route.get("/list/:dictionary")
             .with_path_extractor::<ListPathParams>()
             .with_query_string_extractor::<ListQueryParams>()
@@ -67,6 +78,26 @@ fn router(model : BackendImpl) -> Router {
             .with_path_extractor::<LookupPathParams>()
             .with_query_string_extractor::<LookupQueryParams>()
             .to(rest::lookup);
+        route.get("/pron/:dictionary/:ipa")
+            .with_path_extractor::<PronPathParams>()
+            .with_query_string_extractor::<PronQueryParams>()
+            .to(rest::lookup_pron);
+        route.get("/definition/:dictionary/:word")
+            .with_path_extractor::<DefinitionPathParams>()
+            .with_query_string_extractor::<ListQueryParams>()
+            .to(rest::lookup_definition);
+        route.get("/translate/:dictionary/:lang/:word")
+            .with_path_extractor::<TranslatePathParams>()
+            .with_query_string_extractor::<ListQueryParams>()
+            .to(rest::lookup_translation);
+        route.get("/mwe/:dictionary/:lemma")
+            .with_path_extractor::<MwePathParams>()
+            .with_query_string_extractor::<ListQueryParams>()
+            .to(rest::lookup_mwe);
+        route.get("/analysis/:dictionary/:form")
+            .with_path_extractor::<AnalysisPathParams>()
+            .with_query_string_extractor::<ListQueryParams>()
+            .to(rest::analyse);
         route.get("/json/:dictionary/:id")
             .with_path_extractor::<EntryPathParams>()
             .to(rest::entry_json);
@@ -76,6 +107,24 @@ fn router(model : BackendImpl) -> Router {
         route.get("/tei/:dictionary/:id")
             .with_path_extractor::<EntryPathParams>()
             .to(rest::entry_tei);
+        route.get("/senses/:dictionary/:id")
+            .with_path_extractor::<EntryPathParams>()
+            .to(rest::entry_senses);
+        route.get("/sense/:dictionary/:id")
+            .with_path_extractor::<EntryPathParams>()
+            .to(rest::sense);
+        route.get("/related/:dictionary/:id")
+            .with_path_extractor::<EntryPathParams>()
+            .to(rest::entry_relations);
+        route.get("/etymology/:dictionary/:id")
+            .with_path_extractor::<EntryPathParams>()
+            .to(rest::entry_etymology);
+        route.get("/paradigm/:dictionary/:id")
+            .with_path_extractor::<EntryPathParams>()
+            .to(rest::paradigm);
+        route.get("/media/:dictionary/:id")
+            .with_path_extractor::<EntryPathParams>()
+            .to(rest::media);
         route.get("/img/logo.jpg")
             .to(logo);
     })
@@ -90,9 +139,26 @@ struct ListPathParams {
     dictionary : String
 }
 #[derive(Deserialize, StateData, StaticResponseExtender)]
+#[serde(rename_all = "camelCase")]
 struct ListQueryParams {
     offset : Option<usize>,
-    limit : Option<usize>
+    limit : Option<usize>,
+    min_frequency : Option<u64>,
+    sort : Option<String>
+}
+
+impl ListQueryParams {
+    fn frequency(&self) -> FrequencyFilter {
+        frequency_filter(self.min_frequency, &self.sort)
+    }
+}
+
+/// Filter by a minimum frequency and sort by frequency if `sort=frequency`
+fn frequency_filter(min_frequency : Option<u64>, sort : &Option<String>) -> FrequencyFilter {
+    FrequencyFilter {
+        min : min_frequency,
+        sort : sort.as_ref().map(|s| s == "frequency").unwrap_or(false)
+    }
 }
 #[derive(Deserialize, StateData, StaticResponseExtender)]
 struct LookupPathParams {
@@ -105,7 +171,85 @@ struct LookupQueryParams {
     part_of_speech : Option<PartOfSpeech>,
     limit : Option<usize>,
     offset : Option<usize>,
-    inflected : Option<bool>
+    inflected : Option<bool>,
+    case : Option<String>,
+    number : Option<String>,
+    gender : Option<String>,
+    person : Option<String>,
+    tense : Option<String>,
+    mood : Option<String>,
+    /// UD features, e.g., `Case=Gen|Number=Plur`
+    features : Option<String>,
+    min_frequency : Option<u64>,
+    sort : Option<String>,
+    /// Only the entry with this homograph number
+    homograph : Option<u32>
+}
+
+impl LookupQueryParams {
+    /// The grammatical features the matching form must have, as UD features
+    fn features(&self) -> Result<Vec<(String, String)>, String> {
+        let mut features = match self.features {
+            Some(ref feats) => ud::parse(feats)?,
+            None => Vec::new()
+        };
+        for (name, value) in [("case", &self.case), ("number", &self.number),
+            ("gender", &self.gender), ("person", &self.person),
+            ("tense", &self.tense), ("mood", &self.mood)] {
+            if let Some(value) = value {
+                features.push(ud::feature(name, value));
+            }
+        }
+        Ok(features)
+    }
+
+    fn frequency(&self) -> FrequencyFilter {
+        frequency_filter(self.min_frequency, &self.sort)
+    }
+
+    fn query(&self) -> Result<LookupQuery, String> {
+        Ok(LookupQuery {
+            part_of_speech : self.part_of_speech.clone(),
+            inflected : self.inflected.unwrap_or(false),
+            features : self.features()?,
+            frequency : self.frequency(),
+            homograph : self.homograph,
+            offset : self.offset,
+            limit : self.limit
+        })
+    }
+}
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct PronPathParams {
+    dictionary : String,
+    ipa : String
+}
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct PronQueryParams {
+    distance : Option<f64>,
+    limit : Option<usize>,
+    offset : Option<usize>
+}
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct DefinitionPathParams {
+    dictionary : String,
+    word : String
+}
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct TranslatePathParams {
+    dictionary : String,
+    lang : String,
+    word : String
+}
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct MwePathParams {
+    dictionary : String,
+    lemma : String
+}
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct AnalysisPathParams {
+    dictionary : String,
+    form : String
 }
 #[derive(Deserialize, StateData, StaticResponseExtender)]
 struct EntryPathParams {
@@ -136,12 +280,13 @@ fn main() {
         .subcommand(SubCommand::with_name("load")
             .about("Load data into the database")
             .arg(Arg::with_name("data")
-                .help("The data to host")
+                .help("The data to host (TEI may be given as several files, a directory or a glob)")
                 .required(true)
+                .multiple(true)
                 .index(1))
             .arg(Arg::with_name("format")
                 .help("The format of the input")
-                .value_name("json|ttl|tei")
+                .value_name("json|jsonld|ttl|rdfxml|nt|tei")
                 .short("f")
                 .long("format")
                 .takes_value(true))
@@ -168,6 +313,32 @@ fn main() {
             .arg(Arg::with_name("db_path")
                 .help("The path to use for the database (Default: eds.db)")
                 .long("db-path")
+                .takes_value(true))
+            .arg(Arg::with_name("strict")
+                .help("Do not load the data if it has any errors")
+                .long("strict"))
+            .arg(Arg::with_name("media_root")
+                .help("A directory that media files may be read from besides that of the data")
+                .long("media-root")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("validate")
+            .about("Check a TEI file and report any problems")
+            .arg(Arg::with_name("data")
+                .help("The TEI file(s) to check")
+                .required(true)
+                .multiple(true)
+                .index(1))
+            .arg(Arg::with_name("config")
+                .help("Configuration to help with mapping")
+                .short("c")
+                .long("config")
+                .takes_value(true))
+            .arg(Arg::with_name("json")
+                .help("Output the report as Json")
+                .long("json"))
+            .arg(Arg::with_name("media_root")
+                .help("A directory that media files may be read from besides that of the data")
+                .long("media-root")
                 .takes_value(true)))
         .subcommand(SubCommand::with_name("start")
             .about("Start the server")
@@ -185,7 +356,7 @@ fn main() {
                 .takes_value(true))
             .arg(Arg::with_name("format")
                 .help("The format of the input")
-                .value_name("json|ttl|tei")
+                .value_name("json|jsonld|ttl|rdfxml|nt|tei")
                 .short("f")
                 .long("format")
                 .takes_value(true))
@@ -215,6 +386,10 @@ fn main() {
             .arg(Arg::with_name("db_path")
                 .help("The path to use for the database (Default: eds.db)")
                 .long("db-path")
+                .takes_value(true))
+            .arg(Arg::with_name("media_root")
+                .help("A directory that media files may be read from besides that of the data")
+                .long("media-root")
                 .takes_value(true)))
         .subcommand(SubCommand::with_name("delete")
             .about("Delete a dictionary from the service")
@@ -229,6 +404,8 @@ fn main() {
     let matches = app.clone().get_matches();
     if let Some(matches) = matches.subcommand_matches("load") {
         load_data(matches, &mut app);
+    } else if let Some(matches) = matches.subcommand_matches("validate") {
+        validate(matches, &mut app);
     } else if let Some(matches) = matches.subcommand_matches("start") {
         let state = if matches.value_of("data").is_some() {
             load_data(matches, &mut app)
@@ -265,16 +442,120 @@ fn show_help(msg : &str, app : &mut App) -> ! {
     std::process::exit(-1)
 }
 
+fn read_config(matches : &ArgMatches) -> Config {
+    let mut config = matches.value_of("config").and_then(|fname|  {
+        serde_json::from_reader(File::open(fname)
+            .unwrap_or_else(|e| fail(&format!("Could not open config file: {:?}", e)))
+            ).unwrap_or_else(|e| fail(&format!("Could not parse config file: {:?}", e)))
+    }).unwrap_or_else(Config::blank);
+    if let Some(media_root) = matches.value_of("media_root") {
+        config.media_root = Some(media_root.to_string());
+    }
+    config
+}
+
+/// The files given as data, expanding directories and globs
+fn data_files(matches : &ArgMatches, app : &mut App) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for data in matches.values_of("data").unwrap_or_else(|| show_help("The data paramter is required", app)) {
+        let path = Path::new(data);
+        if path.is_dir() {
+            let mut dir_files : Vec<PathBuf> = list_dir(path).into_iter()
+                .filter(|f| f.extension().map(|e| e == "xml" || e == "tei").unwrap_or(false))
+                .collect();
+            dir_files.sort();
+            files.extend(dir_files);
+        } else if data.contains('*') || data.contains('?') {
+            files.extend(glob_files(path).unwrap_or_else(|e| fail(&e)));
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    if files.is_empty() {
+        fail("No data files found");
+    }
+    files
+}
+
+/// The files matching a path whose file name has wildcards. Wildcards are
+/// only supported in the file name, not in the directories
+fn glob_files(path : &Path) -> Result<Vec<PathBuf>, String> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if dir.to_string_lossy().contains(['*', '?']) {
+        return Err(format!("Wildcards may only be used in the file name, not in the directory: {}", path.display()));
+    }
+    let pattern : Vec<char> = path.file_name().and_then(|n| n.to_str()).unwrap_or("").chars().collect();
+    let mut files : Vec<PathBuf> = list_dir(dir).into_iter()
+        .filter(|f| f.file_name().and_then(|n| n.to_str()).map(|n| 
+                glob_match(&pattern, &n.chars().collect::<Vec<char>>())).unwrap_or(false))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn list_dir(dir : &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .unwrap_or_else(|e| fail(&format!("Could not read directory {}: {}", dir.display(), e)))
+        .filter_map(|f| f.ok().map(|f| f.path()))
+        .filter(|f| f.is_file())
+        .collect()
+}
+
+/// Match a file name against a pattern with `*` and `?` wildcards
+fn glob_match(pattern : &[char], name : &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| glob_match(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && glob_match(&pattern[1..], &name[1..]),
+        Some(c) => name.first() == Some(c) && glob_match(&pattern[1..], &name[1..])
+    }
+}
+
+/// Parse TEI data, which may be split over several files
+fn parse_tei<F>(files : &[PathBuf], options : model::ParseOptions,
+    report : &mut tei::ValidationReport, foo : F) -> Option<BackendImpl> 
+    where F : FnOnce(model::Release, HashMap<String,Dictionary>, HashMap<String, Vec<EntryContent>>) -> BackendImpl {
+    if files.len() == 1 {
+        tei::parse_with_report(File::open(&files[0])
+            .unwrap_or_else(|e| fail(&format!("Could not open data file: {}", e))),
+            files[0].parent(), options, report, foo)
+    } else {
+        tei::parse_files(files, options, report, foo)
+    }
+}
+
+fn validate(matches : &ArgMatches, app : &mut App) {
+    let files = data_files(matches, app);
+    let config = read_config(matches);
+    let mut report = tei::ValidationReport::default();
+    let options = model::ParseOptions { id : "validate", release : model::Release::PUBLIC, genre : Vec::new(),
+        config : &config, strict : true };
+    parse_tei(&files, options, &mut report, |r,d,e| {
+        BackendImpl::Mem(EDSState::new(r,d,e))
+    });
+    if matches.is_present("json") {
+        println!("{}", serde_json::to_string_pretty(&report)
+            .unwrap_or_else(|e| fail(&format!("Could not write report: {:?}", e))));
+    } else {
+        print!("{}", report);
+        println!("{} error(s), {} warning(s)", report.errors(), report.warnings());
+    }
+    if report.errors() > 0 {
+        std::process::exit(1);
+    }
+}
+
 fn load_data(matches : &ArgMatches, app : &mut App) -> BackendImpl {
     let format = matches.value_of("format").unwrap_or("");
-    let data : &str = matches.value_of("data").unwrap_or_else(|| show_help("The data paramter is required", app));
+    let files = data_files(matches, app);
+    let data_path = files[0].to_string_lossy().to_string();
+    let data : &str = &data_path;
+    if files.len() > 1 && !(format == "tei" || data.ends_with(".tei") || data.ends_with(".xml")) {
+        fail("Only TEI data can be loaded from several files");
+    }
     let no_sql = false;//matches.value_of("no_sql").is_some();
     let db_path = matches.value_of("db_path").unwrap_or("eds.db");
-    let config = matches.value_of("config").and_then(|fname|  {
-        serde_json::from_reader(File::open(fname)
-            .unwrap_or_else(|e| fail(&format!("Could not open config file: {:?}", e)))
-            ).unwrap_or_else(|e| fail(&format!("Could not parse config file: {:?}", e)))
-    }).unwrap_or_else(|| Config::blank());
+    let config = read_config(matches);
     let release = matches.value_of("release").and_then(|x| model::Release::from_str(x).ok()).unwrap_or_else(|| {
         if let Some(release) = config.default_release.clone() {
             release
@@ -284,17 +565,55 @@ fn load_data(matches : &ArgMatches, app : &mut App) -> BackendImpl {
         }
     });
      
-    if format == "json" || data.ends_with(".json") {
-        let dictionaries : HashMap<String, DictJson> = serde_json::from_reader(
+    if format == "json" || format == "jsonld" || data.ends_with(".json") || data.ends_with(".jsonld") {
+        let doc : serde_json::Value = serde_json::from_reader(
             File::open(data).
             unwrap_or_else(|e| fail(&format!("Could not open data file: {:?}", e)))).
             unwrap_or_else(|e| fail(&format!("Could not read dictionary file: {:?}", e)));
+        if format == "jsonld" || data.ends_with(".jsonld") || rdf::jsonld::is_json_ld(&doc) {
+            let mut genres = Vec::new();
+            if let Some(gs) = matches.values_of("genre") {
+                for g in gs {
+                    genres.push(model::Genre::from_str(g).
+                        unwrap_or_else(|e| show_help(&e, app)));
+                }
+            };
+            let id = matches.value_of("id").map(|x| x.to_owned()).unwrap_or_else(|| { 
+                if let Some(id) = config.default_id.clone() {
+                    id
+                } else {
+                    "default".to_owned()
+                }
+            });
+            let mut report = tei::ValidationReport::for_file(data);
+            let options = model::ParseOptions { id : &id, release, genre : genres, config : &config,
+                strict : matches.is_present("strict") };
+            let mut media_report = tei::ValidationReport::for_file(data);
+            let result = ontolex::parse_json_ld(&doc, Path::new(data).parent(), options, &mut report, |r,d,e| {
+                    let e = &mut e.map(|x| x.map(|(d, c)| (d, resolve_media(c, data, &config, &mut media_report))));
+                    if no_sql {
+                        Ok(BackendImpl::Mem(EDSState::new(r,d,model::collect_entries(e)?)))
+                    } else {
+                        let db = RusqliteState::new(db_path);
+                        db.load_stream(r,d,e).unwrap_or_else(|e| fail(&format!("Could not load database: {}", e)));
+                        Ok(BackendImpl::DB(db))
+                    }
+                });
+            report.diagnostics.append(&mut media_report.diagnostics);
+            eprint!("{}", report);
+            return result.unwrap_or_else(|e| fail(&format!("Could not read JSON-LD file: {}", e)));
+        }
+        let dictionaries : HashMap<String, DictJson> = serde_json::from_value(doc).
+            unwrap_or_else(|e| fail(&format!("Could not read dictionary file: {:?}", e)));
         let mut dict_map = HashMap::new();
         let mut entry_map = HashMap::new();
+        let mut report = tei::ValidationReport::for_file(data);
         for (id, dj) in dictionaries {
             dict_map.insert(id.clone(), dj.meta);
-            entry_map.insert(id, dj.entries.into_iter().map(|x| EntryContent::Json(x)).collect());
+            entry_map.insert(id, dj.entries.into_iter()
+                .map(|x| resolve_media(EntryContent::Json(x), data, &config, &mut report)).collect());
         }
+        eprint!("{}", report);
         if no_sql {
             BackendImpl::Mem(EDSState::new(release, dict_map, entry_map))
         } else {
@@ -318,9 +637,10 @@ fn load_data(matches : &ArgMatches, app : &mut App) -> BackendImpl {
             }
         });
 
-        tei::parse(File::open(data)
-            .unwrap_or_else(|e| fail(&format!("Could not open data file: {}", e.to_string()))), 
-                &id, release, genres, &config, |r,d,e| {
+        let mut report = tei::ValidationReport::default();
+        let options = model::ParseOptions { id : &id, release, genre : genres, config : &config,
+            strict : matches.is_present("strict") };
+        let result = parse_tei(&files, options, &mut report, |r,d,e| {
                     if no_sql {
                         BackendImpl::Mem(EDSState::new(r,d,e))
                     } else {
@@ -328,8 +648,11 @@ fn load_data(matches : &ArgMatches, app : &mut App) -> BackendImpl {
                         db.load(r,d,e).unwrap_or_else(|e| fail(&format!("Could not load database: {}", e.to_string())));
                         BackendImpl::DB(db)
                     }
-                })
-    } else if format == "ttl" || data.ends_with(".rdf") || data.ends_with(".ttl") || data.ends_with(".nt") {
+                });
+        eprint!("{}", report);
+        result.unwrap_or_else(|| fail(&format!("Not loading the TEI file as it has {} error(s)", report.errors())))
+    } else if format == "ttl" || format == "rdfxml" || format == "nt" || data.ends_with(".rdf") || data.ends_with(".owl") ||
+        data.ends_with(".ttl") || data.ends_with(".nt") || data.ends_with(".nq") {
         let mut genres = Vec::new();
         if let Some(gs) = matches.values_of("genre") {
             for g in gs {
@@ -346,23 +669,55 @@ fn load_data(matches : &ArgMatches, app : &mut App) -> BackendImpl {
         });
 
 
-        ontolex::parse(File::open(data)
-            .unwrap_or_else(|e| fail(&format!("Could not open data file: {}", e.to_string()))), 
-                release, genres, &config, &id, |r,d,e| {
-                    if no_sql {
-                        Ok(BackendImpl::Mem(EDSState::new(r,d,e)))
-                    } else {
-                        let db = RusqliteState::new(db_path);
-                        db.load(r,d,e).unwrap_or_else(|e| fail(&format!("Could not load database: {}", e.to_string())));
-                        Ok(BackendImpl::DB(db))
-                    }
-                }).unwrap_or_else(|e| fail(&format!("Could not read OntoLex file: {}", e.to_string())))
+        let input = File::open(data)
+            .unwrap_or_else(|e| fail(&format!("Could not open data file: {}", e)));
+        let mut media_report = tei::ValidationReport::for_file(data);
+        let store = |r,d,e : &mut model::EntryStream| {
+            let e = &mut e.map(|x| x.map(|(d, c)| (d, resolve_media(c, data, &config, &mut media_report))));
+            if no_sql {
+                Ok(BackendImpl::Mem(EDSState::new(r,d,model::collect_entries(e)?)))
+            } else {
+                let db = RusqliteState::new(db_path);
+                db.load_stream(r,d,e).unwrap_or_else(|e| fail(&format!("Could not load database: {}", e)));
+                Ok(BackendImpl::DB(db))
+            }
+        };
+        let options = model::ParseOptions { id : &id, release, genre : genres, config : &config,
+            strict : matches.is_present("strict") };
+        let mut report = tei::ValidationReport::for_file(data);
+        let result = if format == "rdfxml" || (format.is_empty() && (data.ends_with(".rdf") || data.ends_with(".owl"))) {
+            ontolex::parse_rdf_xml(input, options, &mut report, store)
+        } else if format == "nt" || (format.is_empty() && (data.ends_with(".nt") || data.ends_with(".nq"))) {
+            ontolex::parse_ntriples(input, options, &mut report, store)
+        } else {
+            ontolex::parse(input, options, &mut report, store)
+        };
+        report.diagnostics.append(&mut media_report.diagnostics);
+        eprint!("{}", report);
+        result.unwrap_or_else(|e| fail(&format!("Could not read OntoLex file: {}", e)))
  
     } else {
         show_help(&format!("Unsupported format: {}", format),app);
     }
 }
 
+/// Find the local media files of an entry relative to the data file. Files
+/// that are missing or outside the directory of the data file and the media
+/// root are reported and not stored
+fn resolve_media(mut entry : EntryContent, data : &str, config : &Config,
+    report : &mut tei::ValidationReport) -> EntryContent {
+    let media_root = config.media_root.as_ref().map(Path::new);
+    let mut rejected = Vec::new();
+    entry.media_mut().retain_mut(|m| match m.resolve_file(Path::new(data).parent(), media_root) {
+        Ok(()) => true,
+        Err(msg) => { rejected.push(msg); false }
+    });
+    for msg in rejected {
+        report.add(tei::Severity::Warning, Some(entry.id()), xml::common::TextPosition::new(), "media", msg);
+    }
+    entry
+}
+
 static mut ADDR : (&'static str, u16) = ("0.0.0.0",0);
 
 fn start_server(state : BackendImpl) {
@@ -402,19 +757,18 @@ impl model::Backend for BackendImpl {
     }
     /// List all entries in a dictrionary
     fn list(&self, dictionary : &str, offset : Option<usize>, 
-            limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
+            limit : Option<usize>, frequency : &FrequencyFilter) -> Result<Vec<Entry>,BackendError> {
         match self { 
-            BackendImpl::Mem(s) => s.list(dictionary, offset, limit),
-            BackendImpl::DB(s) => s.list(dictionary, offset, limit)
+            BackendImpl::Mem(s) => s.list(dictionary, offset, limit, frequency),
+            BackendImpl::DB(s) => s.list(dictionary, offset, limit, frequency)
         }
     }
     /// Search the dictionary by headword
     fn lookup(&self, dictionary : &str, headword : &str,
-              offset : Option<usize>, limit : Option<usize>,
-              part_of_speech : Option<PartOfSpeech>, inflected : bool) -> Result<Vec<Entry>,BackendError> {
+              query : &LookupQuery) -> Result<Vec<Entry>,BackendError> {
         match self { 
-            BackendImpl::Mem(s) => s.lookup(dictionary, headword, offset, limit, part_of_speech, inflected),
-            BackendImpl::DB(s) => s.lookup(dictionary, headword, offset, limit, part_of_speech, inflected),
+            BackendImpl::Mem(s) => s.lookup(dictionary, headword, query),
+            BackendImpl::DB(s) => s.lookup(dictionary, headword, query),
         }
     }
     /// Get the content as Json
@@ -438,4 +792,103 @@ impl model::Backend for BackendImpl {
             BackendImpl::DB(s) => s.entry_tei(dictionary, id)
         }
     }
+    /// Get the structured senses of a TEI entry
+    fn entry_senses(&self, dictionary : &str, id : &str) -> Result<Vec<TeiSense>,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.entry_senses(dictionary, id),
+            BackendImpl::DB(s) => s.entry_senses(dictionary, id)
+        }
+    }
+    /// Get a sense of a TEI entry by its identifier
+    fn sense(&self, dictionary : &str, sense_id : &str) -> Result<TeiSense,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.sense(dictionary, sense_id),
+            BackendImpl::DB(s) => s.sense(dictionary, sense_id)
+        }
+    }
+    /// Get the entries a TEI entry is nested in or contains
+    fn entry_relations(&self, dictionary : &str, id : &str) -> Result<TeiRelations,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.entry_relations(dictionary, id),
+            BackendImpl::DB(s) => s.entry_relations(dictionary, id)
+        }
+    }
+    /// Get the etymology of an entry
+    fn entry_etymology(&self, dictionary : &str, id : &str) -> Result<Etymology,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.entry_etymology(dictionary, id),
+            BackendImpl::DB(s) => s.entry_etymology(dictionary, id)
+        }
+    }
+    /// Get the paradigm of an entry
+    fn paradigm(&self, dictionary : &str, id : &str) -> Result<Paradigm,BackendError> {
+        match self {
+            BackendImpl::Mem(s) => s.paradigm(dictionary, id),
+            BackendImpl::DB(s) => s.paradigm(dictionary, id)
+        }
+    }
+    /// Search the dictionary by pronunciation
+    fn lookup_pron(&self, dictionary : &str, ipa : &str, distance : f64,
+                   offset : Option<usize>, limit : Option<usize>) -> Result<Vec<PronMatch>,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.lookup_pron(dictionary, ipa, distance, offset, limit),
+            BackendImpl::DB(s) => s.lookup_pron(dictionary, ipa, distance, offset, limit)
+        }
+    }
+    /// Get a media file by its identifier
+    fn media(&self, dictionary : &str, id : &str) -> Result<Media,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.media(dictionary, id),
+            BackendImpl::DB(s) => s.media(dictionary, id)
+        }
+    }
+    fn lookup_definition(&self, dictionary : &str, word : &str,
+                         offset : Option<usize>, limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.lookup_definition(dictionary, word, offset, limit),
+            BackendImpl::DB(s) => s.lookup_definition(dictionary, word, offset, limit)
+        }
+    }
+    fn lookup_translation(&self, dictionary : &str, language : &str, word : &str,
+                          offset : Option<usize>, limit : Option<usize>) -> Result<Vec<TranslationMatch>,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.lookup_translation(dictionary, language, word, offset, limit),
+            BackendImpl::DB(s) => s.lookup_translation(dictionary, language, word, offset, limit)
+        }
+    }
+    fn lookup_mwe(&self, dictionary : &str, lemma : &str,
+                  offset : Option<usize>, limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.lookup_mwe(dictionary, lemma, offset, limit),
+            BackendImpl::DB(s) => s.lookup_mwe(dictionary, lemma, offset, limit)
+        }
+    }
+    /// Find the entries that have a form, with its features
+    fn analyse(&self, dictionary : &str, form : &str,
+               offset : Option<usize>, limit : Option<usize>) -> Result<Vec<FormAnalysis>,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.analyse(dictionary, form, offset, limit),
+            BackendImpl::DB(s) => s.analyse(dictionary, form, offset, limit)
+        }
+    }
+    /// The part-of-speech tags used in the dictionary and how they were mapped
+    fn tagset(&self, dictionary : &str) -> Result<Vec<TagUsage>,BackendError> {
+        match self { 
+            BackendImpl::Mem(s) => s.tagset(dictionary),
+            BackendImpl::DB(s) => s.tagset(dictionary)
+        }
+    }
+}
+
+#[test]
+fn test_glob_files() {
+    std::fs::create_dir_all("test-tmp-glob/a").unwrap();
+    std::fs::write("test-tmp-glob/a/one.xml", "").unwrap();
+    std::fs::write("test-tmp-glob/a/two.xml", "").unwrap();
+    std::fs::write("test-tmp-glob/a/three.tei", "").unwrap();
+    let files = glob_files(Path::new("test-tmp-glob/a/*.xml"));
+    let nested = glob_files(Path::new("test-tmp-glob/*/one.xml"));
+    std::fs::remove_dir_all("test-tmp-glob").unwrap();
+    assert_eq!(files.unwrap(), vec![PathBuf::from("test-tmp-glob/a/one.xml"), PathBuf::from("test-tmp-glob/a/two.xml")]);
+    assert_eq!(nested.unwrap_err(), "Wildcards may only be used in the file name, not in the directory: test-tmp-glob/*/one.xml");
 }
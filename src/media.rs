@@ -0,0 +1,268 @@
+//! Audio, images and other media referred to by entries. Local files are
+//! given an identifier when they are loaded, copied into a media store next
+//! to the database and served from `/media/:dictionary/:id`.
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A media file referred to by an entry
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Media {
+    /// The identifier in the media store, if it is a local file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub id : Option<String>,
+    /// The URL as given in the data
+    pub url : String,
+    #[serde(default)]
+    pub mime_type : String,
+    /// The local file, while loading
+    #[serde(skip)]
+    pub path : Option<PathBuf>
+}
+
+impl Media {
+    pub fn new(url : &str, mime_type : Option<String>) -> Media {
+        Media {
+            id : None,
+            url : url.to_string(),
+            mime_type : mime_type.unwrap_or_else(|| guess_mime_type(url).to_string()),
+            path : None
+        }
+    }
+
+    /// Find the local file of a URL that is not remote, relative to `base`.
+    /// The file must be within `base` or `media_root`, so that the data cannot
+    /// have other files on the system copied into the public media store
+    pub fn resolve(&mut self, base : Option<&Path>, media_root : Option<&Path>) -> Result<(), String> {
+        if self.mime_type.is_empty() {
+            self.mime_type = guess_mime_type(&self.url).to_string();
+        }
+        if self.path.is_some() {
+            return Ok(());
+        }
+        let file = if self.url.starts_with("file://") {
+            &self.url[7..]
+        } else if self.url.starts_with("file:") {
+            &self.url[5..]
+        } else if self.url.contains("://") || self.url.starts_with("data:") {
+            return Ok(());
+        } else {
+            &self.url
+        };
+        let path = match base {
+            Some(base) => base.join(file),
+            None => PathBuf::from(file)
+        };
+        // A file that does not exist is kept, so that it is reported as missing
+        if let Ok(source) = path.canonicalize() {
+            let base = base.filter(|b| !b.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+            let within = |dir : &Path| dir.canonicalize().map(|d| source.starts_with(d)).unwrap_or(false);
+            if !within(base) && !media_root.map(within).unwrap_or(false) {
+                return Err(format!("Media file is not in the data directory or the media root: {}", self.url));
+            }
+        }
+        self.path = Some(path);
+        Ok(())
+    }
+
+    /// Find the local file as `resolve` does and check that it exists
+    pub fn resolve_file(&mut self, base : Option<&Path>, media_root : Option<&Path>) -> Result<(), String> {
+        self.resolve(base, media_root)?;
+        match self.path {
+            Some(ref path) if !path.is_file() => Err(format!("Media file not found: {}", self.url)),
+            _ => Ok(())
+        }
+    }
+}
+
+/// The MIME type of a file by its extension
+pub fn guess_mime_type(name : &str) -> &'static str {
+    let ext = name.rsplit('.').next().unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "m4a" => "audio/mp4",
+        "flac" => "audio/flac",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream"
+    }
+}
+
+/// The directory where the media of a database is stored
+pub fn store_dir(db_path : &str) -> PathBuf {
+    Path::new(db_path).with_extension("media")
+}
+
+/// A name that can safely be used as a file name and in a URL
+pub fn safe_name(name : &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
+        .collect::<String>().trim_start_matches('.').to_string()
+}
+
+/// Gives each local media file of a dictionary an identifier, by default
+/// its file name, and copies it into the store if there is one
+pub struct MediaImporter {
+    store : Option<PathBuf>,
+    ids : HashMap<(String, PathBuf), String>,
+    used : HashSet<(String, String)>
+}
+
+impl MediaImporter {
+    pub fn new(store : Option<PathBuf>) -> MediaImporter {
+        MediaImporter {
+            store,
+            ids : HashMap::new(),
+            used : HashSet::new()
+        }
+    }
+
+    pub fn import(&mut self, dictionary : &str, media : &mut Media) -> io::Result<()> {
+        let source = match media.path {
+            Some(ref path) => path.canonicalize()?,
+            None => return Ok(())
+        };
+        if !source.is_file() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file"));
+        }
+        let key = (dictionary.to_string(), source.clone());
+        if let Some(id) = self.ids.get(&key) {
+            media.id = Some(id.clone());
+            return Ok(());
+        }
+        let name = safe_name(&source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default());
+        let (stem, ext) = match name.rfind('.') {
+            Some(i) if i > 0 => (&name[..i], &name[i..]),
+            _ => (name.as_str(), "")
+        };
+        let dir = self.store.as_ref().map(|s| s.join(safe_name(dictionary)));
+        let mut id = name.clone();
+        let mut n = 1;
+        loop {
+            if !self.used.contains(&(dictionary.to_string(), id.clone())) {
+                // A file already in the store is reused if it is the same
+                match dir.as_ref().map(|d| d.join(&id)) {
+                    Some(ref dest) if dest.exists() => {
+                        if fs::read(dest)? == fs::read(&source)? {
+                            break;
+                        }
+                    },
+                    Some(ref dest) => {
+                        fs::create_dir_all(dest.parent().unwrap())?;
+                        fs::copy(&source, dest)?;
+                        break;
+                    },
+                    None => break
+                }
+            }
+            n += 1;
+            id = format!("{}-{}{}", stem, n, ext);
+        }
+        self.used.insert((dictionary.to_string(), id.clone()));
+        self.ids.insert(key, id.clone());
+        media.id = Some(id);
+        Ok(())
+    }
+}
+
+/// The part of a file of `len` bytes requested by a `Range` header
+#[derive(Debug,PartialEq)]
+pub enum ByteRange {
+    /// The header is not a single byte range, so the whole file is sent
+    Full,
+    /// The first and last byte
+    Partial(u64, u64),
+    Unsatisfiable
+}
+
+pub fn byte_range(header : &str, len : u64) -> ByteRange {
+    let spec = match header.trim().strip_prefix("bytes=") {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return ByteRange::Full
+    };
+    let (start, end) = match spec.find('-') {
+        Some(i) => (&spec[..i], &spec[i+1..]),
+        None => return ByteRange::Full
+    };
+    let range = match (start.parse::<u64>(), end.parse::<u64>()) {
+        (Ok(s), Ok(e)) if s <= e => Some((s, e.min(len.saturating_sub(1)))),
+        (Ok(s), _) if end.is_empty() => Some((s, len.saturating_sub(1))),
+        // The last bytes of the file
+        (_, Ok(n)) if start.is_empty() && n > 0 => Some((len.saturating_sub(n), len.saturating_sub(1))),
+        (Ok(_), Ok(_)) | (_, Ok(0)) => None,
+        _ => return ByteRange::Full
+    };
+    match range {
+        Some((s, e)) if s < len => ByteRange::Partial(s, e),
+        _ => ByteRange::Unsatisfiable
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let mut m = Media::new("audio/cat.mp3", None);
+        m.resolve(Some(Path::new("data")), None).unwrap();
+        assert_eq!(m.path, Some(PathBuf::from("data/audio/cat.mp3")));
+        assert_eq!(m.mime_type, "audio/mpeg");
+        let mut m = Media::new("http://example.com/cat.PNG", None);
+        m.resolve(Some(Path::new("data")), None).unwrap();
+        assert_eq!(m.path, None);
+        assert_eq!(m.mime_type, "image/png");
+        assert_eq!(safe_name("my cat (1).jpg"), "my_cat__1_.jpg");
+    }
+
+    #[test]
+    fn test_resolve_outside() {
+        let dir = std::env::temp_dir().join(format!("eds-test-media-{}", std::process::id()));
+        let data = dir.join("data");
+        let other = dir.join("other");
+        fs::create_dir_all(data.join("audio")).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        fs::write(data.join("audio/cat.mp3"), b"miaow").unwrap();
+        fs::write(other.join("secret.txt"), b"secret").unwrap();
+        let resolve = |url : &str, media_root : Option<&Path>| {
+            let mut m = Media::new(url, None);
+            let result = m.resolve(Some(&data), media_root);
+            (result.is_ok(), m.path.is_some())
+        };
+        let results = vec![
+            resolve("audio/cat.mp3", None),
+            resolve("audio/../audio/cat.mp3", None),
+            resolve("../other/secret.txt", None),
+            resolve("audio/../../other/secret.txt", None),
+            resolve(&format!("file://{}", other.join("secret.txt").display()), None),
+            resolve("file:///etc/passwd", None),
+            resolve("../other/secret.txt", Some(&other)),
+            resolve("file:///etc/passwd", Some(&other))
+        ];
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(results, vec![(true, true), (true, true), (false, false), (false, false),
+            (false, false), (false, false), (true, true), (false, false)]);
+    }
+
+    #[test]
+    fn test_byte_range() {
+        assert_eq!(byte_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(byte_range("bytes=500-", 1000), ByteRange::Partial(500, 999));
+        assert_eq!(byte_range("bytes=-100", 1000), ByteRange::Partial(900, 999));
+        assert_eq!(byte_range("bytes=900-2000", 1000), ByteRange::Partial(900, 999));
+        assert_eq!(byte_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range("bytes=5-1", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range("bytes=0-1,5-6", 1000), ByteRange::Full);
+        assert_eq!(byte_range("lines=1-2", 1000), ByteRange::Full);
+    }
+}
@@ -1,6 +1,12 @@
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, BTreeMap};
 use std::str::FromStr;
+use crate::tei::{TeiEntry, TeiSense, TeiRelations};
+use crate::ontolex::OntoLexEntry;
+use crate::config::Config;
+use crate::ipa;
+use crate::ud;
+use crate::media::{Media, MediaImporter};
 
 type Date = String;
 type DateTime = String;
@@ -13,17 +19,48 @@ pub trait Backend {
     fn about(&self, dictionary : &str) -> Result<Dictionary,BackendError>;
     /// List all entries in a dictrionary
     fn list(&self, dictionary : &str, offset : Option<usize>, 
-            limit : Option<usize>) -> Result<Vec<Entry>,BackendError>;
+            limit : Option<usize>, frequency : &FrequencyFilter) -> Result<Vec<Entry>,BackendError>;
     /// Search the dictionary by headword
     fn lookup(&self, dictionary : &str, headword : &str,
-              offset : Option<usize>, limit : Option<usize>,
-              part_of_speech : Option<PartOfSpeech>, inflected : bool) -> Result<Vec<Entry>,BackendError>;
+              query : &LookupQuery) -> Result<Vec<Entry>,BackendError>;
     /// Get the content as Json
     fn entry_json(&self, dictionary : &str, id : &str) -> Result<JsonEntry,BackendError>;
     /// Get the content as OntoLex
     fn entry_ontolex(&self, dictionary : &str, id : &str) -> Result<String,BackendError>;
     /// Get the content as TEI
     fn entry_tei(&self, dictionary : &str, id : &str) -> Result<String,BackendError>;
+    /// Get the structured senses of a TEI entry
+    fn entry_senses(&self, dictionary : &str, id : &str) -> Result<Vec<TeiSense>,BackendError>;
+    /// Get a sense of a TEI entry by its identifier
+    fn sense(&self, dictionary : &str, sense_id : &str) -> Result<TeiSense,BackendError>;
+    /// Get the entries a TEI entry is nested in or contains, or that an
+    /// OntoLex entry is described with in a lexicographic entry
+    fn entry_relations(&self, dictionary : &str, id : &str) -> Result<TeiRelations,BackendError>;
+    /// Get the etymology of an entry
+    fn entry_etymology(&self, dictionary : &str, id : &str) -> Result<Etymology,BackendError>;
+    /// Search the dictionary by pronunciation, returning the entries within
+    /// `distance` of the IPA transcription, closest first
+    fn lookup_pron(&self, dictionary : &str, ipa : &str, distance : f64,
+                   offset : Option<usize>, limit : Option<usize>) -> Result<Vec<PronMatch>,BackendError>;
+    /// Get a media file by its identifier, with the path of the file
+    fn media(&self, dictionary : &str, id : &str) -> Result<Media,BackendError>;
+    /// Search the dictionary for entries with a word in their definitions
+    fn lookup_definition(&self, dictionary : &str, word : &str,
+                         offset : Option<usize>, limit : Option<usize>) -> Result<Vec<Entry>,BackendError>;
+    /// Search the dictionary by a translation of its entries into `language`
+    fn lookup_translation(&self, dictionary : &str, language : &str, word : &str,
+                          offset : Option<usize>, limit : Option<usize>) -> Result<Vec<TranslationMatch>,BackendError>;
+    /// Get the paradigm of an entry: its forms with their grammatical features
+    fn paradigm(&self, dictionary : &str, id : &str) -> Result<Paradigm,BackendError>;
+    /// Search the dictionary for the multi-word expressions containing `lemma`
+    fn lookup_mwe(&self, dictionary : &str, lemma : &str,
+                  offset : Option<usize>, limit : Option<usize>) -> Result<Vec<Entry>,BackendError>;
+    /// Find the entries that have `form` as one of their forms, with the
+    /// grammatical features of the form
+    fn analyse(&self, dictionary : &str, form : &str,
+               offset : Option<usize>, limit : Option<usize>) -> Result<Vec<FormAnalysis>,BackendError>;
+    /// The part-of-speech tags used in the dictionary and how they were mapped
+    fn tagset(&self, dictionary : &str) -> Result<Vec<TagUsage>,BackendError>;
 }
 
 quick_error! {
@@ -39,6 +76,15 @@ quick_error! {
         Rdf(err : crate::rdf::turtle::TurtleParserError) {
             from()
         }
+        RdfXml(err : crate::rdf::rdfxml::RdfXmlParserError) {
+            from()
+        }
+        NTriples(err : crate::rdf::ntriples::NTriplesParserError) {
+            from()
+        }
+        JsonLd(err : crate::rdf::jsonld::JsonLdError) {
+            from()
+        }
         Io(err : std::io::Error) {
             from()
         }
@@ -56,8 +102,11 @@ quick_error! {
 pub struct EDSState {
     dictionaries : Arc<Mutex<HashMap<String,Dictionary>>>,
     entries_lemmas : Arc<Mutex<HashMap<String,HashMap<String,Vec<Entry>>>>>,
+    /// The entries of each dictionary in the order they were loaded
+    entries_list : Arc<Mutex<HashMap<String,Vec<Entry>>>>,
     entries_forms : Arc<Mutex<HashMap<String,HashMap<String,Vec<Entry>>>>>,
-    entries_id : Arc<Mutex<HashMap<String,HashMap<String,EntryContent>>>>
+    entries_id : Arc<Mutex<HashMap<String,HashMap<String,EntryContent>>>>,
+    senses_id : Arc<Mutex<HashMap<String,HashMap<String,String>>>>
 }
 
 impl EDSState {
@@ -65,14 +114,33 @@ impl EDSState {
                dictionaries : HashMap<String, Dictionary>,
                dict_entries : HashMap<String, Vec<EntryContent>>) -> Self {
         let mut dict_entry_map = HashMap::new();
+        let mut dict_entry_list = HashMap::new();
         let mut dict_entry_map2 = HashMap::new();
         let mut entry_by_id = HashMap::new();
-        for (id, entries) in dict_entries {
+        let mut sense_by_id = HashMap::new();
+        // Media are served from where they are
+        let mut media = MediaImporter::new(None);
+        for (id, mut entries) in dict_entries {
+            let homographs = number_homographs(&entries.iter()
+                .map(|e| (e.lemma().to_string(), e.homograph(), e.pos(), e.groups_homographs())).collect::<Vec<_>>());
+            for (entry, n) in entries.iter_mut().zip(homographs) {
+                entry.set_homograph(n);
+            }
             let mut entry_map = HashMap::new();
+            let mut entry_list = Vec::new();
             let mut eid_map = HashMap::new();
             let mut entry_map2 = HashMap::new();
-            for entry in entries {
+            let mut sid_map = HashMap::new();
+            for mut entry in entries {
+                // Missing files were reported when the data was read
+                entry.media_mut().retain_mut(|m| media.import(&id, m).is_ok());
                 eid_map.insert(entry.id().to_string(), entry.clone());
+                entry_list.push(entry_from_content(release.clone(), &entry));
+                if let EntryContent::Tei { entry : ref tei, .. } = entry {
+                    for sense_id in tei.sense_ids() {
+                        sid_map.insert(sense_id, entry.id().to_string());
+                    }
+                }
                 if !entry_map.contains_key(entry.lemma()) {
                     entry_map.insert(entry.lemma().to_string(),
                         Vec::new());
@@ -84,19 +152,23 @@ impl EDSState {
                         entry_map2.insert(var.to_string(),
                         Vec::new());
                     }
-                    entry_map.entry(var.clone())
+                    entry_map2.entry(var.clone())
                         .and_modify(|e| e.push(entry_from_content(release.clone(), &entry)));
                     }
             }
             dict_entry_map.insert(id.clone(), entry_map);
+            dict_entry_list.insert(id.clone(), entry_list);
             dict_entry_map2.insert(id.clone(), entry_map2);
+            sense_by_id.insert(id.clone(), sid_map);
             entry_by_id.insert(id, eid_map);
         }
         EDSState {
             dictionaries : Arc::new(Mutex::new(dictionaries)),
             entries_lemmas : Arc::new(Mutex::new(dict_entry_map)),
+            entries_list : Arc::new(Mutex::new(dict_entry_list)),
             entries_forms : Arc::new(Mutex::new(dict_entry_map2)),
-            entries_id : Arc::new(Mutex::new(entry_by_id))
+            entries_id : Arc::new(Mutex::new(entry_by_id)),
+            senses_id : Arc::new(Mutex::new(sense_by_id))
         }
     }
 }
@@ -110,27 +182,11 @@ impl Backend for EDSState {
             .ok_or(BackendError::NotFound)
     }   
     fn list(&self, dictionary : &str, offset : Option<usize>,
-            limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
-        match self.entries_lemmas.lock().unwrap().get(dictionary) {
-            Some(emap) => {
-                let entries : Vec<Entry> = match offset {
-                    Some(offset) => {
-                        match limit {
-                            Some(limit) => 
-                                emap.values().flat_map(|x| x).skip(offset).take(limit).map(|x| x.clone()).collect(),
-                            None =>
-                                emap.values().flat_map(|x| x).skip(offset).map(|x| x.clone()).collect()
-                        }
-                    },
-                    None =>
-                        match limit {
-                            Some(limit) => 
-                                emap.values().flat_map(|x| x).take(limit).map(|x| x.clone()).collect(),
-                            None =>
-                                emap.values().flat_map(|x| x).map(|x| x.clone()).collect()
-                        }
-                };
-                Some(entries).ok_or(BackendError::NotFound)
+            limit : Option<usize>, frequency : &FrequencyFilter) -> Result<Vec<Entry>,BackendError> {
+        match self.entries_list.lock().unwrap().get(dictionary) {
+            Some(elist) => {
+                let entries = frequency.apply(elist.to_vec());
+                Ok(entries.into_iter().skip(offset.unwrap_or(0)).take(limit.unwrap_or(usize::MAX)).collect())
             },
             None => {
                 None.ok_or(BackendError::NotFound)
@@ -138,72 +194,186 @@ impl Backend for EDSState {
         }
     }
     fn lookup(&self, dictionary : &str, headword : &str,
-              offset : Option<usize>, limit : Option<usize>,
-              part_of_speech : Option<PartOfSpeech>, inflected : bool) -> Result<Vec<Entry>,BackendError> {
+              query : &LookupQuery) -> Result<Vec<Entry>,BackendError> {
         let dict = self.entries_lemmas.lock().unwrap();
         let dict2 = self.entries_forms.lock().unwrap();
-        match dict.get(dictionary).and_then(|x| x.get(headword)) {
-            Some(emap) => {
-                let i1 = emap.iter()
-                    .filter(|e| part_of_speech.is_none() || e.part_of_speech.contains(part_of_speech.as_ref().unwrap()));
+        let contents = self.entries_id.lock().unwrap();
+        let lemmas = dict.get(dictionary).and_then(|x| x.get(headword));
+        let forms = if query.inflected {
+            dict2.get(dictionary).and_then(|x| x.get(headword))
+        } else {
+            None
+        };
+        match (lemmas, forms) {
+            (None, None) => Err(BackendError::NotFound),
+            (lemmas, forms) => {
+                let has_features = |e : &&Entry| query.features.is_empty() || 
+                    contents.get(dictionary).and_then(|x| x.get(&e.id))
+                        .map(|c| c.has_form_features(headword, &query.features))
+                        .unwrap_or(false);
                 let el = Vec::new();
-                let i2 = (if inflected {
-                    match dict2.get(dictionary).and_then(|x| x.get(headword)) {
-                        Some(emap2) => {
-                            emap2.iter()
-                        },
-                        None => {
-                            el.iter()
-                        }
-                    }
-                } else {
-                    el.iter()
-                }).filter(|e| part_of_speech.is_none() || e.part_of_speech.contains(part_of_speech.as_ref().unwrap()));
-                let entries : Vec<Entry> = match offset {
-                    Some(offset) => {
-                        match limit {
-                            Some(limit) => 
-                                i1.chain(i2).skip(offset).take(limit).map(|x| x.clone()).collect(),
-                            None =>
-                                i1.chain(i2).skip(offset).map(|x| x.clone()).collect()
-                        }
-                    },
-                    None =>
-                        match limit {
-                            Some(limit) => 
-                                i1.chain(i2).take(limit).map(|x| x.clone()).collect(),
-                            None =>
-                                i1.chain(i2).map(|x| x.clone()).collect()
-                        }
-                };
-                Some(entries)
-            .ok_or(BackendError::NotFound)
-            }
-            None => {
-                None
-            .ok_or(BackendError::NotFound)
+                let i1 = lemmas.unwrap_or(&el).iter()
+                    .filter(|e| query.part_of_speech.is_none() || e.part_of_speech.contains(query.part_of_speech.as_ref().unwrap()))
+                    .filter(|e| query.homograph.is_none() || e.homograph == query.homograph)
+                    .filter(&has_features);
+                let i2 = forms.unwrap_or(&el).iter()
+                    .filter(|e| query.part_of_speech.is_none() || e.part_of_speech.contains(query.part_of_speech.as_ref().unwrap()))
+                    .filter(|e| query.homograph.is_none() || e.homograph == query.homograph)
+                    .filter(&has_features);
+                let entries = query.frequency.apply(i1.chain(i2).cloned().collect());
+                Ok(entries.into_iter().skip(query.offset.unwrap_or(0)).take(query.limit.unwrap_or(usize::MAX)).collect())
             }
         }
     }
     fn entry_json(&self, dictionary : &str, id : &str) -> Result<JsonEntry,BackendError> {
         self.entries_id.lock().unwrap().get(dictionary).and_then(|x| match x.get(id) {
             Some(EntryContent::Json(entry)) => Some(entry.clone()),
-            _ => None
+            Some(EntryContent::Tei { id, lemma, pos, variants : vars, entry : tei, .. }) => Some(JsonEntry::from_tei(id, lemma, pos, vars, tei)),
+            Some(EntryContent::OntoLex { id, lemma, pos, variants : vars, entry : ontolex, .. }) => Some(JsonEntry::from_ontolex(id, lemma, pos, vars, ontolex)),
+            None => None
         })
+            .map(|entry| entry.link_media(dictionary))
             .ok_or(BackendError::NotFound)
     }
     fn entry_ontolex(&self, dictionary : &str, id : &str) -> Result<String,BackendError> { 
         self.entries_id.lock().unwrap().get(dictionary).and_then(|x| match x.get(id) {
-            Some(EntryContent::OntoLex(_,_,_,_,content)) => Some(content.clone()),
+            Some(EntryContent::OntoLex { content, .. }) => Some(content.clone()),
             _ => None
         }).ok_or(BackendError::NotFound)
     }
     fn entry_tei(&self, dictionary : &str, id : &str) -> Result<String,BackendError> { 
         self.entries_id.lock().unwrap().get(dictionary).and_then(|x| match x.get(id) {
-            Some(EntryContent::Tei(_,_,_,_,content)) => Some(content.clone()),
+            Some(EntryContent::Tei { content, .. }) => Some(content.clone()),
+            _ => None
+        }).ok_or(BackendError::NotFound)
+    }
+    fn entry_senses(&self, dictionary : &str, id : &str) -> Result<Vec<TeiSense>,BackendError> {
+        self.entries_id.lock().unwrap().get(dictionary).and_then(|x| match x.get(id) {
+            Some(EntryContent::Tei { entry : tei, .. }) => Some(tei.senses.clone()),
+            _ => None
+        }).ok_or(BackendError::NotFound)
+    }
+    fn sense(&self, dictionary : &str, sense_id : &str) -> Result<TeiSense,BackendError> {
+        let entry_id = self.senses_id.lock().unwrap().get(dictionary)
+            .and_then(|x| x.get(sense_id).cloned())
+            .ok_or(BackendError::NotFound)?;
+        self.entries_id.lock().unwrap().get(dictionary).and_then(|x| match x.get(&entry_id) {
+            Some(EntryContent::Tei { entry : tei, .. }) => tei.find_sense(sense_id).cloned(),
+            _ => None
+        }).ok_or(BackendError::NotFound)
+    }
+    fn entry_relations(&self, dictionary : &str, id : &str) -> Result<TeiRelations,BackendError> {
+        self.entries_id.lock().unwrap().get(dictionary).and_then(|x| match x.get(id) {
+            Some(EntryContent::Tei { entry : tei, .. }) => Some(tei.relations.clone()),
+            Some(EntryContent::OntoLex { entry : ontolex, .. }) => Some(ontolex.relations.clone()),
             _ => None
         }).ok_or(BackendError::NotFound)
     }
+    fn entry_etymology(&self, dictionary : &str, id : &str) -> Result<Etymology,BackendError> {
+        self.entries_id.lock().unwrap().get(dictionary).and_then(|x| x.get(id))
+            .map(|entry| entry.etymology().cloned().unwrap_or_default())
+            .ok_or(BackendError::NotFound)
+    }
+    fn lookup_pron(&self, dictionary : &str, ipa : &str, distance : f64,
+                   offset : Option<usize>, limit : Option<usize>) -> Result<Vec<PronMatch>,BackendError> {
+        let ipa = ipa::normalize(ipa);
+        let dictionaries = self.dictionaries.lock().unwrap();
+        let release = &dictionaries.get(dictionary).ok_or(BackendError::NotFound)?.release;
+        let mut matches = Vec::new();
+        if let Some(entries) = self.entries_id.lock().unwrap().get(dictionary) {
+            for entry in entries.values() {
+                if let Some((pron, d)) = PronMatch::closest(&ipa, entry.pronunciations(), distance) {
+                    matches.push(PronMatch::new(entry_from_content(release.clone(), entry), pron, d));
+                }
+            }
+        }
+        Ok(PronMatch::rank(matches, offset, limit))
+    }
+    fn media(&self, dictionary : &str, id : &str) -> Result<Media,BackendError> {
+        self.entries_id.lock().unwrap().get(dictionary)
+            .and_then(|x| x.values().flat_map(|e| e.media().iter()).find(|m| m.id.as_ref().map(|i| i == id).unwrap_or(false)).cloned())
+            .ok_or(BackendError::NotFound)
+    }
+    fn lookup_definition(&self, dictionary : &str, word : &str,
+                         offset : Option<usize>, limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
+        let dictionaries = self.dictionaries.lock().unwrap();
+        let release = &dictionaries.get(dictionary).ok_or(BackendError::NotFound)?.release;
+        let mut entries = Vec::new();
+        if let Some(contents) = self.entries_id.lock().unwrap().get(dictionary) {
+            for entry in contents.values() {
+                if entry.definitions().iter().any(|d| contains_word(d, word)) {
+                    entries.push(entry_from_content(release.clone(), entry));
+                }
+            }
+        }
+        entries.sort_by(|x, y| x.lemma.cmp(&y.lemma).then_with(|| x.id.cmp(&y.id)));
+        Ok(entries.into_iter().skip(offset.unwrap_or(0)).take(limit.unwrap_or(usize::MAX)).collect())
+    }
+    fn lookup_translation(&self, dictionary : &str, language : &str, word : &str,
+                          offset : Option<usize>, limit : Option<usize>) -> Result<Vec<TranslationMatch>,BackendError> {
+        let dictionaries = self.dictionaries.lock().unwrap();
+        let release = &dictionaries.get(dictionary).ok_or(BackendError::NotFound)?.release;
+        let mut matches = Vec::new();
+        if let Some(entries) = self.entries_id.lock().unwrap().get(dictionary) {
+            for entry in entries.values() {
+                for t in entry.translations() {
+                    if t.matches(language, word) {
+                        matches.push(TranslationMatch { entry : entry_from_content(release.clone(), entry),
+                            sense : t.sense, translation : t.text });
+                    }
+                }
+            }
+        }
+        Ok(TranslationMatch::rank(matches, offset, limit))
+    }
+    fn paradigm(&self, dictionary : &str, id : &str) -> Result<Paradigm,BackendError> {
+        self.entries_id.lock().unwrap().get(dictionary).and_then(|x| x.get(id))
+            .map(|entry| entry.paradigm())
+            .ok_or(BackendError::NotFound)
+    }
+    fn lookup_mwe(&self, dictionary : &str, lemma : &str,
+                  offset : Option<usize>, limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
+        let dictionaries = self.dictionaries.lock().unwrap();
+        let release = &dictionaries.get(dictionary).ok_or(BackendError::NotFound)?.release;
+        let mut entries = Vec::new();
+        if let Some(contents) = self.entries_id.lock().unwrap().get(dictionary) {
+            for entry in contents.values() {
+                if mwe_contains(entry.lemma(), &entry.components(), lemma) {
+                    entries.push(entry_from_content(release.clone(), entry));
+                }
+            }
+        }
+        entries.sort_by(|x, y| x.lemma.cmp(&y.lemma).then_with(|| x.id.cmp(&y.id)));
+        Ok(entries.into_iter().skip(offset.unwrap_or(0)).take(limit.unwrap_or(usize::MAX)).collect())
+    }
+    fn analyse(&self, dictionary : &str, form : &str,
+               offset : Option<usize>, limit : Option<usize>) -> Result<Vec<FormAnalysis>,BackendError> {
+        let dictionaries = self.dictionaries.lock().unwrap();
+        let release = &dictionaries.get(dictionary).ok_or(BackendError::NotFound)?.release;
+        let mut analyses = Vec::new();
+        if let Some(contents) = self.entries_id.lock().unwrap().get(dictionary) {
+            for entry in contents.values() {
+                if entry.lemma() == form || entry.variants().iter().any(|v| v == form) {
+                    analyses.extend(FormAnalysis::from_paradigm(
+                        entry_from_content(release.clone(), entry), &entry.paradigm(), form));
+                }
+            }
+        }
+        Ok(FormAnalysis::rank(analyses, offset, limit))
+    }
+    fn tagset(&self, dictionary : &str) -> Result<Vec<TagUsage>,BackendError> {
+        if !self.dictionaries.lock().unwrap().contains_key(dictionary) {
+            return Err(BackendError::NotFound);
+        }
+        let mut tags : Vec<TagUsage> = Vec::new();
+        if let Some(contents) = self.entries_id.lock().unwrap().get(dictionary) {
+            for entry in contents.values() {
+                TagUsage::count(&mut tags, entry.tags());
+            }
+        }
+        tags.sort_by(|x, y| x.tag.cmp(&y.tag));
+        Ok(tags)
+    }
 
 }
 
@@ -213,8 +383,50 @@ pub fn entry_from_content(release : Release, content : &EntryContent) -> Entry {
         lemma: content.lemma().to_string(),
         id: content.id().to_string(),
         part_of_speech: content.pos(),
-        formats: vec![content.format()]
+        formats: content.formats(),
+        frequency: content.frequency(),
+        homograph: content.homograph()
+    }
+}
+
+/// Number the homographs of a dictionary, given the lemma, the homograph
+/// number in the source, the part of speech of each entry, in source order,
+/// and whether it only groups homographs. Numbers from the source are kept
+/// and the other entries with the same lemma get the lowest unused numbers,
+/// grouped by part of speech and then in source order. An entry without
+/// homographs and an entry that only groups homographs is not numbered
+pub fn number_homographs(entries : &[(String, Option<u32>, Vec<PartOfSpeech>, bool)]) -> Vec<Option<u32>> {
+    let mut numbers : Vec<Option<u32>> = entries.iter().map(|e| if e.3 { None } else { e.1 }).collect();
+    let mut lemmas : HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, e) in entries.iter().enumerate() {
+        if !e.3 {
+            lemmas.entry(&e.0).or_default().push(i);
+        }
+    }
+    for group in lemmas.values() {
+        if group.len() < 2 {
+            continue;
+        }
+        let mut pos_order : Vec<Option<&PartOfSpeech>> = Vec::new();
+        for i in group.iter() {
+            if !pos_order.contains(&entries[*i].2.first()) {
+                pos_order.push(entries[*i].2.first());
+            }
+        }
+        let mut used : HashSet<u32> = group.iter().filter_map(|i| numbers[*i]).collect();
+        let mut missing : Vec<usize> = group.iter().cloned().filter(|i| numbers[*i].is_none()).collect();
+        missing.sort_by_key(|i| (pos_order.iter().position(|p| *p == entries[*i].2.first()), *i));
+        let mut n = 0;
+        for i in missing {
+            n += 1;
+            while used.contains(&n) {
+                n += 1;
+            }
+            used.insert(n);
+            numbers[i] = Some(n);
+        }
     }
+    numbers
 }
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
@@ -412,6 +624,10 @@ pub struct Dictionary {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temporal     : Option<String>,
 
+    /// A name given to the resource.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title : Option<String>,
+
     /// The nature or genre of the resource.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "type")]
@@ -477,6 +693,7 @@ impl Dictionary {
             subject : None,
             table_of_contents : None,
             temporal     : None,
+            title : None,
             _type : None,
             valid : None,
         }
@@ -529,6 +746,7 @@ impl Dictionary {
             "subject" => self.subject = Some(value.to_owned()),
             "tableOfContents" => self.table_of_contents = Some(value.to_owned()),
             "temporal" => self.    temporal = Some(value.to_owned()),
+            "title" => self.title = Some(value.to_owned()),
             "type" => self._type = Some(value.to_owned()),
             "valid" => self.valid = Some(value.to_owned()),
             _ => eprintln!("Unrecognised DC property ({}) ignored", prop)
@@ -672,6 +890,9 @@ impl Dictionary {
         if let Some(ref value) = self.    temporal {
             props.push(("temporal",value.to_owned()));
         }
+        if let Some(ref value) = self.title {
+            props.push(("title",value.to_owned()));
+        }
         if let Some(ref value) = self._type {
             props.push(("type",value.to_owned()));
         }
@@ -762,18 +983,116 @@ pub struct Entry {
     pub lemma : String,
     pub id : String,
     pub part_of_speech : Vec<PartOfSpeech>,
-    pub formats : Vec<Format>
+    pub formats : Vec<Format>,
+    /// The corpus frequency of the entry, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub frequency : Option<u64>,
+    /// The number that tells the entry apart from the entries with the
+    /// same lemma
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub homograph : Option<u32>
 }
 
 impl Entry {
     pub fn new(release : Release, lemma : String, id : String,
                part_of_speech : Vec<PartOfSpeech>, formats : Vec<Format>) -> Self {
         Entry {
-            release, lemma, id, part_of_speech, formats
+            release, lemma, id, part_of_speech, formats, frequency : None, homograph : None
+        }
+    }
+}
+
+/// How list and lookup results are filtered and sorted by frequency
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct FrequencyFilter {
+    /// Only return entries with at least this frequency
+    pub min : Option<u64>,
+    /// Sort the entries by frequency, most frequent first
+    pub sort : bool
+}
+
+impl FrequencyFilter {
+    /// Filter and sort entries. Entries without a frequency are sorted last
+    /// and otherwise the order of the entries is kept
+    pub fn apply(&self, mut entries : Vec<Entry>) -> Vec<Entry> {
+        if let Some(min) = self.min {
+            entries.retain(|e| e.frequency.map(|f| f >= min).unwrap_or(false));
+        }
+        if self.sort {
+            entries.sort_by_key(|e| std::cmp::Reverse(e.frequency));
         }
+        entries
+    }
+}
+
+/// A search by headword: the filters on the entries and the page of the
+/// results to return
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct LookupQuery {
+    pub part_of_speech : Option<PartOfSpeech>,
+    /// Also match the inflected forms of the entries
+    pub inflected : bool,
+    /// The grammatical features that the matching form must have
+    pub features : Vec<(String, String)>,
+    pub frequency : FrequencyFilter,
+    /// Only the entries with this homograph number
+    pub homograph : Option<u32>,
+    pub offset : Option<usize>,
+    pub limit : Option<usize>
+}
+
+/// A frequency of a word or sense in a corpus (`frac:frequency`), or a 
+/// frequency label (TEI `<usg type="frequency">`)
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct Frequency {
+    /// The number of occurrences
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub count : Option<u64>,
+    /// The frequency as text, if it is not a number, e.g., "rare"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub label : Option<String>,
+    /// The corpus the occurrences were counted in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub corpus : Option<String>
+}
+
+impl Frequency {
+    /// Read a frequency that may be a count or a label
+    pub fn from_text(text : &str, corpus : Option<String>) -> Frequency {
+        match text.trim().parse::<u64>() {
+            Ok(count) => Frequency { count : Some(count), label : None, corpus },
+            Err(_) => Frequency { count : None, label : Some(text.trim().to_string()), corpus }
+        }
+    }
+
+    /// The highest count of some frequencies
+    pub fn max_count(frequencies : &[Frequency]) -> Option<u64> {
+        frequencies.iter().filter_map(|f| f.count).max()
     }
 }
 
+/// An attestation of a word or sense in a corpus (`frac:attestation`)
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct Attestation {
+    /// The text of the attestation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub text : Option<String>,
+    /// The source the text is quoted from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub citation : Option<String>,
+    /// Where in the source the text occurs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub locus : Option<String>
+}
+
 #[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
 #[allow(non_camel_case_types)]
 pub enum PartOfSpeech {
@@ -796,6 +1115,82 @@ pub enum PartOfSpeech {
     X
 }
 
+/// What a part-of-speech tag of the source is mapped to: a UD part of
+/// speech, a lexinfo part of speech and UD features (e.g., `Gender=Fem`)
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct TagMapping {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub pos : Option<PartOfSpeech>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub lexinfo : Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub features : Option<String>
+}
+
+impl TagMapping {
+    /// The UD features of the mapping
+    pub fn features(&self) -> Vec<(String, String)> {
+        self.features.as_ref().and_then(|f| ud::parse(f).ok()).unwrap_or_default()
+    }
+}
+
+/// A part-of-speech tag as given in the source, with what it was mapped to.
+/// A tag that could not be mapped has no `pos`
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+pub struct SourceTag {
+    pub tag : String,
+    #[serde(flatten)]
+    pub mapping : TagMapping
+}
+
+impl SourceTag {
+    fn from_json(pos : &JsonPartOfSpeech) -> Option<SourceTag> {
+        match serde_json::to_value(pos) {
+            Ok(serde_json::Value::String(tag)) => Some(SourceTag {
+                mapping : TagMapping { pos : Some(JsonPartOfSpeech::convert(pos)), lexinfo : Some(tag.clone()), features : None },
+                tag
+            }),
+            _ => None
+        }
+    }
+
+    /// Add UD features of the tags to a form that does not have them
+    pub fn add_features(tags : &[SourceTag], features : &mut BTreeMap<String, String>) {
+        for tag in tags.iter() {
+            for (k, v) in tag.mapping.features() {
+                if !ud::features(features.iter()).contains_key(&k) {
+                    features.insert(k, v);
+                }
+            }
+        }
+    }
+}
+
+/// A tag used in a dictionary, with its mapping and the number of entries
+/// it is used in
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+pub struct TagUsage {
+    pub tag : String,
+    #[serde(flatten)]
+    pub mapping : TagMapping,
+    pub entries : usize
+}
+
+impl TagUsage {
+    /// Count the tags of an entry
+    pub fn count(usages : &mut Vec<TagUsage>, tags : Vec<SourceTag>) {
+        for tag in tags {
+            match usages.iter_mut().find(|u| u.tag == tag.tag && u.mapping == tag.mapping) {
+                Some(usage) => usage.entries += 1,
+                None => usages.push(TagUsage { tag : tag.tag, mapping : tag.mapping, entries : 1 })
+            }
+        }
+    }
+}
+
 impl FromStr for PartOfSpeech {
     type Err = String;
 
@@ -849,39 +1244,74 @@ impl FromStr for Format {
 #[derive(Clone,Debug,Deserialize)]
 pub enum EntryContent {
     Json(JsonEntry),
-    Tei(String, String, Vec<PartOfSpeech>, Vec<String>, String),
-    OntoLex(String, String, Vec<PartOfSpeech>, Vec<String>, String)
+    Tei {
+        id : String,
+        lemma : String,
+        pos : Vec<PartOfSpeech>,
+        /// The forms the entry can be looked up by
+        variants : Vec<String>,
+        /// The TEI source of the entry
+        content : String,
+        entry : TeiEntry
+    },
+    OntoLex {
+        id : String,
+        lemma : String,
+        pos : Vec<PartOfSpeech>,
+        /// The forms the entry can be looked up by
+        variants : Vec<String>,
+        /// The Turtle source of the entry
+        content : String,
+        entry : OntoLexEntry
+    }
 }
 
 impl EntryContent {
     pub fn id(&self) -> &str {
         match self {
             EntryContent::Json(j) => &j.id,
-            EntryContent::Tei(id,_,_,_,_) => id,
-            EntryContent::OntoLex(id,_,_,_,_) => id
+            EntryContent::Tei { id, .. } => id,
+            EntryContent::OntoLex { id, .. } => id
         }
     }
 
     pub fn lemma(&self) -> &str {
         match self {
             EntryContent::Json(j) => &j.canonical_form.written_rep,
-            EntryContent::Tei(_,lemma,_,_,_) => lemma,
-            EntryContent::OntoLex(_,lemma,_,_,_) => lemma
+            EntryContent::Tei { lemma, .. } => lemma,
+            EntryContent::OntoLex { lemma, .. } => lemma
         }
     }
 
     pub fn pos(&self) -> Vec<PartOfSpeech> {
         match self { 
             EntryContent::Json(j) => vec![JsonPartOfSpeech::convert(&j.part_of_speech)],
-            EntryContent::Tei(_,_,pos,_,_) => pos.clone(),
-            EntryContent::OntoLex(_,_,pos,_,_) => pos.clone()
+            EntryContent::Tei { pos, .. } => pos.clone(),
+            EntryContent::OntoLex { pos, .. } => pos.clone()
         }
     }
     pub fn format(&self) -> Format {
         match self {
             EntryContent::Json(_) => Format::json,
-            EntryContent::Tei(_,_,_,_,_) => Format::tei,
-            EntryContent::OntoLex(_,_,_,_,_) => Format::ontolex
+            EntryContent::Tei { .. } => Format::tei,
+            EntryContent::OntoLex { .. } => Format::ontolex
+        }
+    }
+    /// Check if a form of the entry has all of the given grammatical features
+    pub fn has_form_features(&self, form : &str, features : &[(String, String)]) -> bool {
+        match self {
+            EntryContent::Tei { entry : tei, .. } => tei.forms.iter().any(|f| f.written_rep == form && f.has_features(features)),
+            EntryContent::OntoLex { entry : ontolex, .. } => ontolex.forms.iter().any(|f| f.written_rep == form && f.has_features(features)),
+            EntryContent::Json(j) => features.is_empty() || 
+                Paradigm::from_json(j).forms.iter().any(|f| f.written_rep == form && f.has_features(features))
+        }
+    }
+    /// The formats the entry can be returned in
+    pub fn formats(&self) -> Vec<Format> {
+        match self {
+            EntryContent::Tei { .. } => vec![Format::tei, Format::json],
+            EntryContent::OntoLex { .. } => vec![Format::ontolex, Format::json],
+            _ => vec![self.format()]
         }
     }
     pub fn variants(&self) -> Vec<String> {
@@ -891,18 +1321,491 @@ impl EntryContent {
             } else {
                 Vec::new()
             },
-            EntryContent::Tei(_,_,_,vars,_) => vars.clone(),
-            EntryContent::OntoLex(_,_,_,vars,_) => vars.clone()
+            EntryContent::Tei { variants : vars, .. } => vars.clone(),
+            EntryContent::OntoLex { variants : vars, .. } => vars.clone()
+        }
+    }
+
+    /// The pronunciations of the forms of the entry, as normalized IPA
+    pub fn pronunciations(&self) -> Vec<Pronunciation> {
+        match self {
+            EntryContent::Json(j) => j.canonical_form.pronunciation().into_iter()
+                .chain(j.other_form.iter().flat_map(|f| f.iter().flat_map(|f| f.pronunciation())))
+                .collect(),
+            EntryContent::Tei { entry : tei, .. } => tei.forms.iter().flat_map(|f| f.pronunciations.iter()
+                .map(move |p| Pronunciation { form : f.written_rep.clone(), ipa : p.clone() }))
+                .collect(),
+            EntryContent::OntoLex { entry : ontolex, .. } => ontolex.pronunciations.clone()
+        }
+    }
+
+    /// The media files the entry refers to
+    pub fn media(&self) -> &[Media] {
+        match self {
+            EntryContent::Json(j) => &j.media,
+            EntryContent::Tei { entry : tei, .. } => &tei.media,
+            EntryContent::OntoLex { entry : ontolex, .. } => &ontolex.media
+        }
+    }
+
+    pub fn media_mut(&mut self) -> &mut Vec<Media> {
+        match self {
+            EntryContent::Json(j) => &mut j.media,
+            EntryContent::Tei { entry : tei, .. } => &mut tei.media,
+            EntryContent::OntoLex { entry : ontolex, .. } => &mut ontolex.media
+        }
+    }
+
+    /// The definitions of all senses of the entry
+    pub fn definitions(&self) -> Vec<String> {
+        match self {
+            EntryContent::Json(j) => j.senses.iter().flat_map(|s| s.definition.iter().cloned()).collect(),
+            EntryContent::Tei { entry : tei, .. } => tei.definitions(),
+            EntryContent::OntoLex { entry : ontolex, .. } => ontolex.senses.iter().flat_map(|s| s.definition.iter().cloned()).collect()
+        }
+    }
+
+    /// The equivalents of the entry in other languages
+    pub fn translations(&self) -> Vec<Equivalent> {
+        match self {
+            EntryContent::Tei { entry : tei, .. } => tei.translations(),
+            EntryContent::OntoLex { entry : ontolex, .. } => ontolex.translations.clone(),
+            EntryContent::Json(_) => Vec::new()
+        }
+    }
+
+    /// The forms of the entry with their grammatical features
+    pub fn paradigm(&self) -> Paradigm {
+        match self {
+            EntryContent::Json(j) => Paradigm::from_json(j),
+            EntryContent::Tei { id, lemma, entry : tei, .. } => Paradigm::from_tei(id, lemma, tei),
+            EntryContent::OntoLex { id, lemma, entry : ontolex, .. } => Paradigm::from_ontolex(id, lemma, ontolex)
+        }
+    }
+
+    /// The frequencies of the entry and of each of its senses
+    pub fn frequencies(&self) -> (Vec<Frequency>, Vec<Frequency>) {
+        match self {
+            EntryContent::Json(j) => (j.frequencies.clone(), j.senses.iter().flat_map(|s| s.frequencies.iter().cloned()).collect()),
+            EntryContent::Tei { entry : tei, .. } => (tei.frequencies.clone(), tei.sense_frequencies()),
+            EntryContent::OntoLex { entry : ontolex, .. } => (ontolex.frequencies.clone(),
+                ontolex.senses.iter().flat_map(|s| s.frequencies.iter().cloned()).collect())
+        }
+    }
+
+    /// The frequency of the entry: its highest count or else the highest
+    /// count of its senses
+    pub fn frequency(&self) -> Option<u64> {
+        let (entry, senses) = self.frequencies();
+        Frequency::max_count(&entry).or_else(|| Frequency::max_count(&senses))
+    }
+
+    /// The homograph number of the entry
+    pub fn homograph(&self) -> Option<u32> {
+        match self {
+            EntryContent::Json(j) => j.homograph,
+            EntryContent::Tei { entry : tei, .. } => tei.homograph,
+            EntryContent::OntoLex { entry : ontolex, .. } => ontolex.homograph
+        }
+    }
+
+    pub fn set_homograph(&mut self, homograph : Option<u32>) {
+        match self {
+            EntryContent::Json(j) => j.homograph = homograph,
+            EntryContent::Tei { entry : tei, .. } => tei.homograph = homograph,
+            EntryContent::OntoLex { entry : ontolex, .. } => ontolex.homograph = homograph
+        }
+    }
+
+    /// Whether the entry only groups homographs (TEI `<hom>`s), which are
+    /// numbered instead of it
+    pub fn groups_homographs(&self) -> bool {
+        match self {
+            EntryContent::Tei { entry : tei, .. } => tei.relations.children.iter().any(|c| c.relation == "homograph"),
+            _ => false
+        }
+    }
+
+    /// The constituents and subterms of a multi-word expression
+    pub fn components(&self) -> Vec<Component> {
+        match self {
+            EntryContent::Json(j) => j.components.iter().chain(j.subterms.iter()).cloned().collect(),
+            EntryContent::OntoLex { entry : ontolex, .. } => ontolex.components.iter().chain(ontolex.subterms.iter()).cloned().collect(),
+            EntryContent::Tei { .. } => Vec::new()
+        }
+    }
+
+    /// The part-of-speech tags of the source and how they were mapped
+    pub fn tags(&self) -> Vec<SourceTag> {
+        match self {
+            EntryContent::Json(j) if j.tags.is_empty() => SourceTag::from_json(&j.part_of_speech).into_iter().collect(),
+            EntryContent::Json(j) => j.tags.clone(),
+            EntryContent::Tei { entry : tei, .. } => tei.tags.clone(),
+            EntryContent::OntoLex { entry : ontolex, .. } => ontolex.tags.clone()
+        }
+    }
+
+    /// The etymology of the entry, if it has one
+    pub fn etymology(&self) -> Option<&Etymology> {
+        match self {
+            EntryContent::Tei { entry : tei, .. } => Some(&tei.etymology),
+            EntryContent::OntoLex { entry : ontolex, .. } => Some(&ontolex.etymology),
+            EntryContent::Json(_) => None
         }
     }
 
     pub fn content(&self) -> String {
         match self {
             EntryContent::Json(j) => serde_json::to_string(j).unwrap(),
-            EntryContent::Tei(_,_,_,_,content) => content.clone(),
-            EntryContent::OntoLex(_,_,_,_,content) => content.clone()
+            EntryContent::Tei { content, .. } => content.clone(),
+            EntryContent::OntoLex { content, .. } => content.clone()
+        }
+    }
+}
+
+/// The etymology of an entry, as a tree of the words it derives from
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct Etymology {
+    /// The etymons the entry derives from
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub ancestors : Vec<Etymon>,
+    /// Words in other languages with the same origin
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub cognates : Vec<Etymon>,
+    /// All the languages of the ancestors and cognates
+    #[serde(default)]
+    pub languages : Vec<String>
+}
+
+impl Etymology {
+    pub fn new(ancestors : Vec<Etymon>, cognates : Vec<Etymon>) -> Etymology {
+        let mut languages = Vec::new();
+        for etymon in ancestors.iter().chain(cognates.iter()) {
+            etymon.add_languages(&mut languages);
+        }
+        Etymology { ancestors, cognates, languages }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ancestors.is_empty() && self.cognates.is_empty()
+    }
+}
+
+/// A word in the history of an entry, or a cognate of it
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Etymon {
+    /// The language code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language : Option<String>,
+    /// The language as named in the dictionary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_name : Option<String>,
+    #[serde(default)]
+    pub forms : Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub glosses : Vec<String>,
+    /// The kind of derivation, e.g., `borrowing` or `inheritance`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "type")]
+    pub etym_type : Option<String>,
+    /// The identifier of the entry for this word, if it is in the dictionary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry : Option<String>,
+    /// The etymons this word in turn derives from
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub ancestors : Vec<Etymon>
+}
+
+impl Etymon {
+    fn add_languages(&self, languages : &mut Vec<String>) {
+        if let Some(lang) = self.language.as_ref().or(self.language_name.as_ref()) {
+            if !languages.contains(lang) {
+                languages.push(lang.clone());
+            }
+        }
+        for a in self.ancestors.iter() {
+            a.add_languages(languages);
+        }
+    }
+}
+
+/// The pronunciation of a form of an entry
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+pub struct Pronunciation {
+    pub form : String,
+    /// The normalized IPA transcription
+    pub ipa : String
+}
+
+/// An entry found by its pronunciation
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct PronMatch {
+    #[serde(flatten)]
+    pub entry : Entry,
+    /// The form with the closest pronunciation
+    pub form : String,
+    pub ipa : String,
+    pub distance : f64
+}
+
+impl PronMatch {
+    pub fn new(entry : Entry, pron : Pronunciation, distance : f64) -> PronMatch {
+        PronMatch { entry, form : pron.form, ipa : pron.ipa, distance }
+    }
+
+    /// The pronunciation closest to `ipa` (normalized) with its distance, 
+    /// if it is within `distance`
+    pub fn closest(ipa : &str, prons : Vec<Pronunciation>, distance : f64) -> Option<(Pronunciation, f64)> {
+        prons.into_iter()
+            .map(|p| { let d = ipa::distance(ipa, &p.ipa); (p, d) })
+            .filter(|(_, d)| *d <= distance)
+            .min_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+    }
+
+    /// Sort matches by distance and then lemma and take a page of them
+    pub fn rank(mut matches : Vec<PronMatch>, offset : Option<usize>, limit : Option<usize>) -> Vec<PronMatch> {
+        matches.sort_by(|x, y| x.distance.partial_cmp(&y.distance).unwrap()
+            .then_with(|| x.entry.lemma.cmp(&y.entry.lemma))
+            .then_with(|| x.entry.id.cmp(&y.entry.id)));
+        matches.into_iter().skip(offset.unwrap_or(0)).take(limit.unwrap_or(usize::MAX)).collect()
+    }
+}
+
+/// Check if `text` contains `word` (which may be several words) as a whole
+/// word, ignoring case
+pub fn contains_word(text : &str, word : &str) -> bool {
+    let text = text.to_lowercase();
+    let word = word.trim().to_lowercase();
+    !word.is_empty() && text.match_indices(&word).any(|(i, _)|
+        !text[..i].chars().next_back().map(|c| c.is_alphanumeric()).unwrap_or(false) &&
+        !text[i + word.len()..].chars().next().map(|c| c.is_alphanumeric()).unwrap_or(false))
+}
+
+/// The paradigm of an entry: the forms it has or that are generated by the
+/// rules of its morphological pattern
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+pub struct Paradigm {
+    pub id : String,
+    pub lemma : String,
+    #[serde(rename = "morphologicalPattern")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub pattern : Option<String>,
+    pub forms : Vec<ParadigmForm>
+}
+
+/// A form of an entry with its grammatical features, e.g., `number=plural`
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ParadigmForm {
+    pub written_rep : String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[serde(default)]
+    pub features : BTreeMap<String, String>,
+    /// The rule the form is generated by
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub rule : Option<String>
+}
+
+impl ParadigmForm {
+    /// Check if this form has all of the given features, compared as UD
+    /// features
+    pub fn has_features(&self, features : &[(String, String)]) -> bool {
+        let own = ud::features(&self.features);
+        features.iter().map(|(k, v)| ud::feature(k, v)).all(|(k, v)| own.get(&k) == Some(&v))
+    }
+}
+
+/// An analysis of a form: the entry it is a form of and its features as a
+/// UD feature string
+#[derive(Clone,Debug,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormAnalysis {
+    #[serde(flatten)]
+    pub entry : Entry,
+    pub written_rep : String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default)]
+    pub features : String
+}
+
+impl FormAnalysis {
+    /// The analyses of a form by the forms of a paradigm. The lemma
+    /// without features is analysed as itself if it is not in the paradigm
+    pub fn from_paradigm(entry : Entry, paradigm : &Paradigm, form : &str) -> Vec<FormAnalysis> {
+        let mut analyses : Vec<FormAnalysis> = Vec::new();
+        for f in paradigm.forms.iter().filter(|f| f.written_rep == form) {
+            let features = ud::format(&ud::features(&f.features));
+            if !analyses.iter().any(|a| a.features == features) {
+                analyses.push(FormAnalysis { entry : entry.clone(), written_rep : form.to_string(), features });
+            }
+        }
+        if analyses.is_empty() && paradigm.lemma == form {
+            analyses.push(FormAnalysis { entry, written_rep : form.to_string(), features : String::new() });
+        }
+        analyses
+    }
+
+    /// Sort analyses by lemma and take a page of them
+    pub fn rank(mut analyses : Vec<FormAnalysis>, offset : Option<usize>, limit : Option<usize>) -> Vec<FormAnalysis> {
+        analyses.sort_by(|x, y| x.entry.lemma.cmp(&y.entry.lemma)
+            .then_with(|| x.entry.id.cmp(&y.entry.id)));
+        analyses.into_iter().skip(offset.unwrap_or(0)).take(limit.unwrap_or(usize::MAX)).collect()
+    }
+}
+
+impl Paradigm {
+    pub fn from_json(entry : &JsonEntry) -> Paradigm {
+        Paradigm {
+            id : entry.id.clone(),
+            lemma : entry.canonical_form.written_rep.clone(),
+            pattern : entry.morphological_pattern.clone(),
+            forms : std::iter::once(&entry.canonical_form).chain(entry.other_form.iter().flatten())
+                .map(|f| ParadigmForm { written_rep : f.written_rep.clone(), 
+                    features : f.features.as_ref().and_then(|x| ud::parse(x).ok()).unwrap_or_default().into_iter().collect(),
+                    rule : None })
+                .collect()
+        }
+    }
+
+    pub fn from_tei(id : &str, lemma : &str, tei : &TeiEntry) -> Paradigm {
+        Paradigm {
+            id : id.to_string(),
+            lemma : lemma.to_string(),
+            pattern : None,
+            forms : tei.forms.iter()
+                .map(|f| ParadigmForm { written_rep : f.written_rep.clone(), features : ud::features(&f.features), rule : None })
+                .collect()
+        }
+    }
+
+    pub fn from_ontolex(id : &str, lemma : &str, ontolex : &OntoLexEntry) -> Paradigm {
+        Paradigm {
+            id : id.to_string(),
+            lemma : lemma.to_string(),
+            pattern : ontolex.pattern.clone(),
+            forms : ontolex.forms.iter().map(|f| ParadigmForm { features : ud::features(&f.features), ..f.clone() }).collect()
+        }
+    }
+}
+
+/// A component of a multi-word expression or compound (from the OntoLex
+/// decomp module)
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct Component {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub id : Option<String>,
+    /// The entry the component corresponds to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub entry : Option<String>,
+    /// The lemma of that entry, or the label of the component
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub lemma : Option<String>
+}
+
+/// Check if a multi-word expression contains `lemma`, either as one of its
+/// components or, if no components are given, as a word of its own lemma
+pub fn mwe_contains(mwe : &str, components : &[Component], lemma : &str) -> bool {
+    let lemma = lemma.trim().to_lowercase();
+    if components.is_empty() {
+        mwe.trim().to_lowercase() != lemma && contains_word(mwe, &lemma)
+    } else {
+        components.iter().any(|c| c.lemma.as_ref().map(|l| l.trim().to_lowercase() == lemma).unwrap_or(false))
+    }
+}
+
+/// An equivalent of an entry in another language, from a translation of
+/// one of its senses
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+pub struct Equivalent {
+    /// The sense that is translated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub sense : Option<String>,
+    pub language : String,
+    pub text : String
+}
+
+impl Equivalent {
+    /// Check if this is `word` in `language`, which also matches any more
+    /// specific language tag, e.g., `de` matches `de-AT`
+    pub fn matches(&self, language : &str, word : &str) -> bool {
+        self.text == word && (self.language.eq_ignore_ascii_case(language) ||
+            (self.language.len() > language.len() && 
+             self.language.is_char_boundary(language.len()) &&
+             self.language[..language.len()].eq_ignore_ascii_case(language) &&
+             self.language[language.len()..].starts_with('-')))
+    }
+}
+
+/// The target languages of a dictionary: the languages its entries are
+/// translated into, or the source language if it is monolingual
+pub fn target_languages<I : IntoIterator<Item=String>>(source_language : &str, languages : I) -> Vec<String> {
+    let mut target = Vec::new();
+    for language in languages {
+        if language != source_language && !target.contains(&language) {
+            target.push(language);
         }
     }
+    if target.is_empty() {
+        target.push(source_language.to_string());
+    }
+    target
+}
+
+/// An entry found by a translation
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct TranslationMatch {
+    #[serde(flatten)]
+    pub entry : Entry,
+    /// The sense that is translated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sense : Option<String>,
+    pub translation : String
+}
+
+impl TranslationMatch {
+    /// Sort matches by lemma and take a page of them
+    pub fn rank(mut matches : Vec<TranslationMatch>, offset : Option<usize>, limit : Option<usize>) -> Vec<TranslationMatch> {
+        matches.sort_by(|x, y| x.entry.lemma.cmp(&y.entry.lemma)
+            .then_with(|| x.entry.id.cmp(&y.entry.id)));
+        matches.into_iter().skip(offset.unwrap_or(0)).take(limit.unwrap_or(usize::MAX)).collect()
+    }
+}
+
+/// How a data file is read into dictionaries
+pub struct ParseOptions<'a> {
+    /// The id of the dictionary, or of the dictionary of the entries that
+    /// the file does not place in one
+    pub id : &'a str,
+    pub release : Release,
+    pub genre : Vec<Genre>,
+    pub config : &'a Config,
+    /// Do not load the data if any errors are found
+    pub strict : bool
+}
+
+/// Entries produced one at a time, each with the id of its dictionary, so
+/// that a large dictionary does not need to be held in memory
+pub type EntryStream<'a> = dyn Iterator<Item=Result<(String, EntryContent), BackendError>> + 'a;
+
+/// Collect a stream of entries by dictionary
+pub fn collect_entries(entries : &mut EntryStream) -> Result<HashMap<String, Vec<EntryContent>>, BackendError> {
+    let mut result = HashMap::new();
+    for entry in entries {
+        let (dict_id, entry) = entry?;
+        result.entry(dict_id).or_insert_with(Vec::new).push(entry);
+    }
+    Ok(result)
 }
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
@@ -917,11 +1820,138 @@ pub struct JsonEntry {
     pub morphological_pattern : Option<String>,
     pub etymology : Option<String>,
     pub senses : Vec<Sense>,
-    pub usage : Option<String>
+    pub usage : Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub media : Vec<Media>,
+    /// The constituents of a multi-word expression, in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub components : Vec<Component>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub frequencies : Vec<Frequency>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub attestations : Vec<Attestation>,
+    /// The entries a multi-word expression contains
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub subterms : Vec<Component>,
+    /// The part-of-speech tags of the source
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub tags : Vec<SourceTag>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub homograph : Option<u32>
 }
 
-#[derive(Clone,Debug,Serialize,Deserialize)]
+impl JsonEntry {
+    /// Convert the structured content of a TEI entry
+    pub fn from_tei(id : &str, lemma : &str, pos : &[PartOfSpeech],
+                    variants : &[String], tei : &TeiEntry) -> JsonEntry {
+        let mut senses = Vec::new();
+        flatten_senses(&tei.senses, &mut senses);
+        let pron = |form : &str| tei.forms.iter()
+            .filter(|f| f.written_rep == form)
+            .flat_map(|f| f.pronunciations.iter())
+            .next().cloned();
+        let features = |form : &str| tei.forms.iter()
+            .find(|f| f.written_rep == form && !f.features.is_empty())
+            .map(|f| ud::format(&ud::features(&f.features)));
+        JsonEntry {
+            context : "http://lexinfo.net/jsonld/3.0/content.json".to_string(),
+            id : id.to_string(),
+            entry_type : Type::LexicalEntry,
+            canonical_form : Form { written_rep : lemma.to_string(), phonetic_rep : pron(lemma), features : features(lemma) },
+            part_of_speech : pos.first().map(JsonPartOfSpeech::from_pos).unwrap_or(JsonPartOfSpeech::Other),
+            other_form : if variants.is_empty() {
+                None
+            } else {
+                Some(variants.iter().map(|v| Form { written_rep : v.to_string(), phonetic_rep : pron(v), features : features(v) }).collect())
+            },
+            morphological_pattern : None,
+            etymology : None,
+            senses,
+            usage : None,
+            media : tei.media.clone(),
+            components : Vec::new(),
+            frequencies : tei.frequencies.clone(),
+            attestations : Vec::new(),
+            subterms : Vec::new(),
+            tags : tei.tags.clone(),
+            homograph : tei.homograph
+        }
+    }
+
+    /// Convert the structured content of an OntoLex entry
+    pub fn from_ontolex(id : &str, lemma : &str, pos : &[PartOfSpeech],
+                        variants : &[String], ontolex : &OntoLexEntry) -> JsonEntry {
+        let pron = |form : &str| ontolex.pronunciations.iter()
+            .find(|p| p.form == form)
+            .map(|p| p.ipa.clone());
+        let features = |form : &str| ontolex.forms.iter()
+            .find(|f| f.written_rep == form && !f.features.is_empty())
+            .map(|f| ud::format(&ud::features(&f.features)));
+        JsonEntry {
+            context : "http://lexinfo.net/jsonld/3.0/content.json".to_string(),
+            id : id.to_string(),
+            entry_type : ontolex.entry_type.clone(),
+            canonical_form : Form { written_rep : lemma.to_string(), phonetic_rep : pron(lemma), features : features(lemma) },
+            part_of_speech : pos.first().map(JsonPartOfSpeech::from_pos).unwrap_or(JsonPartOfSpeech::Other),
+            other_form : if variants.is_empty() {
+                None
+            } else {
+                Some(variants.iter().map(|v| Form { written_rep : v.to_string(), phonetic_rep : pron(v), features : features(v) }).collect())
+            },
+            morphological_pattern : ontolex.pattern.clone(),
+            etymology : None,
+            senses : ontolex.senses.clone(),
+            usage : ontolex.usage.clone(),
+            media : ontolex.media.clone(),
+            components : ontolex.components.clone(),
+            frequencies : ontolex.frequencies.clone(),
+            attestations : ontolex.attestations.clone(),
+            subterms : ontolex.subterms.clone(),
+            tags : ontolex.tags.clone(),
+            homograph : ontolex.homograph
+        }
+    }
+
+    /// Link the media in the store to where they are served
+    pub fn link_media(mut self, dictionary : &str) -> JsonEntry {
+        for m in self.media.iter_mut() {
+            if let Some(ref id) = m.id {
+                m.url = format!("/media/{}/{}", dictionary, id);
+            }
+        }
+        self
+    }
+}
+
+fn flatten_senses(tei_senses : &[TeiSense], senses : &mut Vec<Sense>) {
+    for sense in tei_senses.iter() {
+        senses.push(Sense {
+            id : sense.id.clone(),
+            definition : if sense.definitions.is_empty() { None } else { Some(sense.definitions.join("; ")) },
+            reference : None,
+            usage : if sense.usage.is_empty() { 
+                None 
+            } else { 
+                Some(sense.usage.iter().map(|u| u.label.as_str()).collect::<Vec<&str>>().join("; ")) 
+            },
+            examples : sense.examples.clone(),
+            frequencies : sense.frequencies(),
+            attestations : Vec::new()
+        });
+        flatten_senses(&sense.senses, senses);
+    }
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq,Default)]
 pub enum Type {
+    #[default]
     LexicalEntry,
     Word,
     MultiWordExpression,
@@ -932,7 +1962,21 @@ pub enum Type {
 #[serde(rename_all = "camelCase")]
 pub struct Form {
     written_rep : String,
-    phonetic_rep : Option<String>
+    phonetic_rep : Option<String>,
+    /// The grammatical features of the form as a UD feature string, e.g.,
+    /// `Case=Gen|Number=Plur`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    features : Option<String>
+}
+
+impl Form {
+    fn pronunciation(&self) -> Option<Pronunciation> {
+        self.phonetic_rep.as_ref().map(|p| Pronunciation {
+            form : self.written_rep.clone(),
+            ipa : ipa::normalize(p)
+        })
+    }
 }
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
@@ -976,11 +2020,48 @@ impl JsonPartOfSpeech {
             JsonPartOfSpeech::Other => PartOfSpeech::X
         }
     }
+
+    pub fn from_pos(pos : &PartOfSpeech) -> JsonPartOfSpeech {
+        match pos {
+            PartOfSpeech::ADJ => JsonPartOfSpeech::Adjective,
+            PartOfSpeech::ADP => JsonPartOfSpeech::Adposition,
+            PartOfSpeech::ADV => JsonPartOfSpeech::Adverb,
+            PartOfSpeech::AUX => JsonPartOfSpeech::Auxiliary,
+            PartOfSpeech::CCONJ => JsonPartOfSpeech::CoordinatingConjunction,
+            PartOfSpeech::DET => JsonPartOfSpeech::Determiner,
+            PartOfSpeech::INTJ => JsonPartOfSpeech::Interjection,
+            PartOfSpeech::NOUN => JsonPartOfSpeech::CommonNoun,
+            PartOfSpeech::NUM => JsonPartOfSpeech::Numeral,
+            PartOfSpeech::PART => JsonPartOfSpeech::Particle,
+            PartOfSpeech::PROPN => JsonPartOfSpeech::ProperNoun,
+            PartOfSpeech::PUNCT => JsonPartOfSpeech::Punctuation,
+            PartOfSpeech::SCONJ => JsonPartOfSpeech::SubordinatingConjunction,
+            PartOfSpeech::SYM => JsonPartOfSpeech::Symbol,
+            PartOfSpeech::VERB => JsonPartOfSpeech::Verb,
+            PartOfSpeech::PRON | PartOfSpeech::X => JsonPartOfSpeech::Other
+        }
+    }
 }
 
-#[derive(Clone,Debug,Serialize,Deserialize)]
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
 pub struct Sense {
-    definition : Option<String>,
-    reference : Option<String>
+    #[serde(rename="@id")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub id : Option<String>,
+    pub definition : Option<String>,
+    pub reference : Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub usage : Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub examples : Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub frequencies : Vec<Frequency>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub attestations : Vec<Attestation>
 }
 
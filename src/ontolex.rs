@@ -1,11 +1,21 @@
-use std::io::Read;
-use crate::model::{Release, Genre, Dictionary, EntryContent, PartOfSpeech,BackendError,Agent};
+use std::io::{Read, BufReader};
+use crate::model::{Release, Genre, ParseOptions, Dictionary, EntryContent, EntryStream, PartOfSpeech,BackendError,Agent,Etymology,Etymon,Pronunciation,Equivalent,target_languages,Sense,Type,Component,Frequency,Attestation,ParadigmForm,SourceTag};
 use crate::BackendImpl;
-use std::collections::HashMap;
-use std::iter::Peekable;
-use crate::rdf::turtle::parse_turtle;
+use std::collections::{HashMap, HashSet, BTreeMap};
+use std::path::Path;
+use crate::rdf::turtle::TurtleReader;
+use crate::rdf::rdfxml::RdfXmlReader;
+use crate::rdf::ntriples::{NTriplesParser, NTriplesParserError};
+use crate::rdf::jsonld::{self, JsonLdOptions};
+use crate::rdf::graph::Graph;
 use crate::rdf::model::{NamedNode,Value,Resource,Triple,Namespace,Literal};
 use crate::config::Config;
+use crate::ipa;
+use crate::media::Media;
+use crate::tei::{TeiRelations, RelatedEntry, ValidationReport, Severity};
+use regex::Regex;
+use crate::ids::IdGenerator;
+use xml::common::TextPosition;
 
 fn make_id(s : &str) -> String {
     let e1 : Vec<&str> = s.split("#").collect();
@@ -21,54 +31,147 @@ fn make_id(s : &str) -> String {
     }
 }
 
-pub fn parse<R : Read, F>(mut input : R, release : Release,
-    genre : Vec<Genre>, cfg : &Config, id : &str, foo : F) -> Result<BackendImpl,BackendError>
-    where F : FnOnce(Release, HashMap<String, Dictionary>, HashMap<String, Vec<EntryContent>>) -> Result<BackendImpl,BackendError> {
-        let mut content = String::new();
-        input.read_to_string(&mut content)?;
-        parse_str(&content, release, genre, cfg, id, foo)
+/// Read Turtle. The document is parsed as it is read into a graph that is
+/// kept on disk, so neither the text nor the triples are held in memory
+pub fn parse<R : Read, F>(input : R, options : ParseOptions,
+    report : &mut ValidationReport, foo : F) -> Result<BackendImpl,BackendError>
+    where F : FnOnce(Release, HashMap<String, Dictionary>, &mut EntryStream) -> Result<BackendImpl,BackendError> {
+        let mut error = None;
+        let graph = Graph::new(until_error(TurtleReader::new(input), &mut error))?;
+        if let Some(e) = error {
+            return Err(e.into());
+        }
+        parse_graph(graph, options, report, foo)
+}
+
+pub fn parse_rdf_xml<R : Read, F>(input : R, options : ParseOptions,
+    report : &mut ValidationReport, foo : F) -> Result<BackendImpl,BackendError>
+    where F : FnOnce(Release, HashMap<String, Dictionary>, &mut EntryStream) -> Result<BackendImpl,BackendError> {
+        let mut error = None;
+        let graph = Graph::new(until_error(RdfXmlReader::new(input)?, &mut error))?;
+        if let Some(e) = error {
+            return Err(e.into());
+        }
+        parse_graph(graph, options, report, foo)
+}
+
+/// Read N-Triples or N-Quads line by line. Lines that cannot be parsed are
+/// reported as errors and skipped
+pub fn parse_ntriples<R : Read, F>(input : R, options : ParseOptions,
+    report : &mut ValidationReport, foo : F) -> Result<BackendImpl,BackendError>
+    where F : FnOnce(Release, HashMap<String, Dictionary>, &mut EntryStream) -> Result<BackendImpl,BackendError> {
+        let mut error = None;
+        let triples = NTriplesParser::new(BufReader::new(input)).filter_map(|result| {
+            match result {
+                Ok(triple) => Some(Ok(triple)),
+                Err(NTriplesParserError::Syntax(line, msg)) => {
+                    report.add(Severity::Error, None, TextPosition { row : line.saturating_sub(1) as u64, column : 0 }, "syntax",
+                        format!("Skipping line: {}", msg));
+                    None
+                },
+                Err(e) => Some(Err(e))
+            }
+        });
+        let graph = Graph::new(until_error(triples, &mut error))?;
+        if let Some(e) = error {
+            return Err(e.into());
+        }
+        parse_graph(graph, options, report, foo)
+}
+
+/// Read a JSON-LD document. Contexts are only loaded from local files, either
+/// as given in the `jsonldContexts` configuration or relative to the document
+pub fn parse_json_ld<F>(doc : &serde_json::Value, doc_dir : Option<&Path>,
+    options : ParseOptions, report : &mut ValidationReport, foo : F) -> Result<BackendImpl,BackendError>
+    where F : FnOnce(Release, HashMap<String, Dictionary>, &mut EntryStream) -> Result<BackendImpl,BackendError> {
+        let mut jsonld_options = JsonLdOptions::new();
+        if let Some(ref contexts) = options.config.jsonld_contexts {
+            jsonld_options.contexts = contexts.clone();
+        }
+        jsonld_options.document_dir = doc_dir.map(|d| d.to_path_buf());
+        let mut error = None;
+        let graph = Graph::new(until_error(jsonld::JsonLdReader::new(doc, &jsonld_options)?, &mut error))?;
+        if let Some(e) = error {
+            return Err(e.into());
+        }
+        parse_graph(graph, options, report, foo)
+}
+
+#[cfg(test)]
+pub fn parse_str<F>(content : &str, options : ParseOptions,
+    report : &mut ValidationReport, foo : F) -> Result<BackendImpl,BackendError>
+    where F : FnOnce(Release, HashMap<String, Dictionary>, &mut EntryStream) -> Result<BackendImpl,BackendError> {
+        parse(content.as_bytes(), options, report, foo)
+}
+
+/// Pass on triples until the first error, which is kept in `error`
+fn until_error<'a, I, E>(triples : I, error : &'a mut Option<E>) -> impl Iterator<Item=Triple> + 'a
+    where I : Iterator<Item=Result<Triple, E>> + 'a {
+    triples.map_while(move |result| match result {
+        Ok(triple) => Some(triple),
+        Err(e) => {
+            *error = Some(e);
+            None
+        }
+    })
 }
 
-pub fn parse_str<F>(content : &str, release : Release,
-    genre : Vec<Genre>, cfg : &Config, id : &str, foo : F) -> Result<BackendImpl,BackendError>
-    where F : FnOnce(Release, HashMap<String, Dictionary>, HashMap<String, Vec<EntryContent>>) -> Result<BackendImpl,BackendError> {
-        let triples = parse_turtle(content)?;
+/// Build the dictionaries from a graph. The dictionaries are read first and
+/// the entries are then extracted one at a time as `foo` stores them. If
+/// `strict` is set nothing is stored if any errors have been reported
+fn parse_graph<F>(mut graph : Graph, options : ParseOptions,
+    report : &mut ValidationReport, foo : F) -> Result<BackendImpl,BackendError>
+    where F : FnOnce(Release, HashMap<String, Dictionary>, &mut EntryStream) -> Result<BackendImpl,BackendError> {
+        let ParseOptions { id, release, genre, config : cfg, strict } = options;
+        name_blank_entries(&mut graph, cfg, report)?;
         let mut dictionary = HashMap::new();
-        let mut entries_by_uri = HashMap::new();
+        let mut entry_uris = Vec::new();
         let mut entry2dict = HashMap::new();
 
-        let mut iter = triples.iter();
-        while let Some(Triple(ref subj, ref pred, ref obj)) = iter.next() {
-            if let Resource::Named(r) = subj {
-                if *pred == NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type") && 
-                    *obj == Value::make_uri("http://www.w3.org/ns/lemon/lime#Lexicon") {
-                    
-                    let dict_triples = iter.clone().take_while(|t| 
-                        t.1 != NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"))
-                        .collect();
-                    let dict = read_dictionary(release.clone(), genre.clone(), &dict_triples)?;
+        // Lexicographic resources with a language are dictionaries of the
+        // entries they describe that are not in a lexicon
+        let mut resources = HashMap::new();
+        for subj in graph.subjects() {
+            let subj = subj?;
+            if let Resource::Named(ref r) = subj {
+                if graph.has_type(&subj, &Value::make_uri("http://www.w3.org/ns/lemon/lime#Lexicon"))? {
+                    let dict_triples = graph.closure(&subj, is_lexicon_component)?;
+                    let dict = read_dictionary(release.clone(), genre.clone(), &dict_triples.iter().collect())?;
                     dictionary.insert(make_id(&r.uri()), dict);
-                } else if *pred == NamedNode::make_uri("http://www.w3.org/ns/lemon/lime#entry") {
+                } else if graph.has_type(&subj, &Value::make_uri(&format!("{}LexicographicResource", LEXICOG)))? &&
+                    !graph.objects(&subj, &NamedNode::make_uri("http://www.w3.org/ns/lemon/lime#language"))?.is_empty() {
+                    let dict_triples = graph.closure(&subj, is_lexicon_component)?;
+                    let dict = read_dictionary(release.clone(), genre.clone(), &dict_triples.iter().collect())?;
+                    resources.insert(r.uri(), dict);
+                }
+                for obj in graph.objects(&subj, &NamedNode::make_uri("http://www.w3.org/ns/lemon/lime#entry"))? {
                     if let Value::Resource(Resource::Named(r2)) = obj {
                         entry2dict.insert(r2.uri(), make_id(&r.uri()));
                     }
-                } else if *pred == NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type") && 
-                    is_lexical_entry_uri(&obj) {
-                    let mut entry_triples : Vec<&Triple> = iter.clone().take_while(|t|
-                        t.1 != NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type") ||
-                        !is_lexical_entry_uri(&t.2)).collect();
-                    let t2 = Triple(subj.clone(), pred.clone(), obj.clone());
-                    entry_triples.insert(0, &t2);
-                    let e = add_entries(&r.uri(), 
-                            &mut entry_triples, cfg)?;
-                    entries_by_uri.insert(r.uri(), e);
-                    entry2dict.entry(r.uri().clone())
-                        .or_insert(id.to_string());
+                }
+                if graph.objects(&subj, &NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"))?
+                    .iter().any(is_lexical_entry_uri) {
+                    entry_uris.push(r.uri());
+                }
+            }
+        }
 
+        let lexicog = Lexicog::new(&graph, &mut entry_uris)?;
+        for (resource, entries) in lexicog.resource_entries.iter() {
+            if let Some(dict) = resources.remove(resource) {
+                let mut used = false;
+                for entry in entries.iter() {
+                    if !entry2dict.contains_key(entry) {
+                        entry2dict.insert(entry.clone(), make_id(resource));
+                        used = true;
+                    }
+                }
+                if used {
+                    dictionary.insert(make_id(resource), dict);
                 }
             }
         }
-        
+
         if !dictionary.contains_key(id) {
             dictionary.insert(id.to_owned(), Dictionary::new(
                 release.clone(),
@@ -79,15 +182,83 @@ pub fn parse_str<F>(content : &str, release : Release,
                 Vec::new(), Vec::new()));
         }
 
-        let mut entries = HashMap::new();
-        for (entry_uri, dict_uri) in entry2dict.into_iter() {
-            entries.entry(dict_uri)
-                .or_insert_with(|| Vec::new())
-                .push(entries_by_uri.get(&entry_uri)
-                    .ok_or(BackendError::OntoLex(format!("An entry <{}> is referred to as a member of a dictionary but was not found in the file", entry_uri)))?.clone());
+        let known : HashSet<&String> = entry_uris.iter().collect();
+        if let Some(entry_uri) = entry2dict.keys().find(|e| !known.contains(e)) {
+            return Err(BackendError::OntoLex(format!("An entry <{}> is referred to as a member of a dictionary but was not found in the file", entry_uri)));
+        }
+
+        // The target languages of each dictionary are those its entries are translated into
+        let languages = entry2dict.iter().filter_map(|(e, d)| 
+            dictionary.get(d).map(|d| (e.clone(), d.source_language.clone()))).collect();
+        let translations = Translations::new(&graph, languages)?;
+        let morphology = Morphology::new(&graph, report)?;
+        let mut dict_translations : HashMap<String, Vec<String>> = HashMap::new();
+        for entry_uri in entry_uris.iter() {
+            let dict_id = entry2dict.get(entry_uri).cloned().unwrap_or_else(|| id.to_string());
+            dict_translations.entry(dict_id).or_insert_with(Vec::new)
+                .extend(extract_translations(entry_uri, &graph, &translations)?.into_iter().map(|t| t.language));
+        }
+        for (dict_id, dict) in dictionary.iter_mut() {
+            dict.target_language = target_languages(&dict.source_language, 
+                dict_translations.remove(dict_id).unwrap_or_default());
+        }
+
+        let mut entries = entry_uris.iter().map(|entry_uri| {
+            let mut entry_triples = graph.closure(&Resource::make_uri(entry_uri), is_entry_component)?;
+            entry_triples.extend(lexicog.triples(entry_uri, &graph)?);
+            let e = add_entries(entry_uri, &mut entry_triples.iter().collect(), &graph, &known, &translations, &lexicog, &morphology, cfg)?;
+            let dict_id = entry2dict.get(entry_uri).cloned().unwrap_or_else(|| id.to_string());
+            Ok((dict_id, e))
+        });
+
+        if strict && report.errors() > 0 {
+            return Err(BackendError::OntoLex(format!("Not loading the data as it has {} error(s)", report.errors())));
         }
+        foo(release, dictionary, &mut entries)
+}
+
+/// The namespace of the URIs given to entries that are blank nodes
+const GENID : &str = "https://elexis.eu/.well-known/genid/";
 
-        foo(release, dictionary, entries)
+/// Give the lexical entries that are blank nodes a URI, so they have an id
+/// by the configured strategy. Ids that were already used are reported
+fn name_blank_entries(graph : &mut Graph, cfg : &Config, report : &mut ValidationReport) -> Result<(), BackendError> {
+    let rdf_type = NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type");
+    let mut used = HashSet::new();
+    let mut blank = Vec::new();
+    for subj in graph.subjects() {
+        let subj = subj?;
+        if graph.objects(&subj, &rdf_type)?.iter().any(is_lexical_entry_uri) {
+            match subj {
+                Resource::Named(ref n) => { used.insert(make_id(&n.uri())); },
+                Resource::BlankNode(_) => blank.push(subj)
+            }
+        }
+    }
+    let mut ids = IdGenerator::new(cfg, used);
+    for subj in blank {
+        let lemma = canonical_written_rep(&subj, graph)?.unwrap_or_default();
+        let pos_property = NamedNode::make_uri(&cfg.pos_property.clone().unwrap_or(format!("{}partOfSpeech", LEXINFO)));
+        let pos = graph.objects(&subj, &pos_property)?.iter()
+            .filter_map(value_text)
+            .find_map(|tag| cfg.map_tag(&tag).and_then(|m| m.pos));
+        // The blank nodes of the description are not part of its content
+        let mut content : Vec<String> = graph.closure(&subj, is_entry_component)?.iter()
+            .map(|t| format!("{} {}", t.1.uri(), value_text(&t.2).unwrap_or_default()))
+            .collect();
+        content.sort();
+        let homograph = text_values(&subj, &[&format!("{}homographNumber", LEXINFO)], graph)?.iter()
+            .find_map(|n| n.trim().parse().ok());
+        let (id, collision) = ids.generate(&lemma, pos.as_ref(), homograph, &content.join("\n"));
+        if !graph.rename(&subj, Resource::make_uri(&format!("{}{}", GENID, id)))? {
+            report.add(Severity::Error, Some(&id), TextPosition::new(), "entry-id",
+                format!("Could not name the entry {} as its URI is already used", lemma));
+        } else if let Some(collision) = collision {
+            report.add(Severity::Warning, Some(&id), TextPosition::new(), "entry-id-collision",
+                format!("Generated id {} is already used", collision));
+        }
+    }
+    Ok(())
 }
 
 fn is_lexical_entry_uri(value : &Value) -> bool {
@@ -97,6 +268,38 @@ fn is_lexical_entry_uri(value : &Value) -> bool {
     *value == Value::make_uri("http://www.w3.org/ns/lemon/ontolex#Affix")
 }
 
+/// Named resources that are described as part of a lexical entry
+const ENTRY_COMPONENTS : [&str; 5] = [
+    "http://www.w3.org/ns/lemon/ontolex#canonicalForm",
+    "http://www.w3.org/ns/lemon/ontolex#otherForm",
+    "http://www.w3.org/ns/lemon/ontolex#lexicalForm",
+    "http://www.w3.org/ns/lemon/ontolex#sense",
+    "http://www.w3.org/ns/lemon/decomp#constituent"
+];
+
+/// Named resources that are described as part of a lexicon
+const LEXICON_COMPONENTS : [&str; 5] = [
+    "http://purl.org/dc/terms/creator",
+    "http://purl.org/dc/terms/publisher",
+    "http://purl.org/dc/terms/contributor",
+    "http://purl.org/dc/terms/mediator",
+    "http://purl.org/dc/terms/rightsHolder"
+];
+
+fn is_entry_component(triple : &Triple) -> bool {
+    match triple.2 {
+        Value::Resource(Resource::BlankNode(_)) => true,
+        _ => ENTRY_COMPONENTS.iter().any(|p| triple.1 == NamedNode::make_uri(p))
+    }
+}
+
+fn is_lexicon_component(triple : &Triple) -> bool {
+    match triple.2 {
+        Value::Resource(Resource::BlankNode(_)) => true,
+        _ => LEXICON_COMPONENTS.iter().any(|p| triple.1 == NamedNode::make_uri(p))
+    }
+}
+
 fn read_dictionary(release : Release, genre : Vec<Genre>,
     triples : &Vec<&Triple>) -> Result<Dictionary, BackendError> {
     let mut source_language : Option<String> = None;
@@ -210,13 +413,919 @@ fn make_agents(uris : &Vec<Resource>, agent_names : &HashMap<Resource, &str>,
 
 }
 
-fn add_entries(id : &str, entry_triples : &mut Vec<&Triple>,
-    cfg : &Config) -> Result<EntryContent,BackendError> {
+/// The structured content of an OntoLex entry
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct OntoLexEntry {
+    #[serde(default)]
+    pub etymology : Etymology,
+    #[serde(default)]
+    pub pronunciations : Vec<Pronunciation>,
+    #[serde(default)]
+    pub media : Vec<Media>,
+    #[serde(default)]
+    pub translations : Vec<Equivalent>,
+    #[serde(default)]
+    pub entry_type : Type,
+    #[serde(default)]
+    pub senses : Vec<Sense>,
+    #[serde(default)]
+    pub usage : Option<String>,
+    /// The lexicographic entry (in the lexicog module) the entry is described in
+    #[serde(default)]
+    pub structure : Option<LexicogComponent>,
+    /// The entries described in the same lexicographic entry
+    #[serde(default)]
+    pub relations : TeiRelations,
+    /// The `decomp:constituent`s of the entry, in order
+    #[serde(default)]
+    pub components : Vec<Component>,
+    /// The `decomp:subterm`s of the entry
+    #[serde(default)]
+    pub subterms : Vec<Component>,
+    #[serde(default)]
+    pub frequencies : Vec<Frequency>,
+    #[serde(default)]
+    pub attestations : Vec<Attestation>,
+    /// The `morph:morphologicalPattern` of the entry
+    #[serde(default)]
+    pub pattern : Option<String>,
+    /// The forms of the entry, including those generated by the rules of its pattern
+    #[serde(default)]
+    pub forms : Vec<ParadigmForm>,
+    /// The part-of-speech tags of the entry and how they were mapped
+    #[serde(default)]
+    pub tags : Vec<SourceTag>,
+    /// The `lexinfo:homographNumber` of the entry
+    #[serde(default)]
+    pub homograph : Option<u32>
+}
+
+fn add_entries(id : &str, entry_triples : &mut Vec<&Triple>, graph : &Graph,
+    entry_uris : &HashSet<&String>, translations : &Translations, lexicog : &Lexicog, 
+    morphology : &Morphology, cfg : &Config) -> Result<EntryContent,BackendError> {
     let lemma = extract_lemma(id, entry_triples)?;
-    let pos = extract_pos(id, entry_triples, cfg);
-    let vars = extract_vars(id, entry_triples);
+    let (pos, tags) = extract_pos(id, entry_triples, cfg);
+    let mut vars = extract_vars(id, entry_triples);
+    let (pattern, mut forms) = extract_forms(id, &lemma, graph, morphology)?;
+    for form in forms.iter_mut() {
+        SourceTag::add_features(&tags, &mut form.features);
+    }
+    // Generated forms are indexed for inflected lookup
+    for form in forms.iter() {
+        if form.rule.is_some() && form.written_rep != lemma && !vars.contains(&form.written_rep) {
+            vars.push(form.written_rep.clone());
+        }
+    }
     let data = format_triples(entry_triples);
-    Ok(EntryContent::OntoLex(make_id(id), lemma, pos, vars, data))
+    let entry = OntoLexEntry {
+        etymology : extract_etymology(id, graph, entry_uris)?,
+        pronunciations : extract_pronunciations(id, graph)?,
+        media : extract_media(id, graph)?,
+        translations : extract_translations(id, graph, translations)?,
+        entry_type : extract_type(id, graph)?,
+        senses : extract_senses(id, graph)?,
+        usage : join_values(text_values(&Resource::make_uri(id), &USAGE_PROPERTIES, graph)?),
+        structure : lexicog.structure(id).cloned(),
+        relations : lexicog.relations.get(id).cloned().unwrap_or_default(),
+        components : extract_constituents(id, graph, entry_uris)?,
+        subterms : extract_subterms(id, graph, entry_uris)?,
+        frequencies : extract_frequencies(&Resource::make_uri(id), graph)?,
+        attestations : extract_attestations(&Resource::make_uri(id), graph)?,
+        homograph : text_values(&Resource::make_uri(id), &[&format!("{}homographNumber", LEXINFO)], graph)?
+            .iter().find_map(|n| n.trim().parse().ok()),
+        pattern, forms, tags
+    };
+    Ok(EntryContent::OntoLex { id : make_id(id), lemma, pos, variants : vars, content : data, entry })
+}
+
+const LEXINFO : &str = "http://www.lexinfo.net/ontology/2.0/lexinfo#";
+const ONTOLEX : &str = "http://www.w3.org/ns/lemon/ontolex#";
+const ETY : &str = "http://lari-datasets.ilc.cnr.it/lemonEty#";
+const VARTRANS : &str = "http://www.w3.org/ns/lemon/vartrans#";
+const DECOMP : &str = "http://www.w3.org/ns/lemon/decomp#";
+const FRAC : &str = "http://www.w3.org/ns/lemon/frac#";
+const MORPH : &str = "http://www.w3.org/ns/lemon/morph#";
+
+/// A rule of the morph module, with the replacements that generate a form
+/// from the lemma and the grammatical features of the form
+#[derive(Clone)]
+struct InflectionRule {
+    id : Option<String>,
+    replacements : Vec<(Regex, String)>,
+    features : BTreeMap<String, String>
+}
+
+impl InflectionRule {
+    /// Apply the first replacement whose source matches the lemma
+    fn apply(&self, lemma : &str) -> Option<String> {
+        self.replacements.iter().find(|(source, _)| source.is_match(lemma))
+            .map(|(source, target)| source.replace(lemma, target.as_str()).into_owned())
+    }
+}
+
+/// The rules of the morphological patterns of a graph
+struct Morphology {
+    rules : HashMap<Resource, Vec<InflectionRule>>
+}
+
+impl Morphology {
+    /// Read the rules with a `morph:replacement` and the
+    /// `morph:morphologicalPattern`s they belong to. The features of the
+    /// generated form are the lexinfo properties of the rule or of its
+    /// `morph:grammaticalMeaning`
+    fn new(graph : &Graph, report : &mut ValidationReport) -> Result<Morphology, BackendError> {
+        let mut morphology = Morphology { rules : HashMap::new() };
+        for subj in graph.subjects() {
+            let subj = subj?;
+            let mut replacements = Vec::new();
+            for r in graph.objects(&subj, &NamedNode::make_uri(&format!("{}replacement", MORPH)))? {
+                if let Value::Resource(r) = r {
+                    let source = text_values(&r, &[&format!("{}source", MORPH)], graph)?.into_iter().next();
+                    let target = text_values(&r, &[&format!("{}target", MORPH)], graph)?.into_iter().next().unwrap_or_default();
+                    match source.map(|s| Regex::new(&s)) {
+                        Some(Ok(source)) => replacements.push((source, replacement_target(&target))),
+                        Some(Err(e)) => report.add(Severity::Warning, None, TextPosition::new(), "morph-rule",
+                            format!("Invalid replacement in rule {}: {}", subj.to_string(), e)),
+                        None => report.add(Severity::Warning, None, TextPosition::new(), "morph-rule",
+                            format!("Replacement without morph:source in rule {}", subj.to_string()))
+                    }
+                }
+            }
+            if replacements.is_empty() {
+                continue;
+            }
+            let mut features = grammatical_features(&subj, graph)?;
+            for meaning in graph.objects(&subj, &NamedNode::make_uri(&format!("{}grammaticalMeaning", MORPH)))? {
+                if let Value::Resource(meaning) = meaning {
+                    features.extend(grammatical_features(&meaning, graph)?);
+                }
+            }
+            let rule = InflectionRule {
+                id : match subj {
+                    Resource::Named(ref n) => Some(make_id(&n.uri())),
+                    _ => None
+                },
+                replacements, features
+            };
+            for pattern in graph.objects(&subj, &NamedNode::make_uri(&format!("{}morphologicalPattern", MORPH)))? {
+                if let Value::Resource(pattern) = pattern {
+                    morphology.rules.entry(pattern).or_insert_with(Vec::new).push(rule.clone());
+                }
+            }
+        }
+        Ok(morphology)
+    }
+
+    /// Generate the forms of a lemma by the rules of a pattern
+    fn generate(&self, pattern : &Resource, lemma : &str) -> Vec<ParadigmForm> {
+        self.rules.get(pattern).into_iter().flatten().filter_map(|rule| 
+            rule.apply(lemma).map(|written_rep| ParadigmForm {
+                written_rep,
+                features : rule.features.clone(),
+                rule : rule.id.clone()
+            })).collect()
+    }
+}
+
+/// Use the `${n}` syntax for groups in a replacement, which may be given as
+/// `$n` or `\n`
+fn replacement_target(target : &str) -> String {
+    let mut result = String::new();
+    let mut chars = target.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('$', Some(d)) | ('\\', Some(d)) if d.is_ascii_digit() => {
+                result.push_str("${");
+                while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()).cloned() {
+                    result.push(d);
+                    chars.next();
+                }
+                result.push('}');
+            },
+            ('$', _) => result.push_str("$$"),
+            (c, _) => result.push(c)
+        }
+    }
+    result
+}
+
+/// The lexinfo properties of a form or rule (other than the part of speech),
+/// e.g., `lexinfo:number lexinfo:plural` as `number=plural`
+fn grammatical_features(subject : &Resource, graph : &Graph) -> Result<BTreeMap<String, String>, BackendError> {
+    let mut features = BTreeMap::new();
+    for Triple(_, pred, obj) in graph.about(subject)? {
+        let pred = pred.uri();
+        if let Some(feature) = pred.strip_prefix(LEXINFO) {
+            if feature == "partOfSpeech" {
+                continue;
+            }
+            let value = match obj {
+                Value::Resource(Resource::Named(n)) => make_id(&n.uri()),
+                Value::Literal(l) => l.string_value().to_owned(),
+                _ => continue
+            };
+            features.insert(feature.to_string(), value);
+        }
+    }
+    Ok(features)
+}
+
+/// Extract the canonical and other forms of an entry with their features,
+/// and the forms generated by the rules of its `morph:morphologicalPattern`
+fn extract_forms(id : &str, lemma : &str, graph : &Graph, morphology : &Morphology) -> Result<(Option<String>, Vec<ParadigmForm>), BackendError> {
+    let entry = Resource::make_uri(id);
+    let mut forms : Vec<ParadigmForm> = Vec::new();
+    for prop in ["canonicalForm", "otherForm"].iter() {
+        for form in graph.objects(&entry, &NamedNode::make_uri(&format!("{}{}", ONTOLEX, prop)))? {
+            if let Value::Resource(form) = form {
+                let features = grammatical_features(&form, graph)?;
+                for rep in graph.objects(&form, &NamedNode::make_uri(&format!("{}writtenRep", ONTOLEX)))? {
+                    if let Value::Literal(l) = rep {
+                        forms.push(ParadigmForm { written_rep : l.string_value().to_owned(), features : features.clone(), rule : None });
+                    }
+                }
+            }
+        }
+    }
+    let mut pattern = None;
+    for p in graph.objects(&entry, &NamedNode::make_uri(&format!("{}morphologicalPattern", MORPH)))? {
+        match p {
+            Value::Resource(p) => {
+                pattern.get_or_insert_with(|| match p {
+                    Resource::Named(ref n) => make_id(&n.uri()),
+                    ref r => r.to_string()
+                });
+                for form in morphology.generate(&p, lemma) {
+                    if !forms.iter().any(|f| f.written_rep == form.written_rep && f.features == form.features) {
+                        forms.push(form);
+                    }
+                }
+            },
+            Value::Literal(l) => { pattern.get_or_insert_with(|| l.string_value().to_owned()); }
+        }
+    }
+    Ok((pattern, forms))
+}
+const LEXICOG : &str = "http://www.w3.org/ns/lemon/lexicog#";
+
+/// A lexicographic entry (`lexicog:Entry`) or one of its components
+/// (`lexicog:LexicographicComponent`), with the lexical entries, senses or
+/// forms it describes and its components in order
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct LexicogComponent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub id : Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub describes : Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub components : Vec<LexicogComponent>
+}
+
+/// The structure of the lexicographic entries of a graph
+struct Lexicog {
+    /// The top-level `lexicog:Entry` resources, in order
+    entries : Vec<(Resource, LexicogComponent)>,
+    /// The index in `entries` of the entry each lexical entry is described in
+    described_in : HashMap<String, usize>,
+    relations : HashMap<String, TeiRelations>,
+    /// The lexical entries described by each lexicographic resource
+    resource_entries : HashMap<String, Vec<String>>
+}
+
+const RDF : &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// The members of a container (`rdf:_1`, `rdf:_2`, ...) in order
+fn container_members(subj : &Resource, graph : &Graph) -> Result<Vec<Resource>, BackendError> {
+    let mut members : Vec<(usize, Resource)> = graph.about(subj)?.into_iter()
+        .filter_map(|t| match (t.1.uri().strip_prefix(&format!("{}_", RDF)).and_then(|n| n.parse::<usize>().ok()), t.2) {
+            (Some(n), Value::Resource(r)) => Some((n, r)),
+            _ => None
+        }).collect();
+    members.sort_by_key(|m| m.0);
+    Ok(members.into_iter().map(|m| m.1).collect())
+}
+
+fn is_lexicog_component(triple : &Triple) -> bool {
+    match triple.2 {
+        Value::Resource(Resource::BlankNode(_)) => true,
+        _ => triple.1.uri().starts_with(&format!("{}_", RDF))
+    }
+}
+
+impl Lexicog {
+    /// Read the lexicographic entries, in the order of their resource (by
+    /// `rdf:_n` or else `lexicog:entry`) or of the graph, and put the lexical
+    /// entries in that order. Resources a lexicographic entry describes that
+    /// have a canonical form are lexical entries even if they are not typed
+    fn new(graph : &Graph, entry_uris : &mut Vec<String>) -> Result<Lexicog, BackendError> {
+        let entry_type = Value::make_uri(&format!("{}Entry", LEXICOG));
+        let mut lexicog = Lexicog {
+            entries : Vec::new(),
+            described_in : HashMap::new(),
+            relations : HashMap::new(),
+            resource_entries : HashMap::new()
+        };
+        let mut seen = HashSet::new();
+        let mut top = Vec::new();
+        let mut resource_of = HashMap::new();
+        for subj in graph.subjects() {
+            let subj = subj?;
+            if graph.has_type(&subj, &Value::make_uri(&format!("{}LexicographicResource", LEXICOG)))? {
+                let mut members = container_members(&subj, graph)?;
+                members.extend(graph.objects(&subj, &NamedNode::make_uri(&format!("{}entry", LEXICOG)))?
+                    .into_iter().filter_map(|o| match o { Value::Resource(r) => Some(r), _ => None }));
+                for member in members {
+                    if seen.insert(member.clone()) {
+                        if let Resource::Named(ref n) = subj {
+                            resource_of.insert(member.clone(), n.uri());
+                        }
+                        top.push(member);
+                    }
+                }
+            }
+        }
+        for subj in graph.subjects() {
+            let subj = subj?;
+            if graph.has_type(&subj, &entry_type)? && seen.insert(subj.clone()) {
+                top.push(subj);
+            }
+        }
+
+        let mut known : HashSet<String> = entry_uris.iter().cloned().collect();
+        let mut order = HashMap::new();
+        for entry in top {
+            let mut visited = HashSet::new();
+            let component = read_component(&entry, graph, &mut visited)?;
+            let index = lexicog.entries.len();
+            let mut described = Vec::new();
+            lexicog.group(&component, None, graph, &mut known, entry_uris, &mut described)?;
+            for uri in described {
+                lexicog.described_in.entry(uri.clone()).or_insert(index);
+                let n = order.len();
+                order.entry(uri.clone()).or_insert(n);
+                if let Some(resource) = resource_of.get(&entry) {
+                    lexicog.resource_entries.entry(resource.clone()).or_insert_with(Vec::new).push(uri);
+                }
+            }
+            lexicog.entries.push((entry, component));
+        }
+        // A stable sort, so entries not in a lexicographic entry keep their order at the end
+        entry_uris.sort_by_key(|e| order.get(e).cloned().unwrap_or(usize::MAX));
+        Ok(lexicog)
+    }
+
+    /// Relate the lexical entries described by a component to those described
+    /// by its ancestors and by the same component
+    fn group(&mut self, component : &LexicogComponent, parent : Option<&String>, graph : &Graph,
+             known : &mut HashSet<String>, entry_uris : &mut Vec<String>, described : &mut Vec<String>) -> Result<(), BackendError> {
+        let mut entries = Vec::new();
+        for uri in component.describes.iter() {
+            if !known.contains(uri) && !graph.objects(&Resource::make_uri(uri),
+                &NamedNode::make_uri(&format!("{}canonicalForm", ONTOLEX)))?.is_empty() {
+                known.insert(uri.clone());
+                entry_uris.push(uri.clone());
+            }
+            if known.contains(uri) && !entries.contains(uri) {
+                entries.push(uri.clone());
+            }
+        }
+        for uri in entries.iter() {
+            let relations = self.relations.entry(uri.clone()).or_insert_with(TeiRelations::default);
+            for other in entries.iter() {
+                if other != uri && !relations.homographs.contains(&make_id(other)) {
+                    relations.homographs.push(make_id(other));
+                }
+            }
+            if let Some(parent) = parent {
+                if relations.parent.is_none() {
+                    relations.parent = Some(RelatedEntry { id : make_id(parent), relation : "component".to_string() });
+                    self.relations.entry(parent.clone()).or_insert_with(TeiRelations::default)
+                        .children.push(RelatedEntry { id : make_id(uri), relation : "component".to_string() });
+                }
+            }
+            described.push(uri.clone());
+        }
+        let parent = entries.first().or(parent).cloned();
+        for c in component.components.iter() {
+            self.group(c, parent.as_ref(), graph, known, entry_uris, described)?;
+        }
+        Ok(())
+    }
+
+    /// The lexicographic entry a lexical entry is described in
+    fn structure(&self, uri : &str) -> Option<&LexicogComponent> {
+        self.described_in.get(uri).map(|i| &self.entries[*i].1)
+    }
+
+    /// The triples of the lexicographic entry a lexical entry is described in
+    fn triples(&self, uri : &str, graph : &Graph) -> Result<Vec<Triple>, BackendError> {
+        match self.described_in.get(uri) {
+            Some(i) => Ok(graph.closure(&self.entries[*i].0, is_lexicog_component)?),
+            None => Ok(Vec::new())
+        }
+    }
+}
+
+fn read_component(subj : &Resource, graph : &Graph, visited : &mut HashSet<Resource>) -> Result<LexicogComponent, BackendError> {
+    visited.insert(subj.clone());
+    let components : Vec<Resource> = container_members(subj, graph)?.into_iter()
+        .filter(|c| !visited.contains(c)).collect();
+    Ok(LexicogComponent {
+        id : match subj {
+            Resource::Named(n) => Some(make_id(&n.uri())),
+            _ => None
+        },
+        describes : graph.objects(subj, &NamedNode::make_uri(&format!("{}describes", LEXICOG)))?
+            .into_iter().filter_map(|o| match o {
+                Value::Resource(Resource::Named(n)) => Some(n.uri()),
+                _ => None
+            }).collect(),
+        components : components.into_iter()
+            .map(|c| read_component(&c, graph, visited)).collect::<Result<_, _>>()?
+    })
+}
+
+const USAGE_PROPERTIES : [&str; 2] = [
+    "http://www.w3.org/ns/lemon/ontolex#usage",
+    "http://www.lexinfo.net/ontology/2.0/lexinfo#usage"
+];
+
+/// The most specific class of an entry
+fn extract_type(id : &str, graph : &Graph) -> Result<Type, BackendError> {
+    let entry = Resource::make_uri(id);
+    Ok(if graph.has_type(&entry, &Value::make_uri(&format!("{}MultiWordExpression", ONTOLEX)))? {
+        Type::MultiWordExpression
+    } else if graph.has_type(&entry, &Value::make_uri(&format!("{}Affix", ONTOLEX)))? {
+        Type::Affix
+    } else if graph.has_type(&entry, &Value::make_uri(&format!("{}Word", ONTOLEX)))? {
+        Type::Word
+    } else {
+        Type::LexicalEntry
+    })
+}
+
+/// The text of the values of some properties, given either as literals or
+/// as resources with an `rdf:value`
+fn text_values(subject : &Resource, props : &[&str], graph : &Graph) -> Result<Vec<String>, BackendError> {
+    let rdf_value = NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#value");
+    let mut values = Vec::new();
+    for prop in props.iter() {
+        for obj in graph.objects(subject, &NamedNode::make_uri(prop))? {
+            match obj {
+                Value::Literal(l) => values.push(l.string_value().to_owned()),
+                Value::Resource(r) => for v in graph.objects(&r, &rdf_value)? {
+                    if let Value::Literal(l) = v {
+                        values.push(l.string_value().to_owned());
+                    }
+                }
+            }
+        }
+    }
+    Ok(values)
+}
+
+fn join_values(values : Vec<String>) -> Option<String> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.join("; "))
+    }
+}
+
+/// Extract the senses of an entry with their `skos:definition`s,
+/// `ontolex:reference`, usage and `lexicog:usageExample`s
+fn extract_senses(id : &str, graph : &Graph) -> Result<Vec<Sense>, BackendError> {
+    let entry = Resource::make_uri(id);
+    let mut senses = Vec::new();
+    for sense in graph.objects(&entry, &NamedNode::make_uri(&format!("{}sense", ONTOLEX)))? {
+        if let Value::Resource(sense) = sense {
+            let reference = graph.objects(&sense, &NamedNode::make_uri(&format!("{}reference", ONTOLEX)))?
+                .into_iter().find_map(|r| match r {
+                    Value::Resource(Resource::Named(n)) => Some(n.uri()),
+                    _ => None
+                });
+            senses.push(Sense {
+                id : match sense {
+                    Resource::Named(ref n) => Some(make_id(&n.uri())),
+                    _ => None
+                },
+                definition : join_values(text_values(&sense, &["http://www.w3.org/2004/02/skos/core#definition"], graph)?),
+                reference,
+                usage : join_values(text_values(&sense, &USAGE_PROPERTIES, graph)?),
+                examples : text_values(&sense, &[&format!("{}usageExample", LEXICOG)], graph)?,
+                frequencies : extract_frequencies(&sense, graph)?,
+                attestations : extract_attestations(&sense, graph)?
+            });
+        }
+    }
+    Ok(senses)
+}
+
+/// A literal, or the URI of a named resource
+fn value_text(value : &Value) -> Option<String> {
+    match value {
+        Value::Literal(l) => Some(l.string_value().to_owned()),
+        Value::Resource(Resource::Named(n)) => Some(n.uri()),
+        _ => None
+    }
+}
+
+/// Extract the `frac:frequency`s of an entry or sense, given either as a
+/// number or as a `frac:CorpusFrequency` with an `rdf:value` and a `frac:corpus`
+fn extract_frequencies(subject : &Resource, graph : &Graph) -> Result<Vec<Frequency>, BackendError> {
+    let mut frequencies = Vec::new();
+    for freq in graph.objects(subject, &NamedNode::make_uri(&format!("{}frequency", FRAC)))? {
+        match freq {
+            Value::Literal(l) => frequencies.push(Frequency::from_text(l.string_value(), None)),
+            Value::Resource(r) => {
+                let corpus = graph.objects(&r, &NamedNode::make_uri(&format!("{}corpus", FRAC)))?
+                    .iter().find_map(value_text);
+                for v in graph.objects(&r, &NamedNode::make_uri(&format!("{}value", RDF)))? {
+                    if let Value::Literal(l) = v {
+                        frequencies.push(Frequency::from_text(l.string_value(), corpus.clone()));
+                    }
+                }
+            }
+        }
+    }
+    Ok(frequencies)
+}
+
+/// Extract the `frac:attestation`s of an entry or sense, with their text
+/// (`rdf:value`), `frac:citation` and `frac:locus`
+fn extract_attestations(subject : &Resource, graph : &Graph) -> Result<Vec<Attestation>, BackendError> {
+    let mut attestations = Vec::new();
+    for att in graph.objects(subject, &NamedNode::make_uri(&format!("{}attestation", FRAC)))? {
+        match att {
+            Value::Literal(l) => attestations.push(Attestation { text : Some(l.string_value().to_owned()), ..Attestation::default() }),
+            Value::Resource(r) => attestations.push(Attestation {
+                text : text_values(&r, &[&format!("{}value", RDF)], graph)?.into_iter().next(),
+                citation : graph.objects(&r, &NamedNode::make_uri(&format!("{}citation", FRAC)))?.iter().find_map(value_text),
+                locus : graph.objects(&r, &NamedNode::make_uri(&format!("{}locus", FRAC)))?.iter().find_map(value_text)
+            })
+        }
+    }
+    Ok(attestations)
+}
+
+/// The first `ontolex:writtenRep` of the canonical form of a resource
+fn canonical_written_rep(subj : &Resource, graph : &Graph) -> Result<Option<String>, BackendError> {
+    for form in graph.objects(subj, &NamedNode::make_uri(&format!("{}canonicalForm", ONTOLEX)))? {
+        if let Value::Resource(form) = form {
+            for rep in graph.objects(&form, &NamedNode::make_uri(&format!("{}writtenRep", ONTOLEX)))? {
+                if let Value::Literal(l) = rep {
+                    return Ok(Some(l.string_value().to_owned()));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// A lexical entry as a component of another
+fn entry_component(entry : &Resource, graph : &Graph, entry_uris : &HashSet<&String>) -> Result<Component, BackendError> {
+    Ok(Component {
+        id : None,
+        entry : match entry {
+            Resource::Named(n) if entry_uris.contains(&n.uri()) => Some(make_id(&n.uri())),
+            _ => None
+        },
+        lemma : canonical_written_rep(entry, graph)?
+    })
+}
+
+/// Extract the `decomp:constituent`s of an entry, ordered by the `rdf:_n`
+/// of the entry if given, with the entry each `decomp:correspondsTo`
+fn extract_constituents(id : &str, graph : &Graph, entry_uris : &HashSet<&String>) -> Result<Vec<Component>, BackendError> {
+    let entry = Resource::make_uri(id);
+    let mut constituents : Vec<Resource> = graph.objects(&entry, &NamedNode::make_uri(&format!("{}constituent", DECOMP)))?
+        .into_iter().filter_map(|c| match c { Value::Resource(r) => Some(r), _ => None }).collect();
+    let order = container_members(&entry, graph)?;
+    constituents.sort_by_key(|c| order.iter().position(|o| o == c).unwrap_or(usize::MAX));
+    let mut components = Vec::new();
+    for c in constituents {
+        let corresponds_to = graph.objects(&c, &NamedNode::make_uri(&format!("{}correspondsTo", DECOMP)))?
+            .into_iter().find_map(|e| match e { Value::Resource(r) => Some(r), _ => None });
+        let mut component = match corresponds_to {
+            Some(e) => entry_component(&e, graph, entry_uris)?,
+            None => Component::default()
+        };
+        if component.lemma.is_none() {
+            component.lemma = text_values(&c, &["http://www.w3.org/2000/01/rdf-schema#label"], graph)?.into_iter().next();
+        }
+        if let Resource::Named(ref n) = c {
+            component.id = Some(make_id(&n.uri()));
+        }
+        components.push(component);
+    }
+    Ok(components)
+}
+
+/// Extract the entries an entry has as `decomp:subterm`s
+fn extract_subterms(id : &str, graph : &Graph, entry_uris : &HashSet<&String>) -> Result<Vec<Component>, BackendError> {
+    graph.objects(&Resource::make_uri(id), &NamedNode::make_uri(&format!("{}subterm", DECOMP)))?
+        .into_iter().filter_map(|e| match e {
+            Value::Resource(e) => Some(entry_component(&e, graph, entry_uris)),
+            _ => None
+        }).collect()
+}
+
+/// The `vartrans:Translation`s of a graph, linking senses in both directions,
+/// with the entry of each sense
+struct Translations {
+    senses : HashMap<Resource, Vec<Resource>>,
+    sense_entry : HashMap<Resource, Resource>,
+    entry_senses : HashMap<Resource, Vec<Resource>>,
+    /// The language of the lexicon of each entry
+    languages : HashMap<String, String>
+}
+
+impl Translations {
+    fn new(graph : &Graph, languages : HashMap<String, String>) -> Result<Translations, BackendError> {
+        let mut translations = Translations {
+            senses : HashMap::new(),
+            sense_entry : HashMap::new(),
+            entry_senses : HashMap::new(),
+            languages
+        };
+        let sense_prop = NamedNode::make_uri(&format!("{}sense", ONTOLEX));
+        let is_sense_of = NamedNode::make_uri(&format!("{}isSenseOf", ONTOLEX));
+        let translation = Value::make_uri(&format!("{}Translation", VARTRANS));
+        for subj in graph.subjects() {
+            let subj = subj?;
+            for sense in graph.objects(&subj, &sense_prop)? {
+                if let Value::Resource(sense) = sense {
+                    translations.add_sense(sense, subj.clone());
+                }
+            }
+            for entry in graph.objects(&subj, &is_sense_of)? {
+                if let Value::Resource(entry) = entry {
+                    translations.add_sense(subj.clone(), entry);
+                }
+            }
+            if graph.has_type(&subj, &translation)? {
+                let sources = graph.objects(&subj, &NamedNode::make_uri(&format!("{}source", VARTRANS)))?;
+                let targets = graph.objects(&subj, &NamedNode::make_uri(&format!("{}target", VARTRANS)))?;
+                for source in sources.iter() {
+                    for target in targets.iter() {
+                        if let (Value::Resource(source), Value::Resource(target)) = (source, target) {
+                            translations.senses.entry(source.clone()).or_insert_with(Vec::new).push(target.clone());
+                            translations.senses.entry(target.clone()).or_insert_with(Vec::new).push(source.clone());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(translations)
+    }
+
+    fn add_sense(&mut self, sense : Resource, entry : Resource) {
+        if !self.sense_entry.contains_key(&sense) {
+            self.entry_senses.entry(entry.clone()).or_insert_with(Vec::new).push(sense.clone());
+            self.sense_entry.insert(sense, entry);
+        }
+    }
+
+    /// The canonical form of an entry in its language, from the language tag
+    /// of the form or else the language of its lexicon
+    fn equivalent(&self, entry : &Resource, sense : Option<String>, graph : &Graph) -> Result<Option<Equivalent>, BackendError> {
+        for form in graph.objects(entry, &NamedNode::make_uri(&format!("{}canonicalForm", ONTOLEX)))? {
+            if let Value::Resource(form) = form {
+                for rep in graph.objects(&form, &NamedNode::make_uri(&format!("{}writtenRep", ONTOLEX)))? {
+                    if let Value::Literal(l) = rep {
+                        let language = match l {
+                            Literal::LangLiteral(_, ref lang) => Some(lang.clone()),
+                            _ => match entry {
+                                Resource::Named(n) => self.languages.get(&n.uri()).cloned(),
+                                _ => None
+                            }
+                        };
+                        if let Some(language) = language {
+                            return Ok(Some(Equivalent { sense, language, text : l.string_value().to_owned() }));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Extract the translations of an entry: the entries of the senses that
+/// its senses are translations of and the entries it is
+/// `vartrans:translatableAs`
+fn extract_translations(id : &str, graph : &Graph, translations : &Translations) -> Result<Vec<Equivalent>, BackendError> {
+    let entry = Resource::make_uri(id);
+    let mut result : Vec<Equivalent> = Vec::new();
+    for sense in translations.entry_senses.get(&entry).into_iter().flatten() {
+        let sense_id = match sense {
+            Resource::Named(n) => Some(make_id(&n.uri())),
+            _ => None
+        };
+        for target in translations.senses.get(sense).into_iter().flatten() {
+            if let Some(target_entry) = translations.sense_entry.get(target) {
+                if let Some(t) = translations.equivalent(target_entry, sense_id.clone(), graph)? {
+                    if !result.contains(&t) {
+                        result.push(t);
+                    }
+                }
+            }
+        }
+    }
+    for target in graph.objects(&entry, &NamedNode::make_uri(&format!("{}translatableAs", VARTRANS)))? {
+        if let Value::Resource(target) = target {
+            if let Some(t) = translations.equivalent(&target, None, graph)? {
+                if !result.contains(&t) {
+                    result.push(t);
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Extract the `foaf:depiction`s of an entry and its senses
+fn extract_media(id : &str, graph : &Graph) -> Result<Vec<Media>, BackendError> {
+    let entry = Resource::make_uri(id);
+    let depiction = NamedNode::make_uri("http://xmlns.com/foaf/0.1/depiction");
+    let mut subjects = vec![entry.clone()];
+    for sense in graph.objects(&entry, &NamedNode::make_uri(&format!("{}sense", ONTOLEX)))? {
+        if let Value::Resource(sense) = sense {
+            subjects.push(sense);
+        }
+    }
+    let mut media : Vec<Media> = Vec::new();
+    for subject in subjects.iter() {
+        for image in graph.objects(subject, &depiction)? {
+            let url = match image {
+                Value::Resource(Resource::Named(n)) => n.uri(),
+                Value::Literal(l) => l.string_value().to_owned(),
+                _ => continue
+            };
+            if !media.iter().any(|m| m.url == url) {
+                media.push(Media::new(&url, None));
+            }
+        }
+    }
+    Ok(media)
+}
+
+/// Extract the `ontolex:phoneticRep`s of the forms of an entry
+fn extract_pronunciations(id : &str, graph : &Graph) -> Result<Vec<Pronunciation>, BackendError> {
+    let entry = Resource::make_uri(id);
+    let mut prons = Vec::new();
+    for prop in ["canonicalForm", "otherForm", "lexicalForm"].iter() {
+        for form in graph.objects(&entry, &NamedNode::make_uri(&format!("{}{}", ONTOLEX, prop)))? {
+            if let Value::Resource(form) = form {
+                let written_rep = graph.objects(&form, &NamedNode::make_uri(&format!("{}writtenRep", ONTOLEX)))?
+                    .into_iter().find_map(|v| match v {
+                        Value::Literal(l) => Some(l.string_value().to_owned()),
+                        _ => None
+                    }).unwrap_or_default();
+                for rep in graph.objects(&form, &NamedNode::make_uri(&format!("{}phoneticRep", ONTOLEX)))? {
+                    if let Value::Literal(l) = rep {
+                        let pron = Pronunciation { form : written_rep.clone(), ipa : ipa::normalize(l.string_value()) };
+                        if !pron.ipa.is_empty() && !prons.contains(&pron) {
+                            prons.push(pron);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(prons)
+}
+
+/// Properties linking a word directly to the words it derives from
+const ETYMON_PROPERTIES : [&str; 2] = ["etymon", "etymologicalRoot"];
+
+/// Extract the etymology of an entry, either given by direct links 
+/// (`lexinfo:etymon`, `lexinfo:etymologicalRoot` and `lexinfo:cognate`) or 
+/// with the lemonEty module, where the `ety:EtyLink`s of an `ety:Etymology`
+/// link each word (`ety:etyTarget`, the entry if not given) to the word it 
+/// derives from (`ety:etySource`)
+fn extract_etymology(id : &str, graph : &Graph, entry_uris : &HashSet<&String>) -> Result<Etymology, BackendError> {
+    let entry = Resource::make_uri(id);
+    let mut visited = HashSet::new();
+    visited.insert(entry.clone());
+    let ancestors = etymon_ancestors(&entry, graph, entry_uris, &mut visited)?;
+    let mut cognates = Vec::new();
+    for pred in [format!("{}cognate", LEXINFO), format!("{}cognate", ETY)].iter() {
+        for obj in graph.objects(&entry, &NamedNode::make_uri(pred))? {
+            if let Value::Resource(r) = obj {
+                if visited.insert(r.clone()) {
+                    cognates.push(make_etymon(&r, None, graph, entry_uris, &mut visited)?);
+                }
+            }
+        }
+    }
+    Ok(Etymology::new(ancestors, cognates))
+}
+
+fn etymon_ancestors(word : &Resource, graph : &Graph, entry_uris : &HashSet<&String>,
+    visited : &mut HashSet<Resource>) -> Result<Vec<Etymon>, BackendError> {
+    let mut ancestors = Vec::new();
+    for prop in ETYMON_PROPERTIES.iter() {
+        for obj in graph.objects(word, &NamedNode::make_uri(&format!("{}{}", LEXINFO, prop)))? {
+            if let Value::Resource(r) = obj {
+                if visited.insert(r.clone()) {
+                    ancestors.push(make_etymon(&r, None, graph, entry_uris, visited)?);
+                }
+            }
+        }
+    }
+    for etymology in graph.objects(word, &NamedNode::make_uri(&format!("{}hasEtymology", ETY)))? {
+        if let Value::Resource(etymology) = etymology {
+            let links : Vec<Resource> = graph.objects(&etymology, &NamedNode::make_uri(&format!("{}hasEtyLink", ETY)))?
+                .into_iter().filter_map(|l| match l { Value::Resource(r) => Some(r), _ => None }).collect();
+            ancestors.extend(ety_link_sources(word, word, &links, graph, entry_uris, visited)?);
+        }
+    }
+    Ok(ancestors)
+}
+
+/// The sources of the etymology links to `target`
+fn ety_link_sources(target : &Resource, word : &Resource, links : &Vec<Resource>, graph : &Graph,
+    entry_uris : &HashSet<&String>, visited : &mut HashSet<Resource>) -> Result<Vec<Etymon>, BackendError> {
+    let mut sources = Vec::new();
+    for link in links.iter() {
+        let link_target = match graph.objects(link, &NamedNode::make_uri(&format!("{}etyTarget", ETY)))?.into_iter().next() {
+            Some(Value::Resource(r)) => r,
+            _ => word.clone()
+        };
+        if link_target != *target {
+            continue;
+        }
+        let link_type = graph.objects(link, &NamedNode::make_uri(&format!("{}etyLinkType", ETY)))?.into_iter().next()
+            .map(|t| match t {
+                Value::Literal(l) => l.string_value().to_owned(),
+                Value::Resource(Resource::Named(n)) => make_id(&n.uri()),
+                Value::Resource(r) => r.to_string()
+            });
+        for source in graph.objects(link, &NamedNode::make_uri(&format!("{}etySource", ETY)))? {
+            if let Value::Resource(source) = source {
+                if visited.insert(source.clone()) {
+                    let mut etymon = make_etymon(&source, link_type.clone(), graph, entry_uris, visited)?;
+                    etymon.ancestors.extend(ety_link_sources(&source, word, links, graph, entry_uris, visited)?);
+                    sources.push(etymon);
+                }
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Describe a word from its forms, language and definitions
+fn make_etymon(word : &Resource, etym_type : Option<String>, graph : &Graph, 
+    entry_uris : &HashSet<&String>, visited : &mut HashSet<Resource>) -> Result<Etymon, BackendError> {
+    let mut etymon = Etymon { etym_type, ..Etymon::default() };
+    for form in graph.objects(word, &NamedNode::make_uri(&format!("{}canonicalForm", ONTOLEX)))? {
+        if let Value::Resource(form) = form {
+            for rep in graph.objects(&form, &NamedNode::make_uri(&format!("{}writtenRep", ONTOLEX)))? {
+                if let Value::Literal(l) = rep {
+                    if let Literal::LangLiteral(_, ref lang) = l {
+                        etymon.language.get_or_insert_with(|| lang.clone());
+                    }
+                    etymon.forms.push(l.string_value().to_owned());
+                }
+            }
+        }
+    }
+    if etymon.forms.is_empty() {
+        for label in graph.objects(word, &NamedNode::make_uri("http://www.w3.org/2000/01/rdf-schema#label"))? {
+            if let Value::Literal(l) = label {
+                etymon.forms.push(l.string_value().to_owned());
+            }
+        }
+    }
+    for pred in ["http://www.w3.org/ns/lemon/lime#language", "http://purl.org/dc/terms/language"].iter() {
+        if let Some(lang) = graph.objects(word, &NamedNode::make_uri(pred))?.into_iter().next() {
+            etymon.language = Some(match lang {
+                Value::Literal(l) => l.string_value().to_owned(),
+                Value::Resource(Resource::Named(n)) => make_id(&n.uri()),
+                Value::Resource(r) => r.to_string()
+            });
+        }
+    }
+    for sense in graph.objects(word, &NamedNode::make_uri(&format!("{}sense", ONTOLEX)))? {
+        if let Value::Resource(sense) = sense {
+            for def in graph.objects(&sense, &NamedNode::make_uri("http://www.w3.org/2004/02/skos/core#definition"))? {
+                if let Value::Literal(l) = def {
+                    etymon.glosses.push(l.string_value().to_owned());
+                }
+            }
+        }
+    }
+    if let Resource::Named(ref n) = word {
+        if entry_uris.contains(&n.uri()) {
+            etymon.entry = Some(make_id(&n.uri()));
+        }
+    }
+    etymon.ancestors = etymon_ancestors(word, graph, entry_uris, visited)?;
+    Ok(etymon)
 }
 
 fn extract_lemma(id : &str, triples : &Vec<&Triple>) -> Result<String,BackendError> {
@@ -252,49 +1361,27 @@ fn extract_lemma(id : &str, triples : &Vec<&Triple>) -> Result<String,BackendErr
             }))
 }
 
-fn extract_pos(id : &str, triples : &Vec<&Triple>, cfg : &Config) -> Vec<PartOfSpeech> {
-    triples.iter().filter(|t|
+/// The parts of speech of an entry, with the tags they were mapped from
+fn extract_pos(id : &str, triples : &Vec<&Triple>, cfg : &Config) -> (Vec<PartOfSpeech>, Vec<SourceTag>) {
+    let mut pos = Vec::new();
+    let mut tags = Vec::new();
+    for t in triples.iter().filter(|t|
         t.0 == Resource::make_uri(id) &&
         t.1 == NamedNode::make_uri(&
             cfg.pos_property.clone().unwrap_or(
-            "http://www.lexinfo.net/ontology/2.0/lexinfo#partOfSpeech".to_owned())))
-        .flat_map(|t| {
-            if let Value::Resource(Resource::Named(ref obj)) = t.2 {
-                map_pos_value(obj,cfg)
-            } else {
-                None
-            }
-        }).collect()
-}
-
-fn map_pos_value(obj : &NamedNode, cfg : &Config) -> Option<PartOfSpeech> {
-    if let Some(ref mapping) = cfg.pos_mapping {
-        if let Some(pos) = mapping.get(&obj.uri()) {
-            Some(pos.clone())
-        } else {
-            None
-        }
-    } else {
-        match obj.uri().as_ref() {
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#adjective" => Some(PartOfSpeech::ADJ),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#adposition" => Some(PartOfSpeech::ADP),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#adverb" => Some(PartOfSpeech::ADV),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#auxiliary" => Some(PartOfSpeech::AUX),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#coordinatingConjunction" => Some(PartOfSpeech::CCONJ),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#determiner" => Some(PartOfSpeech::DET),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#interjection" => Some(PartOfSpeech::INTJ),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#commonNoun" => Some(PartOfSpeech::NOUN),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#numeral" => Some(PartOfSpeech::NUM),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#particle" => Some(PartOfSpeech::PART),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#properNoun" => Some(PartOfSpeech::PROPN),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#punctuation" => Some(PartOfSpeech::PUNCT),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#subordinatingConjunction" => Some(PartOfSpeech::SCONJ),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#symbol" => Some(PartOfSpeech::SYM),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#verb" => Some(PartOfSpeech::VERB),
-    "http://www.lexinfo.net/ontology/2.0/lexinfo#other" => Some(PartOfSpeech::X),
-        _ => None
+            "http://www.lexinfo.net/ontology/2.0/lexinfo#partOfSpeech".to_owned()))) {
+        let tag = match t.2 {
+            Value::Resource(Resource::Named(ref obj)) => obj.uri(),
+            Value::Literal(ref l) => l.string_value().to_owned(),
+            _ => continue
+        };
+        let mapping = cfg.map_tag(&tag).unwrap_or_default();
+        if let Some(ref p) = mapping.pos {
+            pos.push(p.clone());
         }
+        tags.push(SourceTag { tag, mapping });
     }
+    (pos, tags)
 }
 
 fn extract_vars(id : &str, triples : &Vec<&Triple>) -> Vec<String> {
@@ -305,7 +1392,7 @@ fn extract_vars(id : &str, triples : &Vec<&Triple>) -> Vec<String> {
             if let Value::Resource(ref form) = t0.2 {
                 triples.iter().find(|t|
                     t.0 == *form &&
-                    t.1 == NamedNode::make_uri("http::/www.w3.org/ns/lemon/ontolex#writtenRep")) 
+                    t.1 == NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#writtenRep")) 
                 .ok_or(BackendError::OntoLex("Canonical Form has no written rep".to_owned()))
                 .and_then(|t1| {
                     match t1.2 {
@@ -321,9 +1408,10 @@ fn extract_vars(id : &str, triples : &Vec<&Triple>) -> Vec<String> {
 
 fn format_triples(triples : &Vec<&Triple>) -> String {
     let out = String::new();
-    let mut subject_pred : Option<(Resource, NamedNode)> = None;
     let prefixes = HashMap::new();
     let mut bnode_ref_count : HashMap<String, usize> = HashMap::new();
+    let mut subjects : Vec<Resource> = Vec::new();
+    let mut by_subject : HashMap<Resource, Vec<Triple>> = HashMap::new();
 
     for triple in triples.iter() {
         match triple.2 {
@@ -332,57 +1420,81 @@ fn format_triples(triples : &Vec<&Triple>) -> String {
             },
             _ => {}
         }
+        if !by_subject.contains_key(&triple.0) {
+            subjects.push(triple.0.clone());
+        }
+        by_subject.entry(triple.0.clone())
+            .or_insert_with(Vec::new)
+            .push((**triple).clone());
     }
 
-    let iter = triples.iter().peekable();
-
     let mut state = WriteState {
-        out, iter, prefixes, bnode_ref_count, 
+        out, prefixes, bnode_ref_count, 
         bnode_triples: HashMap::new(),
         indent:0
     };
 
-    state.out.push_str("@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
-@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
-@prefix dct: <http://purl.org/dc/terms/> .
-@prefix foaf: <http://xmlns.com/foaf/0.1/> .
-@prefix skos: <http://www.w3.org/2004/02/skos/core#> .
-@prefix lexinfo: <http://www.lexinfo.net/ontology/2.0/lexinfo#> .
-
-");
+    // Blank nodes referred to exactly once are written inline, everything
+    // else as a statement of its own
+    let mut statements = Vec::new();
+    for subj in subjects.into_iter() {
+        let subj_triples = by_subject.remove(&subj).unwrap_or_else(Vec::new);
+        match subj {
+            Resource::BlankNode(ref bnode) if state.bnode_ref_count.get(bnode) == Some(&1) => {
+                state.bnode_triples.insert(bnode.to_owned(), subj_triples);
+            },
+            _ => statements.push((subj, subj_triples))
+        }
+    }
 
-    while let Some(ref triple) = state.iter.next() {
-        if let Some((ref subj, ref pred)) = subject_pred {
-            if *subj == triple.0 {
-                if *pred == triple.1 {
+    for (i, (subj, subj_triples)) in statements.iter().enumerate() {
+        if i > 0 {
+            state.out.push_str(".\n\n");
+        }
+        write_resource(subj, &mut state);
+        let mut pred : Option<&NamedNode> = None;
+        for triple in subj_triples.iter() {
+            match pred {
+                None => {
+                    write_named_node(&triple.1, &mut state);
+                    write_value(&triple.2, &mut state);
+                },
+                Some(p) if *p == triple.1 => {
                     state.out.push_str(", ");
                     write_value(&triple.2, &mut state);
-                } else {
+                },
+                Some(_) => {
                     state.out.push_str(";\n");
                     write_pred_obj(&triple.1, &triple.2, &mut state);
                 }
-                subject_pred = Some((triple.0.clone(), triple.1.clone()));
-            } else if let Resource::BlankNode(ref bnode) = triple.0 {
-                state.bnode_triples.entry(bnode.to_owned())
-                    .or_insert_with(|| Vec::new())
-                    .push((**triple).clone());
-            } else {
-                state.out.push_str(".\n\n");
-                write_triple(&triple, &mut state);
-                subject_pred = Some((triple.0.clone(), triple.1.clone()));
             }
-        } else {
-            write_triple(&triple, &mut state);
-            subject_pred = Some((triple.0.clone(), triple.1.clone()));
+            pred = Some(&triple.1);
         }
     }
     state.out.push_str(".\n");
-    state.out
+
+    let mut header = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+@prefix foaf: <http://xmlns.com/foaf/0.1/> .
+@prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+@prefix lexinfo: <http://www.lexinfo.net/ontology/2.0/lexinfo#> .
+".to_string();
+    // Any other prefixes used in the triples, such as lexicog
+    let mut prefixes : Vec<&Namespace> = state.prefixes.values()
+        .filter(|ns| !["lime", "ontolex", "dct", "foaf", "skos", "lexinfo"].contains(&ns.0.as_str()))
+        .collect();
+    prefixes.sort_by(|x, y| x.0.cmp(&y.0));
+    for ns in prefixes {
+        header.push_str(&format!("@prefix {}: <{}> .\n", ns.0, ns.1));
+    }
+    header.push('\n');
+    header.push_str(&state.out);
+    header
 }
 
-struct WriteState<'a> {
+struct WriteState {
     out : String,
-    iter : Peekable<std::slice::Iter<'a, &'a Triple>>,
     prefixes : HashMap<String, Namespace>,
     bnode_ref_count : HashMap<String, usize>,
     bnode_triples : HashMap<String, Vec<Triple>>,
@@ -438,26 +1550,18 @@ fn write_blank_node(bnodeid : &str, state : &mut WriteState) {
         state.out.push_str("[\n");
         state.indent += 1;
         let mut pred : Option<NamedNode> = None;
-        let seen_triples : Vec<Triple> = state.bnode_triples.remove(bnodeid).unwrap_or_else(|| Vec::new()).clone();
-        let mut iter2 = seen_triples.iter().chain(state.iter.clone().map(|x| x.clone()));
-        while let Some(ref t) = iter2.next() {
-            match t.0 {
-                Resource::BlankNode(ref bid) if bid == bnodeid => {
-                    match pred {
-                        None => {},
-                        Some(ref p) if *p == t.1 => {
-                            state.out.push_str(",\n");
-                        }
-                        Some(_) => {
-                            state.out.push_str(";\n");
-                        }
-                    }
-                    write_pred_obj(&t.1, &t.2, state);
-                },
-                _ => {
-                    break
+        let bnode_triples = state.bnode_triples.remove(bnodeid).unwrap_or_else(Vec::new);
+        for t in bnode_triples.iter() {
+            match pred {
+                None => {},
+                Some(ref p) if *p == t.1 => {
+                    state.out.push_str(",\n");
+                }
+                Some(_) => {
+                    state.out.push_str(";\n");
                 }
             }
+            write_pred_obj(&t.1, &t.2, state);
             pred = Some(t.1.clone());
         }
         state.indent -= 1;
@@ -475,16 +1579,17 @@ fn write_pred_obj(pred : &NamedNode, obj : &Value, state : &mut WriteState) {
     write_value(obj, state);
 }
 
-fn write_triple(triple : &Triple, state : &mut WriteState) {
-    write_resource(&triple.0, state);
-    write_named_node(&triple.1, state);
-    write_value(&triple.2, state);
-}
-
 #[cfg(test)]
 use crate::model::EDSState;
 #[cfg(test)]
-use crate::model::{Backend,Format};
+use crate::model::{Backend,Format,FrequencyFilter,LookupQuery,collect_entries};
+#[cfg(test)]
+use crate::ids::IdStrategy;
+
+#[cfg(test)]
+fn options<'a>(id : &'a str, config : &'a Config) -> ParseOptions<'a> {
+    ParseOptions { id, release : Release::PUBLIC, genre : vec![Genre::gen], config, strict : false }
+}
 
 #[test]
 fn test_read_ontolex() {
@@ -526,8 +1631,8 @@ fn test_read_ontolex() {
         ontolex:reference <http://www.example.com/ontology>  
     ] .";
 
-    let dictionary = parse_str(ontolex, Release::PUBLIC, vec![Genre::gen], &Config::blank(), |r,d,e| {
-        Ok(BackendImpl::Mem(EDSState::new(r,d,e)))
+    let dictionary = parse_str(ontolex, options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
     }).unwrap();
     assert_eq!(dictionary.dictionaries().unwrap().len(), 1);
     let dict = dictionary.about("dictionary").unwrap();
@@ -548,23 +1653,23 @@ fn test_read_ontolex() {
     }]);
     assert_eq!(dict.description, Some("An awesome test resource".to_owned()));
 
-    let entry_set1 = dictionary.lookup("dictionary", "cat", None, None, None, false).unwrap();
+    let entry_set1 = dictionary.lookup("dictionary", "cat", &LookupQuery::default()).unwrap();
     assert_eq!(entry_set1.len(), 1);
     let ref entry1 = entry_set1[0];
     assert_eq!(entry1.release, Release::PUBLIC);
     assert_eq!(entry1.lemma, "cat");
     assert_eq!(entry1.id, "entry1");
     assert_eq!(entry1.part_of_speech, vec![PartOfSpeech::NOUN]);
-    assert_eq!(entry1.formats, vec![Format::ontolex]);
+    assert_eq!(entry1.formats, vec![Format::ontolex, Format::json]);
 
-    let entry_set2 = dictionary.lookup("dictionary", "dog", None, None, None, false).unwrap();
+    let entry_set2 = dictionary.lookup("dictionary", "dog", &LookupQuery::default()).unwrap();
     assert_eq!(entry_set2.len(), 1);
     let ref entry2 = entry_set2[0];
     assert_eq!(entry2.release, Release::PUBLIC);
     assert_eq!(entry2.lemma, "dog");
     assert_eq!(entry2.id, "entry2");
     assert_eq!(entry2.part_of_speech, vec![]);
-    assert_eq!(entry2.formats, vec![Format::ontolex]);
+    assert_eq!(entry2.formats, vec![Format::ontolex, Format::json]);
 
     let entry1_ontolex = dictionary.entry_ontolex("dictionary", "entry1").unwrap();
 
@@ -583,9 +1688,645 @@ fn test_read_ontolex() {
     skos:definition \"This is a definition\"@en ] .
 ");
 
+    let streamed = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    assert_eq!(streamed.entry_ontolex("dictionary", "entry1").unwrap(), entry1_ontolex);
+
+
+
+
+}
+
+
+
+#[test]
+fn test_etymology() {
+    let ontolex = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+@prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+@prefix lexinfo: <http://www.lexinfo.net/ontology/2.0/lexinfo#> .
+@prefix ety: <http://lari-datasets.ilc.cnr.it/lemonEty#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#beef>, <#cow> .
+
+<#beef> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"beef\"@en ] ;
+    ety:hasEtymology [
+        ety:hasEtyLink [ ety:etySource <#boef> ; ety:etyLinkType \"borrowing\" ],
+            [ ety:etyTarget <#boef> ; ety:etySource <#bovem> ]
+    ] ;
+    lexinfo:cognate <#cow> .
+
+<#boef> a ety:Etymon ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"boef\"@fro ] .
+
+<#bovem> a ety:Etymon ;
+    lime:language \"la\" ;
+    rdfs:label \"bovem\" ;
+    ontolex:sense [ skos:definition \"ox\" ] .
+
+<#cow> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"cow\"@en ] ;
+    lexinfo:etymon <#cu> .
+
+<#cu> ontolex:canonicalForm [ ontolex:writtenRep \"cū\"@ang ] .";
+
+    let dictionary = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    let etymology = dictionary.entry_etymology("dictionary", "beef").unwrap();
+    assert_eq!(etymology.ancestors.len(), 1);
+    assert_eq!(etymology.ancestors[0].forms, vec!["boef"]);
+    assert_eq!(etymology.ancestors[0].language, Some("fro".to_string()));
+    assert_eq!(etymology.ancestors[0].etym_type, Some("borrowing".to_string()));
+    assert_eq!(etymology.ancestors[0].ancestors[0].forms, vec!["bovem"]);
+    assert_eq!(etymology.ancestors[0].ancestors[0].glosses, vec!["ox"]);
+    assert_eq!(etymology.cognates[0].entry, Some("cow".to_string()));
+    assert_eq!(etymology.languages, vec!["fro", "la", "en", "ang"]);
+    let etymology = dictionary.entry_etymology("dictionary", "cow").unwrap();
+    assert_eq!(etymology.ancestors[0].forms, vec!["cū"]);
+}
+
+#[test]
+fn test_pronunciation() {
+    let ontolex = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#cat> .
+
+<#cat> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"cat\"@en ; ontolex:phoneticRep \"/kæt/\"@en-fonipa ] ;
+    ontolex:otherForm [ ontolex:writtenRep \"cats\"@en ; ontolex:phoneticRep \"kæts\"@en-fonipa ] .";
+
+    let dictionary = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    let matches = dictionary.lookup_pron("dictionary", "kæts", 0.5, None, None).unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].entry.id, "cat");
+    assert_eq!(matches[0].form, "cats");
+    assert_eq!(matches[0].distance, 0.0);
+}
+
+#[test]
+fn test_translations() {
+    let ontolex = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix vartrans: <http://www.w3.org/ns/lemon/vartrans#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#bank>, <#shore> .
+
+<#german> a lime:Lexicon ;
+    lime:language \"de\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#Ufer> .
+
+<#bank> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"bank\"@en ] ;
+    ontolex:sense <#bank-1> ;
+    vartrans:translatableAs <#banque> .
+
+<#bank-1> ontolex:reference <http://dbpedia.org/resource/Bank_(geography)> .
+
+<#shore> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"shore\"@en ] .
+
+<#shore-1> ontolex:isSenseOf <#shore> .
+
+<#Ufer> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"Ufer\" ] ;
+    ontolex:sense <#Ufer-1> .
+
+<#banque> ontolex:canonicalForm [ ontolex:writtenRep \"banque\"@fr ] .
+
+<#trans1> a vartrans:Translation ;
+    vartrans:source <#bank-1> ;
+    vartrans:target <#Ufer-1> .
+
+<#trans2> a vartrans:Translation ;
+    vartrans:source <#Ufer-1> ;
+    vartrans:target <#shore-1> .";
+
+    let dictionary = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    assert_eq!(dictionary.about("dictionary").unwrap().target_language, vec!["de", "fr"]);
+    assert_eq!(dictionary.about("german").unwrap().target_language, vec!["en"]);
+    let matches = dictionary.lookup_translation("dictionary", "de", "Ufer", None, None).unwrap();
+    assert_eq!(matches.iter().map(|m| m.entry.id.as_str()).collect::<Vec<_>>(), vec!["bank", "shore"]);
+    assert_eq!(matches[0].sense, Some("bank-1".to_string()));
+    let matches = dictionary.lookup_translation("dictionary", "fr", "banque", None, None).unwrap();
+    assert_eq!(matches[0].entry.id, "bank");
+    assert_eq!(matches[0].sense, None);
+    assert_eq!(dictionary.lookup_translation("german", "en", "bank", None, None).unwrap()[0].entry.id, "Ufer");
+}
+
+#[test]
+fn test_senses() {
+    let ontolex = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix lexicog: <http://www.w3.org/ns/lemon/lexicog#> .
+@prefix lexinfo: <http://www.lexinfo.net/ontology/2.0/lexinfo#> .
+@prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#cat>, <#dog> .
+
+<#cat> a ontolex:Word ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"cat\"@en ; ontolex:phoneticRep \"/kæt/\"@en-fonipa ] ;
+    ontolex:otherForm [ ontolex:writtenRep \"cats\"@en ] ;
+    ontolex:sense <#cat-1>, <#cat-2> .
+
+<#cat-1> skos:definition \"A small domesticated feline\"@en ;
+    ontolex:reference <http://dbpedia.org/resource/Cat> ;
+    lexicog:usageExample [ rdf:value \"The cat sat on the mat\"@en ] .
 
+<#cat-2> skos:definition [ rdf:value \"A jazz musician\"@en ] ;
+    lexinfo:usage \"slang\" .
 
+<#dog> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"dog\"@en ] ;
+    ontolex:sense [ skos:definition \"An animal that is not a cat\"@en ] .";
 
+    let dictionary = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    let json = dictionary.entry_json("dictionary", "cat").unwrap();
+    assert_eq!(json.entry_type, Type::Word);
+    assert_eq!(json.senses, vec![Sense {
+        id : Some("cat-1".to_string()),
+        definition : Some("A small domesticated feline".to_string()),
+        reference : Some("http://dbpedia.org/resource/Cat".to_string()),
+        usage : None,
+        examples : vec!["The cat sat on the mat".to_string()],
+        frequencies : Vec::new(),
+        attestations : Vec::new()
+    }, Sense {
+        id : Some("cat-2".to_string()),
+        definition : Some("A jazz musician".to_string()),
+        reference : None,
+        usage : Some("slang".to_string()),
+        examples : Vec::new(),
+        frequencies : Vec::new(),
+        attestations : Vec::new()
+    }]);
+    let value = serde_json::to_value(&json).unwrap();
+    assert_eq!(value["canonicalForm"]["phoneticRep"], "kæt");
+    assert_eq!(value["otherForm"][0]["writtenRep"], "cats");
+    assert_eq!(dictionary.lookup("dictionary", "cats", &LookupQuery { inflected : true, ..LookupQuery::default() }).unwrap()[0].id, "cat");
+    let found = dictionary.lookup_definition("dictionary", "Cat", None, None).unwrap();
+    assert_eq!(found.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["dog"]);
+    assert_eq!(dictionary.lookup_definition("dictionary", "feline", None, None).unwrap()[0].id, "cat");
+    assert!(dictionary.lookup_definition("dictionary", "line", None, None).unwrap().is_empty());
+}
+
+#[test]
+fn test_lexicog() {
+    let ontolex = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix lexicog: <http://www.w3.org/ns/lemon/lexicog#> .
+@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lexicog:LexicographicResource ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    rdf:_1 <#entry-take> ;
+    rdf:_2 <#entry-bank> .
+
+<#entry-bank> a lexicog:Entry ;
+    lexicog:describes <#bank-n>, <#bank-v> .
+
+<#entry-take> a lexicog:Entry ;
+    lexicog:describes <#take> ;
+    rdf:_2 <#comp-take-off> ;
+    rdf:_1 <#comp-take-1> .
+
+<#comp-take-1> a lexicog:LexicographicComponent ;
+    lexicog:describes <#take-sense-1> .
+
+<#comp-take-off> a lexicog:LexicographicComponent ;
+    lexicog:describes <#take-off> .
+
+<#bank-n> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"bank\"@en ] .
+
+<#bank-v> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"bank\"@en ] .
+
+<#take-off> a ontolex:MultiWordExpression ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"take off\"@en ] .
+
+<#take> ontolex:canonicalForm [ ontolex:writtenRep \"take\"@en ] ;
+    ontolex:sense <#take-sense-1> .";
+
+    let dictionary = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    assert_eq!(dictionary.about("dictionary").unwrap().source_language, "en");
+    let entries = dictionary.list("dictionary", None, None, &FrequencyFilter::default()).unwrap();
+    assert_eq!(entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["take", "take-off", "bank-n", "bank-v"]);
+    let take = dictionary.entry_relations("dictionary", "take").unwrap();
+    assert_eq!(take.children, vec![RelatedEntry { id : "take-off".to_string(), relation : "component".to_string() }]);
+    assert_eq!(dictionary.entry_relations("dictionary", "take-off").unwrap().parent,
+        Some(RelatedEntry { id : "take".to_string(), relation : "component".to_string() }));
+    assert_eq!(dictionary.entry_relations("dictionary", "bank-n").unwrap().homographs, vec!["bank-v"]);
+    let content = dictionary.entry_ontolex("dictionary", "take").unwrap();
+    assert!(content.contains("@prefix lexicog: <http://www.w3.org/ns/lemon/lexicog#> ."));
+    assert!(content.contains("lexicog:describes <#take-off>"));
+    assert!(content.contains("rdf:_1 <#comp-take-1>"));
+    // The structure is kept when the output is read back
+    let reparsed = format!("{}\n<#dictionary> a lexicog:LexicographicResource ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> .", content);
+    let dictionary = parse(reparsed.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    assert_eq!(dictionary.entry_ontolex("dictionary", "take").unwrap(), content);
+}
+
+#[test]
+fn test_decomp() {
+    let ontolex = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix decomp: <http://www.w3.org/ns/lemon/decomp#> .
+@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#hand>, <#over>, <#hand-over-fist>, <#second-hand>, <#handbag> .
+
+<#hand> a ontolex:Word ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"hand\"@en ] .
+
+<#over> a ontolex:Word ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"over\"@en ] .
+
+<#hand-over-fist> a ontolex:MultiWordExpression ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"hand over fist\"@en ] ;
+    rdf:_3 <#hand-over-fist-3> ;
+    rdf:_1 <#hand-over-fist-1> ;
+    rdf:_2 <#hand-over-fist-2> ;
+    decomp:constituent <#hand-over-fist-3>, <#hand-over-fist-2>, <#hand-over-fist-1> .
+
+<#hand-over-fist-1> decomp:correspondsTo <#hand> .
+<#hand-over-fist-2> decomp:correspondsTo <#over> .
+<#hand-over-fist-3> rdfs:label \"fist\" .
+
+<#second-hand> a ontolex:MultiWordExpression ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"second hand\"@en ] .
+
+<#handbag> a ontolex:Word ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"handbag\"@en ] ;
+    decomp:subterm <#hand> .";
+
+    let dictionary = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    let json = dictionary.entry_json("dictionary", "hand-over-fist").unwrap();
+    assert_eq!(json.components, vec![
+        Component { id : Some("hand-over-fist-1".to_string()), entry : Some("hand".to_string()), lemma : Some("hand".to_string()) },
+        Component { id : Some("hand-over-fist-2".to_string()), entry : Some("over".to_string()), lemma : Some("over".to_string()) },
+        Component { id : Some("hand-over-fist-3".to_string()), entry : None, lemma : Some("fist".to_string()) }]);
+    assert_eq!(dictionary.entry_json("dictionary", "handbag").unwrap().subterms, vec![
+        Component { id : None, entry : Some("hand".to_string()), lemma : Some("hand".to_string()) }]);
+    let found = dictionary.lookup_mwe("dictionary", "Hand", None, None).unwrap();
+    assert_eq!(found.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["hand-over-fist", "handbag", "second-hand"]);
+    assert_eq!(dictionary.lookup_mwe("dictionary", "fist", None, None).unwrap()[0].id, "hand-over-fist");
+    assert!(dictionary.lookup_mwe("dictionary", "second", Some(1), None).unwrap().is_empty());
+    assert!(dictionary.entry_ontolex("dictionary", "hand-over-fist").unwrap().contains("<#hand-over-fist-3> rdfs:label"));
+}
+
+#[test]
+fn test_frac() {
+    let ontolex = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix frac: <http://www.w3.org/ns/lemon/frac#> .
+@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#cat>, <#dog>, <#gnu> .
+
+<#cat> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"cat\"@en ] ;
+    frac:frequency [ a frac:CorpusFrequency ;
+        rdf:value \"1200\"^^xsd:int ;
+        frac:corpus <http://www.example.com/corpus> ] ;
+    ontolex:sense <#cat-1> .
+
+<#cat-1> frac:attestation [ a frac:Attestation ;
+        rdf:value \"The cat sat on the mat\" ;
+        frac:citation <http://www.example.com/book> ;
+        frac:locus \"p. 12\" ] .
+
+<#dog> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"dog\"@en ] ;
+    ontolex:sense [ frac:frequency \"3400\" ] .
+
+<#gnu> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"gnu\"@en ] .";
+
+    let dictionary = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    let json = dictionary.entry_json("dictionary", "cat").unwrap();
+    assert_eq!(json.frequencies, vec![Frequency { count : Some(1200), label : None,
+        corpus : Some("http://www.example.com/corpus".to_string()) }]);
+    assert_eq!(json.senses[0].attestations, vec![Attestation { text : Some("The cat sat on the mat".to_string()),
+        citation : Some("http://www.example.com/book".to_string()), locus : Some("p. 12".to_string()) }]);
+    let value = serde_json::to_value(&json).unwrap();
+    assert_eq!(value["frequencies"][0]["count"], 1200);
+    let sorted = FrequencyFilter { min : None, sort : true };
+    let entries = dictionary.list("dictionary", None, None, &sorted).unwrap();
+    assert_eq!(entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["dog", "cat", "gnu"]);
+    assert_eq!(entries[0].frequency, Some(3400));
+    let frequent = FrequencyFilter { min : Some(2000), sort : false };
+    assert_eq!(dictionary.list("dictionary", None, None, &frequent).unwrap().len(), 1);
+}
+
+#[test]
+fn test_morph() {
+    let ontolex = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix morph: <http://www.w3.org/ns/lemon/morph#> .
+@prefix lexinfo: <http://www.lexinfo.net/ontology/2.0/lexinfo#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#cat>, <#city> .
+
+<#noun> a morph:InflectionPattern .
+
+<#plural> a morph:InflectionRule ;
+    morph:morphologicalPattern <#noun> ;
+    morph:replacement [ morph:source \"^(.*)y$\" ; morph:target \"$1ies\" ] ,
+        [ morph:source \"$\" ; morph:target \"s\" ] ,
+        [ morph:source \"(\" ; morph:target \"s\" ] , [ morph:target \"s\" ] ;
+    morph:grammaticalMeaning [ lexinfo:number lexinfo:plural ] .
+
+<#cat> a ontolex:LexicalEntry ;
+    morph:morphologicalPattern <#noun> ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"cat\"@en ; lexinfo:number lexinfo:singular ] .
+
+<#city> a ontolex:LexicalEntry ;
+    morph:morphologicalPattern <#noun> ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"city\"@en ] .";
+
+    let mut report = ValidationReport::default();
+    let dictionary = parse(ontolex.as_bytes(), options("dictionary", &Config::blank()), &mut report, |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    assert_eq!(report.diagnostics.iter().filter(|d| d.rule == "morph-rule").count(), 2);
+    assert_eq!(report.warnings(), 2);
+    let paradigm = dictionary.paradigm("dictionary", "cat").unwrap();
+    assert_eq!(paradigm.pattern, Some("noun".to_string()));
+    assert_eq!(paradigm.forms.iter().map(|f| f.written_rep.as_str()).collect::<Vec<_>>(), vec!["cat", "cats"]);
+    assert_eq!(paradigm.forms[1].rule, Some("plural".to_string()));
+    assert_eq!(paradigm.forms[1].features.get("Number"), Some(&"Plur".to_string()));
+    let paradigm = dictionary.paradigm("dictionary", "city").unwrap();
+    assert_eq!(paradigm.forms[1].written_rep, "cities");
+    let plural = vec![("number".to_string(), "plural".to_string())];
+    let entries = dictionary.lookup("dictionary", "cities", &LookupQuery { inflected : true, features : plural.clone(), ..LookupQuery::default() }).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, "city");
+    let singular = vec![("number".to_string(), "singular".to_string())];
+    assert!(dictionary.lookup("dictionary", "cats", &LookupQuery { inflected : true, features : singular.clone(), ..LookupQuery::default() }).unwrap().is_empty());
+    let json = dictionary.entry_json("dictionary", "cat").unwrap();
+    assert_eq!(json.morphological_pattern, Some("noun".to_string()));
+    let value = serde_json::to_value(&json).unwrap();
+    assert_eq!(value["canonicalForm"]["features"], "Number=Sing");
+    assert_eq!(value["otherForm"][0]["features"], "Number=Plur");
+    let analyses = dictionary.analyse("dictionary", "cats", None, None).unwrap();
+    assert_eq!(analyses[0].features, "Number=Plur");
+    assert!(dictionary.paradigm("dictionary", "dog").is_err());
+}
+
+#[test]
+fn test_blank_entries() {
+    let ontolex = |extra : &str| format!("@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix lexinfo: <http://www.lexinfo.net/ontology/2.0/lexinfo#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry {} [ a ontolex:LexicalEntry ;
+        lexinfo:partOfSpeech lexinfo:commonNoun ;
+        ontolex:canonicalForm [ ontolex:writtenRep \"cat\"@en ] ] ,
+      [ a ontolex:LexicalEntry ;
+        ontolex:canonicalForm [ ontolex:writtenRep \"dog\"@en ] ] .", extra);
+    let ids = |doc : &str, config : &Config| {
+        let dictionary = parse(doc.as_bytes(), options("dictionary", config), &mut ValidationReport::default(), |r,d,e| {
+            Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+        }).unwrap();
+        let mut ids : Vec<String> = dictionary.list("dictionary", None, None, &FrequencyFilter::default()).unwrap()
+            .into_iter().map(|e| e.id).collect();
+        ids.sort();
+        ids
+    };
+    assert_eq!(ids(&ontolex(""), &Config::blank()), vec!["unidentified_entry_1", "unidentified_entry_2"]);
+    let mut config = Config::blank();
+    config.id_strategy = Some(IdStrategy::Lemma);
+    assert_eq!(ids(&ontolex(""), &config), vec!["cat-noun-1", "dog-x-1"]);
+    config.id_strategy = Some(IdStrategy::Hash);
+    let before = ids(&ontolex(""), &config);
+    let after = ids(&ontolex("[ a ontolex:LexicalEntry ; ontolex:canonicalForm [ ontolex:writtenRep \"ant\"@en ] ] ,"), &config);
+    assert_eq!(after.len(), 3);
+    assert!(before.iter().all(|id| after.contains(id)));
+}
+
+#[test]
+fn test_blank_entry_collision() {
+    let doc = "@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix lexinfo: <http://www.lexinfo.net/ontology/2.0/lexinfo#> .
+
+<http://www.example.com/cat-noun-1> a ontolex:LexicalEntry ;
+    lexinfo:partOfSpeech lexinfo:commonNoun ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"cat\"@en ] .
+
+[ a ontolex:LexicalEntry ;
+    lexinfo:partOfSpeech lexinfo:commonNoun ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"cat\"@en ] ] .";
+    let mut graph = Graph::new(TurtleReader::new(doc.as_bytes()).map(|t| t.unwrap())).unwrap();
+    let mut config = Config::blank();
+    config.id_strategy = Some(IdStrategy::Lemma);
+    let mut report = ValidationReport::default();
+    name_blank_entries(&mut graph, &config, &mut report).unwrap();
+    assert_eq!(report.diagnostics.iter().map(|d| (d.rule, d.entry.clone().unwrap())).collect::<Vec<_>>(),
+        vec![("entry-id-collision", "cat-noun-1_2".to_string())]);
+    assert!(graph.has_type(&Resource::make_uri(&format!("{}cat-noun-1_2", GENID)),
+        &Value::make_uri("http://www.w3.org/ns/lemon/ontolex#LexicalEntry")).unwrap());
+}
+
+#[test]
+fn test_homograph_number() {
+    let doc = "@prefix lime: <http://www.w3.org/ns/lemon/lime#> .
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix lexinfo: <http://www.lexinfo.net/ontology/2.0/lexinfo#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<#dictionary> a lime:Lexicon ;
+    lime:language \"en\" ;
+    dct:license <http://www.example.com/license> ;
+    lime:entry <#bank1>, <#bank2> .
+
+<#bank1> a ontolex:LexicalEntry ;
+    lexinfo:homographNumber 2 ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"bank\"@en ] .
+
+<#bank2> a ontolex:LexicalEntry ;
+    ontolex:canonicalForm [ ontolex:writtenRep \"bank\"@en ] .";
+    let dictionary = parse(doc.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    let entries = dictionary.lookup("dictionary", "bank", &LookupQuery { homograph : Some(2), ..LookupQuery::default() }).unwrap();
+    assert_eq!(entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["bank1"]);
+    let entries = dictionary.lookup("dictionary", "bank", &LookupQuery { homograph : Some(1), ..LookupQuery::default() }).unwrap();
+    assert_eq!(entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["bank2"]);
+}
+
+#[test]
+fn test_read_rdfxml() {
+    let rdfxml = "<?xml version=\"1.0\"?>
+<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"
+    xmlns:lime=\"http://www.w3.org/ns/lemon/lime#\"
+    xmlns:ontolex=\"http://www.w3.org/ns/lemon/ontolex#\"
+    xmlns:dct=\"http://purl.org/dc/terms/\"
+    xmlns:foaf=\"http://xmlns.com/foaf/0.1/\"
+    xmlns:lexinfo=\"http://www.lexinfo.net/ontology/2.0/lexinfo#\">
+  <lime:Lexicon rdf:about=\"#dictionary\">
+    <lime:language>en</lime:language>
+    <dct:license rdf:resource=\"http://www.example.com/license\"/>
+    <dct:creator rdf:parseType=\"Resource\">
+      <foaf:name>Joe Bloggs</foaf:name>
+    </dct:creator>
+    <lime:entry rdf:resource=\"#entry1\"/>
+  </lime:Lexicon>
+  <ontolex:LexicalEntry rdf:about=\"#entry1\">
+    <lexinfo:partOfSpeech rdf:resource=\"http://www.lexinfo.net/ontology/2.0/lexinfo#commonNoun\"/>
+    <ontolex:canonicalForm rdf:parseType=\"Resource\">
+      <ontolex:writtenRep xml:lang=\"en\">cat</ontolex:writtenRep>
+    </ontolex:canonicalForm>
+  </ontolex:LexicalEntry>
+</rdf:RDF>";
+
+    let dictionary = parse_rdf_xml(rdfxml.as_bytes(), options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    let dict = dictionary.about("dictionary").unwrap();
+    assert_eq!(dict.source_language, "en");
+    assert_eq!(dict.license, "http://www.example.com/license");
+    assert_eq!(dict.creator[0].name, "Joe Bloggs");
+
+    let entry_set = dictionary.lookup("dictionary", "cat", &LookupQuery::default()).unwrap();
+    assert_eq!(entry_set.len(), 1);
+    assert_eq!(entry_set[0].id, "entry1");
+    assert_eq!(entry_set[0].part_of_speech, vec![PartOfSpeech::NOUN]);
+}
+
+#[test]
+fn test_read_json_ld() {
+    let doc : serde_json::Value = serde_json::from_str("{
+  \"@context\": {
+    \"lime\": \"http://www.w3.org/ns/lemon/lime#\",
+    \"ontolex\": \"http://www.w3.org/ns/lemon/ontolex#\",
+    \"dct\": \"http://purl.org/dc/terms/\",
+    \"lexinfo\": \"http://www.lexinfo.net/ontology/2.0/lexinfo#\",
+    \"partOfSpeech\": { \"@id\": \"lexinfo:partOfSpeech\", \"@type\": \"@vocab\" },
+    \"entry\": { \"@id\": \"lime:entry\", \"@type\": \"@id\" },
+    \"license\": { \"@id\": \"dct:license\", \"@type\": \"@id\" },
+    \"writtenRep\": { \"@id\": \"ontolex:writtenRep\", \"@language\": \"en\" }
+  },
+  \"@graph\": [{
+    \"@id\": \"#dictionary\",
+    \"@type\": \"lime:Lexicon\",
+    \"lime:language\": \"en\",
+    \"license\": \"http://www.example.com/license\",
+    \"entry\": [\"#entry1\"]
+  }, {
+    \"@id\": \"#entry1\",
+    \"@type\": \"ontolex:LexicalEntry\",
+    \"partOfSpeech\": \"lexinfo:commonNoun\",
+    \"ontolex:canonicalForm\": { \"writtenRep\": \"cat\" }
+  }]
+}").unwrap();
+
+    let dictionary = parse_json_ld(&doc, None, options("dictionary", &Config::blank()), &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    let dict = dictionary.about("dictionary").unwrap();
+    assert_eq!(dict.source_language, "en");
+    let entry_set = dictionary.lookup("dictionary", "cat", &LookupQuery::default()).unwrap();
+    assert_eq!(entry_set.len(), 1);
+    assert_eq!(entry_set[0].id, "entry1");
+    assert_eq!(entry_set[0].part_of_speech, vec![PartOfSpeech::NOUN]);
 }
 
+#[test]
+fn test_read_unordered() {
+    let nt = "<http://ex.org/form1> <http://www.w3.org/ns/lemon/ontolex#writtenRep> \"cat\"@en .
+_:s1 <http://www.w3.org/2004/02/skos/core#definition> \"This is a definition\"@en .
+<http://ex.org/entry1> not a triple .
+<http://ex.org/entry1> <http://www.w3.org/ns/lemon/ontolex#sense> _:s1 .
+<http://ex.org/entry1> <http://www.w3.org/ns/lemon/ontolex#canonicalForm> <http://ex.org/form1> .
+<http://ex.org/dict> <http://www.w3.org/ns/lemon/lime#entry> <http://ex.org/entry1> .
+_:c1 <http://xmlns.com/foaf/0.1/name> \"Joe Bloggs\" .
+<http://ex.org/dict> <http://purl.org/dc/terms/creator> _:c1 .
+<http://ex.org/dict> <http://www.w3.org/ns/lemon/lime#language> \"en\" .
+<http://ex.org/dict> <http://purl.org/dc/terms/license> <http://www.example.com/license> .
+<http://ex.org/entry1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/ns/lemon/ontolex#LexicalEntry> .
+<http://ex.org/dict> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/ns/lemon/lime#Lexicon> .
+";
+
+    let mut report = ValidationReport::default();
+    let dictionary = parse_ntriples(nt.as_bytes(), options("default", &Config::blank()), &mut report, |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).unwrap();
+    assert_eq!(report.errors(), 1);
+    assert_eq!(report.diagnostics[0].rule, "syntax");
+    assert_eq!(report.diagnostics[0].line, 3);
+    let config = Config::blank();
+    assert!(parse_ntriples(nt.as_bytes(), ParseOptions { strict : true, ..options("default", &config) }, &mut ValidationReport::default(), |r,d,e| {
+        Ok(BackendImpl::Mem(EDSState::new(r,d,collect_entries(e)?)))
+    }).is_err());
+    let dict = dictionary.about("dict").unwrap();
+    assert_eq!(dict.source_language, "en");
+    assert_eq!(dict.creator[0].name, "Joe Bloggs");
 
+    let entry_set = dictionary.lookup("dict", "cat", &LookupQuery::default()).unwrap();
+    assert_eq!(entry_set.len(), 1);
+    assert_eq!(entry_set[0].id, "entry1");
+
+    let entry1_ontolex = dictionary.entry_ontolex("dict", "entry1").unwrap();
+    assert!(entry1_ontolex.contains("<http://ex.org/entry1> <http://www.w3.org/ns/lemon/ontolex#sense> [
+    <http://www.w3.org/2004/02/skos/core#definition> \"This is a definition\"@en ] ;"));
+    assert!(entry1_ontolex.contains("<http://ex.org/form1> <http://www.w3.org/ns/lemon/ontolex#writtenRep> \"cat\"@en ."));
+}
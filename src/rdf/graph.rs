@@ -0,0 +1,305 @@
+use crate::rdf::model::*;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use rusqlite::types::Type;
+use std::collections::{HashSet, VecDeque};
+
+/// A set of triples indexed by subject, so that the description of a resource
+/// can be found regardless of the order of the statements in the source.
+/// The triples are kept in a temporary SQLite database, which SQLite writes
+/// to a file in the temporary directory once it outgrows its page cache, so
+/// the memory used does not grow with the size of the lexicon. Each distinct
+/// term is stored only once and the triples refer to the terms by number
+pub struct Graph {
+    db : Connection
+}
+
+/// The number of subjects read from the database at a time by `subjects`
+const SUBJECT_BATCH : i64 = 1000;
+
+impl Graph {
+    pub fn new<I : IntoIterator<Item=Triple>>(triples : I) -> Result<Graph, rusqlite::Error> {
+        let db = Connection::open("")?;
+        db.execute_batch("PRAGMA journal_mode = OFF;
+            PRAGMA synchronous = OFF;
+            CREATE TABLE terms (id INTEGER PRIMARY KEY,
+                                kind INTEGER NOT NULL,
+                                value TEXT NOT NULL,
+                                extra TEXT NOT NULL,
+                                abbrev TEXT,
+                                prefix TEXT,
+                                UNIQUE (kind, value, extra));
+            CREATE TABLE triples (subj INTEGER NOT NULL,
+                                  pred INTEGER NOT NULL,
+                                  obj INTEGER NOT NULL);
+            CREATE TABLE subjects (seq INTEGER PRIMARY KEY,
+                                   term INTEGER NOT NULL UNIQUE);
+            BEGIN;")?;
+        for Triple(subj, pred, obj) in triples {
+            let s = intern(&db, &Value::Resource(subj))?;
+            let p = intern(&db, &pred.as_value())?;
+            let o = intern(&db, &obj)?;
+            db.prepare_cached("INSERT OR IGNORE INTO subjects (term) VALUES (?)")?
+                .execute(params![s])?;
+            db.prepare_cached("INSERT INTO triples (subj, pred, obj) VALUES (?, ?, ?)")?
+                .execute(params![s, p, o])?;
+        }
+        // The index is built once all triples are read, which sorts them by
+        // subject on disk. The row id keeps the triples of each subject in order
+        db.execute_batch("CREATE INDEX triples_subj ON triples (subj);
+            COMMIT;")?;
+        Ok(Graph { db })
+    }
+
+    fn term_id(&self, value : &Value) -> Result<Option<i64>, rusqlite::Error> {
+        let (kind, value, extra, _) = encode(value);
+        self.db.prepare_cached("SELECT id FROM terms WHERE kind = ? AND value = ? AND extra = ?")?
+            .query_row(params![kind, value, extra], |row| row.get(0)).optional()
+    }
+
+    /// The triples of a subject, with the id of each object
+    fn about_id(&self, subj : i64) -> Result<Vec<(Triple, i64)>, rusqlite::Error> {
+        let mut stmt = self.db.prepare_cached("SELECT s.kind, s.value, s.extra, s.abbrev, s.prefix,
+                   p.kind, p.value, p.extra, p.abbrev, p.prefix,
+                   o.kind, o.value, o.extra, o.abbrev, o.prefix, t.obj
+            FROM triples AS t
+            JOIN terms AS s ON s.id = t.subj
+            JOIN terms AS p ON p.id = t.pred
+            JOIN terms AS o ON o.id = t.obj
+            WHERE t.subj = ? ORDER BY t.rowid")?;
+        let rows = stmt.query_map(params![subj], |row| {
+            let subj = match decode(row, 0)? {
+                Value::Resource(r) => r,
+                Value::Literal(_) => return Err(bad_term(0, "Literal as subject"))
+            };
+            let pred = match decode(row, 5)? {
+                Value::Resource(Resource::Named(n)) => n,
+                _ => return Err(bad_term(5, "Predicate is not a named node"))
+            };
+            Ok((Triple(subj, pred, decode(row, 10)?), row.get(15)?))
+        })?;
+        rows.collect()
+    }
+
+    /// Replace a resource everywhere it occurs. This fails if the new
+    /// resource already occurs in the graph
+    pub fn rename(&mut self, from : &Resource, to : Resource) -> Result<bool, rusqlite::Error> {
+        let to = Value::Resource(to);
+        if self.term_id(&to)?.is_some() {
+            return Ok(false);
+        }
+        match self.term_id(&Value::Resource(from.clone()))? {
+            Some(id) => {
+                let (kind, value, extra, ns) = encode(&to);
+                self.db.execute("UPDATE terms SET kind = ?, value = ?, extra = ?, abbrev = ?, prefix = ?
+                    WHERE id = ?",
+                    params![kind, value, extra, ns.map(|n| &n.0), ns.map(|n| &n.1), id])?;
+                Ok(true)
+            },
+            None => Ok(false)
+        }
+    }
+
+    /// All subjects in the order they first occur
+    pub fn subjects<'a>(&'a self) -> impl Iterator<Item=Result<Resource, rusqlite::Error>> + 'a {
+        Subjects { graph : self, last : 0, batch : VecDeque::new() }
+    }
+
+    /// The triples with a given subject
+    pub fn about(&self, subj : &Resource) -> Result<Vec<Triple>, rusqlite::Error> {
+        match self.term_id(&Value::Resource(subj.clone()))? {
+            Some(id) => Ok(self.about_id(id)?.into_iter().map(|(t, _)| t).collect()),
+            None => Ok(Vec::new())
+        }
+    }
+
+    /// The values of a property for a subject
+    pub fn objects(&self, subj : &Resource, pred : &NamedNode) -> Result<Vec<Value>, rusqlite::Error> {
+        Ok(self.about(subj)?.into_iter().filter(|t| t.1 == *pred).map(|t| t.2).collect())
+    }
+
+    /// Check if a subject has a particular `rdf:type`
+    pub fn has_type(&self, subj : &Resource, class : &Value) -> Result<bool, rusqlite::Error> {
+        Ok(self.objects(subj, &NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"))?
+            .iter().any(|o| o == class))
+    }
+
+    /// Collect the description of a resource: all triples about it, and,
+    /// recursively, the triples about every object for which `follow` holds.
+    /// The triples of the resource itself come first
+    pub fn closure<F>(&self, subj : &Resource, follow : F) -> Result<Vec<Triple>, rusqlite::Error>
+        where F : Fn(&Triple) -> bool {
+        let mut result = Vec::new();
+        let start = match self.term_id(&Value::Resource(subj.clone()))? {
+            Some(id) => id,
+            None => return Ok(result)
+        };
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(start);
+        queue.push_back(start);
+        while let Some(s) = queue.pop_front() {
+            for (triple, obj) in self.about_id(s)? {
+                if let Value::Resource(_) = triple.2 {
+                    if !visited.contains(&obj) && follow(&triple) {
+                        visited.insert(obj);
+                        queue.push_back(obj);
+                    }
+                }
+                result.push(triple);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Reads the subjects of a graph a batch at a time
+struct Subjects<'a> {
+    graph : &'a Graph,
+    last : i64,
+    batch : VecDeque<Resource>
+}
+
+impl<'a> Subjects<'a> {
+    fn next_batch(&mut self) -> Result<(), rusqlite::Error> {
+        let mut stmt = self.graph.db.prepare_cached("SELECT s.seq, t.kind, t.value, t.extra, t.abbrev, t.prefix
+            FROM subjects AS s JOIN terms AS t ON t.id = s.term
+            WHERE s.seq > ? ORDER BY s.seq LIMIT ?")?;
+        let rows = stmt.query_map(params![self.last, SUBJECT_BATCH], |row| {
+            Ok((row.get::<_, i64>(0)?, decode(row, 1)?))
+        })?;
+        for row in rows {
+            let (seq, value) = row?;
+            self.last = seq;
+            if let Value::Resource(r) = value {
+                self.batch.push_back(r);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for Subjects<'a> {
+    type Item = Result<Resource, rusqlite::Error>;
+
+    fn next(&mut self) -> Option<Result<Resource, rusqlite::Error>> {
+        if self.batch.is_empty() {
+            if let Err(e) = self.next_batch() {
+                return Some(Err(e));
+            }
+        }
+        self.batch.pop_front().map(Ok)
+    }
+}
+
+fn intern(db : &Connection, value : &Value) -> Result<i64, rusqlite::Error> {
+    let (kind, value, extra, ns) = encode(value);
+    let id = db.prepare_cached("SELECT id FROM terms WHERE kind = ? AND value = ? AND extra = ?")?
+        .query_row(params![kind, value, extra], |row| row.get(0)).optional()?;
+    match id {
+        Some(id) => Ok(id),
+        None => {
+            db.prepare_cached("INSERT INTO terms (kind, value, extra, abbrev, prefix) VALUES (?, ?, ?, ?, ?)")?
+                .execute(params![kind, value, extra, ns.map(|n| &n.0), ns.map(|n| &n.1)])?;
+            Ok(db.last_insert_rowid())
+        }
+    }
+}
+
+/// The kind, text and language or datatype of a term, and the namespace of
+/// the term or its datatype if it is written as a qualified name. Terms are
+/// the same if they have the same kind, text and language or datatype
+fn encode(value : &Value) -> (i64, String, String, Option<&Namespace>) {
+    match value {
+        Value::Resource(Resource::Named(n)) => (0, n.uri(), String::new(), namespace(n)),
+        Value::Resource(Resource::BlankNode(b)) => (1, b.clone(), String::new(), None),
+        Value::Literal(Literal::PlainLiteral(s)) => (2, s.clone(), String::new(), None),
+        Value::Literal(Literal::LangLiteral(s, l)) => (3, s.clone(), l.clone(), None),
+        Value::Literal(Literal::TypedLiteral(s, t)) => (4, s.clone(), t.uri(), namespace(t))
+    }
+}
+
+fn namespace(node : &NamedNode) -> Option<&Namespace> {
+    match node {
+        NamedNode::QName(ns, _) => Some(ns),
+        NamedNode::URIRef(_) => None
+    }
+}
+
+/// Read a named node, as a qualified name if it has a namespace
+fn named_node(uri : &str, abbrev : Option<String>, prefix : Option<String>) -> NamedNode {
+    match (abbrev, prefix) {
+        (Some(abbrev), Some(prefix)) if uri.starts_with(&prefix) =>
+            NamedNode::QName(Namespace(abbrev, prefix.clone()), uri[prefix.len()..].to_string()),
+        _ => NamedNode::make_uri(uri)
+    }
+}
+
+/// The error for a term that cannot occur where it was found
+fn bad_term(col : usize, msg : &str) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(col, Type::Integer, msg.into())
+}
+
+/// Read a term from five columns of a row, starting at `col`
+fn decode(row : &Row, col : usize) -> Result<Value, rusqlite::Error> {
+    let value : String = row.get(col + 1)?;
+    let extra : String = row.get(col + 2)?;
+    let abbrev : Option<String> = row.get(col + 3)?;
+    let prefix : Option<String> = row.get(col + 4)?;
+    Ok(match row.get::<_, i64>(col)? {
+        0 => named_node(&value, abbrev, prefix).as_value(),
+        1 => Value::make_blank(&value),
+        2 => Value::make_literal(&value),
+        3 => Value::make_lang_literal(&value, &extra),
+        _ => Value::make_typed_literal(&value, named_node(&extra, abbrev, prefix))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_closure() {
+        let ns = Namespace::new("ex", "http://example.org/");
+        let graph = Graph::new(vec![
+            Triple(Resource::make_blank("b1"), NamedNode::make_uri("p"), Value::make_literal("x")),
+            Triple(Resource::make_uri("other"), NamedNode::make_uri("p"), Value::make_literal("y")),
+            Triple(Resource::make_uri("s"), NamedNode::make_uri("p"), Value::make_blank("b1")),
+            Triple(Resource::make_uri("s"), NamedNode::make_uri("q"), Value::make_uri("other")),
+            Triple(Resource::make_uri("s"), NamedNode::make_uri("r"), ns.make_value("named")),
+            Triple(Resource::make_uri("http://example.org/named"), NamedNode::make_uri("p"), Value::make_literal("z"))
+        ]).unwrap();
+        let closure = graph.closure(&Resource::make_uri("s"), |t| t.1 != NamedNode::make_uri("q")).unwrap();
+        assert_eq!(closure, vec![
+            Triple(Resource::make_uri("s"), NamedNode::make_uri("p"), Value::make_blank("b1")),
+            Triple(Resource::make_uri("s"), NamedNode::make_uri("q"), Value::make_uri("other")),
+            Triple(Resource::make_uri("s"), NamedNode::make_uri("r"), ns.make_value("named")),
+            Triple(Resource::make_blank("b1"), NamedNode::make_uri("p"), Value::make_literal("x")),
+            Triple(Resource::make_uri("http://example.org/named"), NamedNode::make_uri("p"), Value::make_literal("z"))
+        ]);
+        assert_eq!(graph.subjects().count(), 4);
+    }
+
+    #[test]
+    fn test_large_graph() {
+        // More subjects than are read at a time, and more data than the
+        // page cache holds, so the database is written to disk
+        let n = 20000;
+        let mut graph = Graph::new((0..n).flat_map(|i| vec![
+            Triple(Resource::make_uri(&format!("http://example.org/{}", i)),
+                NamedNode::make_uri("http://example.org/label"),
+                Value::make_lang_literal(&format!("{} {}", i, "x".repeat(200)), "en")),
+            Triple(Resource::make_uri(&format!("http://example.org/{}", i)),
+                NamedNode::make_uri("http://example.org/next"),
+                Value::make_uri(&format!("http://example.org/{}", (i + 1) % n)))
+        ])).unwrap();
+        assert_eq!(graph.subjects().count(), n);
+        assert_eq!(graph.subjects().nth(12345).unwrap().unwrap(), Resource::make_uri("http://example.org/12345"));
+        assert_eq!(graph.objects(&Resource::make_uri("http://example.org/7"),
+            &NamedNode::make_uri("http://example.org/next")).unwrap(), vec![Value::make_uri("http://example.org/8")]);
+        assert!(graph.rename(&Resource::make_uri("http://example.org/8"), Resource::make_uri("http://example.org/eight")).unwrap());
+        assert!(!graph.rename(&Resource::make_uri("http://example.org/9"), Resource::make_uri("http://example.org/eight")).unwrap());
+        assert_eq!(graph.objects(&Resource::make_uri("http://example.org/7"),
+            &NamedNode::make_uri("http://example.org/next")).unwrap(), vec![Value::make_uri("http://example.org/eight")]);
+    }
+}
@@ -0,0 +1,727 @@
+use serde_json::{Map, Value as JsonValue};
+use crate::rdf::model::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+use std::result;
+
+type Result<T> = result::Result<T, JsonLdError>;
+
+const RDF : &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XSD : &str = "http://www.w3.org/2001/XMLSchema#";
+const MAX_CONTEXT_DEPTH : usize = 10;
+
+/// Options for reading JSON-LD. As the service does not fetch documents from
+/// the web, remote contexts must be available locally
+pub struct JsonLdOptions {
+    /// The base IRI of the document
+    pub base : String,
+    /// Local copies of remote contexts, as a map from the context IRI to a file path
+    pub contexts : HashMap<String, String>,
+    /// The directory that relative context references are resolved against
+    pub document_dir : Option<PathBuf>
+}
+
+impl JsonLdOptions {
+    pub fn new() -> JsonLdOptions {
+        JsonLdOptions {
+            base : String::new(),
+            contexts : HashMap::new(),
+            document_dir : None
+        }
+    }
+}
+
+/// Check if a JSON document looks like JSON-LD, i.e., it has a top-level
+/// `@context` or `@graph`
+pub fn is_json_ld(doc : &JsonValue) -> bool {
+    match doc {
+        JsonValue::Object(o) => o.contains_key("@context") || o.contains_key("@graph"),
+        JsonValue::Array(a) => a.iter().any(is_json_ld),
+        _ => false
+    }
+}
+
+/// Convert a JSON-LD document to triples. Named graphs are merged into the
+/// default graph
+#[cfg(test)]
+pub fn parse_json_ld(doc : &JsonValue, options : &JsonLdOptions) -> Result<Vec<Triple>> {
+    JsonLdReader::new(doc, options)?.collect()
+}
+
+/// Returns the triples of a JSON-LD document one top-level node at a time, so
+/// that they need not all be held in memory. Reading stops at the first error
+pub struct JsonLdReader<'a> {
+    nodes : std::vec::IntoIter<(&'a Map<String, JsonValue>, Context)>,
+    state : ParserState<'a>,
+    pending : std::vec::IntoIter<Triple>
+}
+
+impl<'a> JsonLdReader<'a> {
+    pub fn new(doc : &'a JsonValue, options : &'a JsonLdOptions) -> Result<JsonLdReader<'a>> {
+        let state = ParserState {
+            triples : Vec::new(),
+            bnodes : 0,
+            bnode_labels : HashMap::new(),
+            options
+        };
+        let mut nodes = Vec::new();
+        top_nodes(doc, &Context::new(&options.base), &state, &mut nodes)?;
+        Ok(JsonLdReader { nodes : nodes.into_iter(), state, pending : Vec::new().into_iter() })
+    }
+}
+
+impl<'a> Iterator for JsonLdReader<'a> {
+    type Item = Result<Triple>;
+
+    fn next(&mut self) -> Option<Result<Triple>> {
+        loop {
+            if let Some(triple) = self.pending.next() {
+                return Some(Ok(triple));
+            }
+            let (node, ctx) = self.nodes.next()?;
+            if let Err(e) = process_node(node, &ctx, &mut self.state) {
+                self.nodes = Vec::new().into_iter();
+                return Some(Err(e));
+            }
+            self.pending = std::mem::take(&mut self.state.triples).into_iter();
+        }
+    }
+}
+
+struct ParserState<'a> {
+    triples : Vec<Triple>,
+    bnodes : usize,
+    bnode_labels : HashMap<String, String>,
+    options : &'a JsonLdOptions
+}
+
+impl<'a> ParserState<'a> {
+    fn fresh_bnode(&mut self) -> Resource {
+        let id = format!("nodeID{}", self.bnodes);
+        self.bnodes += 1;
+        Resource::make_blank(&id)
+    }
+
+    /// Blank node labels in the document are relabelled so they cannot clash
+    /// with generated labels
+    fn bnode(&mut self, label : &str) -> Resource {
+        if let Some(id) = self.bnode_labels.get(label) {
+            return Resource::make_blank(id);
+        }
+        let r = self.fresh_bnode();
+        if let Resource::BlankNode(ref id) = r {
+            self.bnode_labels.insert(label.to_string(), id.clone());
+        }
+        r
+    }
+
+    fn resource(&mut self, iri : &str) -> Resource {
+        if let Some(id) = iri.strip_prefix("_:") {
+            self.bnode(id)
+        } else {
+            Resource::make_uri(iri)
+        }
+    }
+}
+
+#[derive(Clone,Debug)]
+struct Context {
+    base : String,
+    vocab : Option<String>,
+    language : Option<String>,
+    terms : HashMap<String, Option<TermDef>>
+}
+
+#[derive(Clone,Debug)]
+struct TermDef {
+    id : String,
+    type_mapping : Option<String>,
+    container : Vec<String>,
+    language : Option<Option<String>>,
+    reverse : bool
+}
+
+impl Context {
+    fn new(base : &str) -> Context {
+        Context {
+            base : base.to_string(),
+            vocab : None,
+            language : None,
+            terms : HashMap::new()
+        }
+    }
+
+    fn term(&self, key : &str) -> Option<&TermDef> {
+        self.terms.get(key).and_then(|t| t.as_ref())
+    }
+
+    /// Expand a term, compact IRI or relative IRI
+    fn expand_iri(&self, value : &str, vocab : bool, document_relative : bool) -> Option<String> {
+        if value.starts_with("@") {
+            return Some(value.to_string());
+        }
+        if vocab {
+            match self.terms.get(value) {
+                Some(Some(t)) => return Some(t.id.clone()),
+                Some(None) => return None,
+                None => {}
+            }
+        }
+        if let Some(i) = value.find(':') {
+            let (prefix, suffix) = (&value[..i], &value[(i+1)..]);
+            if prefix == "_" || suffix.starts_with("//") {
+                return Some(value.to_string());
+            }
+            if let Some(t) = self.term(prefix) {
+                return Some(format!("{}{}", t.id, suffix));
+            }
+            return Some(value.to_string());
+        }
+        if vocab {
+            if let Some(ref v) = self.vocab {
+                return Some(format!("{}{}", v, value));
+            }
+        }
+        if document_relative {
+            Some(resolve_iri(&self.base, value))
+        } else {
+            Some(value.to_string())
+        }
+    }
+}
+
+fn process_context(active : &Context, local : &JsonValue, state : &ParserState,
+                   depth : usize) -> Result<Context> {
+    if depth > MAX_CONTEXT_DEPTH {
+        return Err(JsonLdError::Context("Context inclusion is too deep or recursive".to_string()));
+    }
+    match local {
+        JsonValue::Null => Ok(Context::new(&state.options.base)),
+        JsonValue::Array(contexts) => {
+            let mut result = active.clone();
+            for c in contexts.iter() {
+                result = process_context(&result, c, state, depth + 1)?;
+            }
+            Ok(result)
+        },
+        JsonValue::String(iri) => {
+            let doc = load_context(&resolve_iri(&active.base, iri), iri, state)?;
+            match doc {
+                JsonValue::Object(ref o) if o.contains_key("@context") =>
+                    process_context(active, &o["@context"], state, depth + 1),
+                _ => Err(JsonLdError::Context(format!("Context document {} has no @context", iri)))
+            }
+        },
+        JsonValue::Object(defs) => {
+            let mut result = active.clone();
+            if let Some(base) = defs.get("@base") {
+                match base {
+                    JsonValue::Null => result.base = String::new(),
+                    JsonValue::String(b) => result.base = resolve_iri(&result.base, b),
+                    _ => return Err(JsonLdError::Context("Invalid @base".to_string()))
+                }
+            }
+            if let Some(vocab) = defs.get("@vocab") {
+                match vocab {
+                    JsonValue::Null => result.vocab = None,
+                    JsonValue::String(v) => result.vocab = result.expand_iri(v, true, true),
+                    _ => return Err(JsonLdError::Context("Invalid @vocab".to_string()))
+                }
+            }
+            if let Some(lang) = defs.get("@language") {
+                match lang {
+                    JsonValue::Null => result.language = None,
+                    JsonValue::String(l) => result.language = Some(l.to_lowercase()),
+                    _ => return Err(JsonLdError::Context("Invalid @language".to_string()))
+                }
+            }
+            let mut defined = HashMap::new();
+            for term in defs.keys() {
+                define_term(&mut result, defs, term, &mut defined)?;
+            }
+            Ok(result)
+        },
+        _ => Err(JsonLdError::Context("Invalid local context".to_string()))
+    }
+}
+
+fn load_context(iri : &str, reference : &str, state : &ParserState) -> Result<JsonValue> {
+    let path = if let Some(p) = state.options.contexts.get(iri).or(state.options.contexts.get(reference)) {
+        PathBuf::from(p)
+    } else if let Some(file) = iri.strip_prefix("file://") {
+        PathBuf::from(file)
+    } else if !iri.contains(':') {
+        match state.options.document_dir {
+            Some(ref dir) => dir.join(iri),
+            None => PathBuf::from(iri)
+        }
+    } else {
+        return Err(JsonLdError::Context(format!("Remote context {} is not available offline; add a local copy in jsonldContexts", iri)));
+    };
+    let file = File::open(&path)
+        .map_err(|e| JsonLdError::Context(format!("Could not open context {}: {}", path.display(), e)))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+fn define_term(result : &mut Context, defs : &Map<String, JsonValue>, term : &str,
+               defined : &mut HashMap<String, bool>) -> Result<()> {
+    if term.starts_with("@") {
+        return Ok(());
+    }
+    match defined.get(term) {
+        Some(true) => return Ok(()),
+        Some(false) => return Err(JsonLdError::Context(format!("Cyclic definition of {}", term))),
+        None => {}
+    }
+    defined.insert(term.to_string(), false);
+    let value = &defs[term];
+
+    // Make sure any prefix this term depends on is defined first
+    let depends = |v : &str| -> Option<String> {
+        match v.find(':') {
+            Some(i) => Some(v[..i].to_string()),
+            None => Some(v.to_string())
+        }
+    };
+    let deps : Vec<String> = match value {
+        JsonValue::String(s) => depends(s).into_iter().collect(),
+        JsonValue::Object(o) => ["@id", "@reverse", "@type"].iter()
+            .flat_map(|k| o.get(*k).and_then(|v| v.as_str()).and_then(&depends))
+            .collect(),
+        _ => Vec::new()
+    };
+    for dep in deps.iter() {
+        if dep != term && defs.contains_key(dep) {
+            define_term(result, defs, dep, defined)?;
+        }
+    }
+    if term.contains(':') {
+        let prefix = term.split(':').next().unwrap_or("");
+        if defs.contains_key(prefix) && prefix != term {
+            define_term(result, defs, prefix, defined)?;
+        }
+    }
+
+    let def = match value {
+        JsonValue::Null => None,
+        JsonValue::String(s) => Some(TermDef {
+            id : result.expand_iri(s, true, false).unwrap_or_default(),
+            type_mapping : None,
+            container : Vec::new(),
+            language : None,
+            reverse : false
+        }),
+        JsonValue::Object(o) => {
+            let (id, reverse) = if let Some(r) = o.get("@reverse").and_then(|r| r.as_str()) {
+                (result.expand_iri(r, true, false), true)
+            } else if let Some(i) = o.get("@id") {
+                match i {
+                    JsonValue::Null => (None, false),
+                    JsonValue::String(i) => (result.expand_iri(i, true, false), false),
+                    _ => return Err(JsonLdError::Context(format!("Invalid @id for {}", term)))
+                }
+            } else {
+                (default_term_iri(result, term), false)
+            };
+            id.map(|id| TermDef {
+                id,
+                type_mapping : o.get("@type").and_then(|t| t.as_str())
+                    .and_then(|t| result.expand_iri(t, true, false)),
+                container : match o.get("@container") {
+                    Some(JsonValue::String(c)) => vec![c.to_string()],
+                    Some(JsonValue::Array(cs)) => cs.iter().flat_map(|c| c.as_str()).map(|c| c.to_string()).collect(),
+                    _ => Vec::new()
+                },
+                language : match o.get("@language") {
+                    Some(JsonValue::Null) => Some(None),
+                    Some(JsonValue::String(l)) => Some(Some(l.to_lowercase())),
+                    _ => None
+                },
+                reverse
+            })
+        },
+        _ => return Err(JsonLdError::Context(format!("Invalid term definition for {}", term)))
+    };
+    result.terms.insert(term.to_string(), def);
+    defined.insert(term.to_string(), true);
+    Ok(())
+}
+
+fn default_term_iri(ctx : &Context, term : &str) -> Option<String> {
+    if term.contains(':') {
+        ctx.expand_iri(term, false, false)
+    } else {
+        ctx.vocab.as_ref().map(|v| format!("{}{}", v, term))
+    }
+}
+
+fn process_top(doc : &JsonValue, ctx : &Context, state : &mut ParserState) -> Result<()> {
+    let mut nodes = Vec::new();
+    top_nodes(doc, ctx, state, &mut nodes)?;
+    for (node, ctx) in nodes {
+        process_node(node, &ctx, state)?;
+    }
+    Ok(())
+}
+
+/// The node objects at the top of a document or graph, with their contexts
+fn top_nodes<'a>(doc : &'a JsonValue, ctx : &Context, state : &ParserState,
+                 nodes : &mut Vec<(&'a Map<String, JsonValue>, Context)>) -> Result<()> {
+    match doc {
+        JsonValue::Array(items) => {
+            for item in items.iter() {
+                top_nodes(item, ctx, state, nodes)?;
+            }
+            Ok(())
+        },
+        JsonValue::Object(o) => {
+            let is_graph_container = o.contains_key("@graph") &&
+                o.keys().all(|k| k == "@context" || k == "@graph" || k == "@id");
+            if is_graph_container {
+                let ctx = match o.get("@context") {
+                    Some(c) => process_context(ctx, c, state, 0)?,
+                    None => ctx.clone()
+                };
+                top_nodes(&o["@graph"], &ctx, state, nodes)
+            } else {
+                nodes.push((o, ctx.clone()));
+                Ok(())
+            }
+        },
+        _ => Err(JsonLdError::Syntax("Top-level value must be an object or array".to_string()))
+    }
+}
+
+fn process_node(node : &Map<String, JsonValue>, ctx : &Context,
+                state : &mut ParserState) -> Result<Resource> {
+    let ctx = match node.get("@context") {
+        Some(c) => process_context(ctx, c, state, 0)?,
+        None => ctx.clone()
+    };
+    let mut subject = None;
+    let mut types = Vec::new();
+    for (key, value) in node.iter() {
+        match ctx.expand_iri(key, true, false).as_deref() {
+            Some("@id") => if let Some(id) = value.as_str() {
+                if let Some(iri) = ctx.expand_iri(id, false, true) {
+                    subject = Some(state.resource(&iri));
+                }
+            },
+            Some("@type") => types.push(value),
+            _ => {}
+        }
+    }
+    let subject = match subject {
+        Some(s) => s,
+        None => state.fresh_bnode()
+    };
+    for value in types {
+        for t in as_array(value) {
+            if let Some(t) = t.as_str().and_then(|t| ctx.expand_iri(t, true, true)) {
+                let obj = state.resource(&t).as_value();
+                state.triples.push(Triple(subject.clone(),
+                    NamedNode::make_uri(&format!("{}type", RDF)), obj));
+            }
+        }
+    }
+    for (key, value) in node.iter() {
+        let prop = match ctx.expand_iri(key, true, false) {
+            Some(p) => p,
+            None => continue
+        };
+        if prop == "@graph" {
+            process_top(value, &ctx, state)?;
+        } else if prop == "@reverse" {
+            if let JsonValue::Object(rev) = value {
+                for (rkey, rvalue) in rev.iter() {
+                    if let Some(rprop) = ctx.expand_iri(rkey, true, false).filter(|p| is_absolute(p)) {
+                        for obj in expand_values(rvalue, None, &ctx, state)? {
+                            if let Value::Resource(r) = obj {
+                                state.triples.push(Triple(r, NamedNode::make_uri(&rprop), subject.clone().as_value()));
+                            }
+                        }
+                    }
+                }
+            }
+        } else if prop.starts_with("@") || !is_absolute(&prop) {
+            // Keywords handled above, other keywords and unmapped terms are dropped
+            continue;
+        } else {
+            let term = ctx.term(key).cloned();
+            let pred = NamedNode::make_uri(&prop);
+            let objects = match term {
+                Some(ref t) if t.container.iter().any(|c| c == "@language") && value.is_object() => {
+                    let mut objs = Vec::new();
+                    for (lang, strings) in value.as_object().unwrap().iter() {
+                        for s in as_array(strings) {
+                            if let Some(s) = s.as_str() {
+                                objs.push(Value::make_lang_literal(s, &lang.to_lowercase()));
+                            }
+                        }
+                    }
+                    objs
+                },
+                Some(ref t) if t.container.iter().any(|c| c == "@list") => {
+                    let items = expand_values(value, Some(t), &ctx, state)?;
+                    vec![make_list(items, state).as_value()]
+                },
+                _ => expand_values(value, term.as_ref(), &ctx, state)?
+            };
+            let reverse = term.as_ref().map(|t| t.reverse).unwrap_or(false);
+            for obj in objects {
+                if reverse {
+                    if let Value::Resource(r) = obj {
+                        state.triples.push(Triple(r, pred.clone(), subject.clone().as_value()));
+                    }
+                } else {
+                    state.triples.push(Triple(subject.clone(), pred.clone(), obj));
+                }
+            }
+        }
+    }
+    Ok(subject)
+}
+
+fn is_absolute(iri : &str) -> bool {
+    iri.contains(':')
+}
+
+fn as_array(value : &JsonValue) -> Vec<&JsonValue> {
+    match value {
+        JsonValue::Array(a) => a.iter().collect(),
+        v => vec![v]
+    }
+}
+
+fn expand_values(value : &JsonValue, term : Option<&TermDef>, ctx : &Context,
+                 state : &mut ParserState) -> Result<Vec<Value>> {
+    let mut values = Vec::new();
+    for v in as_array(value) {
+        match v {
+            JsonValue::Array(_) => values.extend(expand_values(v, term, ctx, state)?),
+            JsonValue::Object(o) if o.contains_key("@set") =>
+                values.extend(expand_values(&o["@set"], term, ctx, state)?),
+            JsonValue::Object(o) if o.contains_key("@list") => {
+                let items = expand_values(&o["@list"], term, ctx, state)?;
+                values.push(make_list(items, state).as_value());
+            },
+            _ => if let Some(v) = expand_value(v, term, ctx, state)? {
+                values.push(v);
+            }
+        }
+    }
+    Ok(values)
+}
+
+fn expand_value(value : &JsonValue, term : Option<&TermDef>, ctx : &Context,
+                state : &mut ParserState) -> Result<Option<Value>> {
+    let type_mapping = term.and_then(|t| t.type_mapping.clone());
+    match value {
+        JsonValue::Null => Ok(None),
+        JsonValue::String(s) => {
+            match type_mapping.as_deref() {
+                Some("@id") => Ok(ctx.expand_iri(s, false, true).map(|i| state.resource(&i).as_value())),
+                Some("@vocab") => Ok(ctx.expand_iri(s, true, true).map(|i| state.resource(&i).as_value())),
+                Some(t) => Ok(Some(Value::make_typed_literal(s, NamedNode::make_uri(t)))),
+                None => {
+                    let lang = match term.and_then(|t| t.language.clone()) {
+                        Some(l) => l,
+                        None => ctx.language.clone()
+                    };
+                    match lang {
+                        Some(l) => Ok(Some(Value::make_lang_literal(s, &l))),
+                        None => Ok(Some(Value::make_literal(s)))
+                    }
+                }
+            }
+        },
+        JsonValue::Number(_) | JsonValue::Bool(_) => Ok(Some(native_literal(value, type_mapping.as_deref()))),
+        JsonValue::Object(o) => {
+            if let Some(v) = o.get("@value") {
+                let datatype = o.get("@type").and_then(|t| t.as_str())
+                    .and_then(|t| ctx.expand_iri(t, true, true));
+                match v {
+                    JsonValue::Null => Ok(None),
+                    JsonValue::String(s) => {
+                        if let Some(dt) = datatype {
+                            Ok(Some(Value::make_typed_literal(s, NamedNode::make_uri(&dt))))
+                        } else if let Some(l) = o.get("@language").and_then(|l| l.as_str()) {
+                            Ok(Some(Value::make_lang_literal(s, &l.to_lowercase())))
+                        } else {
+                            Ok(Some(Value::make_literal(s)))
+                        }
+                    },
+                    _ => Ok(Some(native_literal(v, datatype.as_deref())))
+                }
+            } else {
+                Ok(Some(process_node(o, ctx, state)?.as_value()))
+            }
+        },
+        JsonValue::Array(_) => Err(JsonLdError::Syntax("Unexpected nested array".to_string()))
+    }
+}
+
+/// Convert a JSON number or boolean to a literal with its canonical lexical form
+fn native_literal(value : &JsonValue, datatype : Option<&str>) -> Value {
+    let xsd_double = format!("{}double", XSD);
+    let is_double = datatype == Some(xsd_double.as_str());
+    let (lexical, default_type) = match value {
+        JsonValue::Bool(b) => (format!("{}", b), "boolean"),
+        JsonValue::Number(n) if (n.is_i64() || n.is_u64()) && !is_double =>
+            (format!("{}", n), "integer"),
+        JsonValue::Number(n) => {
+            let f = n.as_f64().unwrap_or(0.0);
+            if f.fract() == 0.0 && f.abs() < 1e21 && !is_double {
+                (format!("{}", f as i64), "integer")
+            } else {
+                let s = format!("{:E}", f);
+                let s = match s.find('E') {
+                    Some(i) if !s[..i].contains('.') => format!("{}.0{}", &s[..i], &s[i..]),
+                    _ => s
+                };
+                (s, "double")
+            }
+        },
+        v => (v.to_string(), "string")
+    };
+    let datatype = match datatype {
+        Some(dt) if !dt.starts_with("@") => dt.to_string(),
+        _ => format!("{}{}", XSD, default_type)
+    };
+    Value::make_typed_literal(&lexical, NamedNode::make_uri(&datatype))
+}
+
+fn make_list(items : Vec<Value>, state : &mut ParserState) -> Resource {
+    let nil = Resource::make_uri(&format!("{}nil", RDF));
+    if items.is_empty() {
+        return nil;
+    }
+    let head = state.fresh_bnode();
+    let mut node = head.clone();
+    let n = items.len();
+    for (i, item) in items.into_iter().enumerate() {
+        state.triples.push(Triple(node.clone(),
+            NamedNode::make_uri(&format!("{}first", RDF)), item));
+        let next = if i + 1 < n { state.fresh_bnode() } else { nil.clone() };
+        state.triples.push(Triple(node,
+            NamedNode::make_uri(&format!("{}rest", RDF)), next.clone().as_value()));
+        node = next;
+    }
+    head
+}
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum JsonLdError {
+        Json(err : serde_json::Error) {
+            from()
+            display("Could not read JSON: {}", err)
+        }
+        Context(msg : String) {
+            description("Bad JSON-LD context")
+            display("Bad JSON-LD context: {}", msg)
+        }
+        Syntax(msg : String) {
+            description("Bad JSON-LD")
+            display("Bad JSON-LD: {}", msg)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let doc : JsonValue = serde_json::from_str("{
+            \"@context\": {
+                \"ontolex\": \"http://www.w3.org/ns/lemon/ontolex#\",
+                \"canonicalForm\": \"ontolex:canonicalForm\",
+                \"writtenRep\": { \"@id\": \"ontolex:writtenRep\", \"@language\": \"en\" },
+                \"reference\": { \"@id\": \"ontolex:reference\", \"@type\": \"@id\" },
+                \"sense\": \"ontolex:sense\"
+            },
+            \"@id\": \"http://example.org/cat\",
+            \"@type\": \"ontolex:LexicalEntry\",
+            \"canonicalForm\": { \"writtenRep\": \"cat\" },
+            \"sense\": { \"reference\": \"http://example.org/Cat\" },
+            \"unmapped\": \"dropped\"
+        }").unwrap();
+        let triples = parse_json_ld(&doc, &JsonLdOptions::new()).unwrap();
+        let cat = Resource::make_uri("http://example.org/cat");
+        assert_eq!(triples, vec![
+            Triple(cat.clone(), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"), Value::make_uri("http://www.w3.org/ns/lemon/ontolex#LexicalEntry")),
+            Triple(Resource::make_blank("nodeID0"), NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#writtenRep"), Value::make_lang_literal("cat", "en")),
+            Triple(cat.clone(), NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#canonicalForm"), Value::make_blank("nodeID0")),
+            Triple(Resource::make_blank("nodeID1"), NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#reference"), Value::make_uri("http://example.org/Cat")),
+            Triple(cat.clone(), NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#sense"), Value::make_blank("nodeID1"))
+        ]);
+    }
+
+    #[test]
+    fn test_graph_and_values() {
+        let doc : JsonValue = serde_json::from_str("{
+            \"@context\": { \"@vocab\": \"http://example.org/\", \"@base\": \"http://example.org/data/\" },
+            \"@graph\": [
+                { \"@id\": \"a\", \"count\": 3, \"ratio\": 0.5, \"flag\": true,
+                  \"label\": { \"@value\": \"A\", \"@language\": \"EN\" },
+                  \"items\": { \"@list\": [ \"x\" ] } },
+                { \"@id\": \"_:b\", \"label\": \"B\" }
+            ]
+        }").unwrap();
+        assert!(is_json_ld(&doc));
+        let triples = parse_json_ld(&doc, &JsonLdOptions::new()).unwrap();
+        let a = Resource::make_uri("http://example.org/data/a");
+        assert!(triples.contains(&Triple(a.clone(), NamedNode::make_uri("http://example.org/count"),
+            Value::make_typed_literal("3", NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#integer")))));
+        assert!(triples.contains(&Triple(a.clone(), NamedNode::make_uri("http://example.org/ratio"),
+            Value::make_typed_literal("5.0E-1", NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#double")))));
+        assert!(triples.contains(&Triple(a.clone(), NamedNode::make_uri("http://example.org/flag"),
+            Value::make_typed_literal("true", NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#boolean")))));
+        assert!(triples.contains(&Triple(a.clone(), NamedNode::make_uri("http://example.org/label"),
+            Value::make_lang_literal("A", "en"))));
+        assert!(triples.iter().any(|t| t.0.is_bnode() && t.2 == Value::make_literal("B")));
+        assert_eq!(triples.len(), 8);
+    }
+
+    #[test]
+    fn test_reader() {
+        let doc : JsonValue = serde_json::from_str("{
+            \"@context\": { \"name\": \"http://xmlns.com/foaf/0.1/name\" },
+            \"@graph\": [
+                { \"@id\": \"http://example.org/joe\", \"name\": \"Joe\" },
+                { \"@id\": \"http://example.org/ann\", \"@context\": \"http://example.org/missing.jsonld\" }
+            ]
+        }").unwrap();
+        let options = JsonLdOptions::new();
+        let mut reader = JsonLdReader::new(&doc, &options).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap(), Triple(Resource::make_uri("http://example.org/joe"),
+            NamedNode::make_uri("http://xmlns.com/foaf/0.1/name"), Value::make_literal("Joe")));
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_offline_context() {
+        // Named by the process, so that concurrent test runs do not share it
+        let path = std::env::temp_dir().join(format!("eds-test-context-{}.jsonld", std::process::id()));
+        std::fs::write(&path, "{ \"@context\": { \"name\": \"http://xmlns.com/foaf/0.1/name\" } }").unwrap();
+        let mut options = JsonLdOptions::new();
+        options.contexts.insert("http://example.org/context.jsonld".to_string(), path.to_string_lossy().to_string());
+        let doc : JsonValue = serde_json::from_str("{
+            \"@context\": \"http://example.org/context.jsonld\",
+            \"@id\": \"http://example.org/joe\",
+            \"name\": \"Joe\"
+        }").unwrap();
+        let triples = parse_json_ld(&doc, &options);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(triples.unwrap(), vec![
+            Triple(Resource::make_uri("http://example.org/joe"), NamedNode::make_uri("http://xmlns.com/foaf/0.1/name"), Value::make_literal("Joe"))
+        ]);
+
+        let doc : JsonValue = serde_json::from_str("{ \"@context\": \"http://example.org/missing.jsonld\" }").unwrap();
+        assert!(parse_json_ld(&doc, &JsonLdOptions::new()).is_err());
+    }
+}
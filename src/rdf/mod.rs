@@ -1,2 +1,6 @@
 pub mod model;
 pub mod turtle;
+pub mod rdfxml;
+pub mod ntriples;
+pub mod jsonld;
+pub mod graph;
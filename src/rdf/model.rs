@@ -1,12 +1,13 @@
 use rand;
 use rand::Rng;
 use std::cmp::{Ord, Ordering};
+use std::hash::{Hash, Hasher};
 
 /////////////////////////////////////////////////////////////////////////////////////////////
 // Values
 
 /// Any RDF value
-#[derive(PartialEq,Debug,Clone,Eq,PartialOrd,Ord)]
+#[derive(PartialEq,Debug,Clone,Eq,PartialOrd,Ord,Hash)]
 pub enum Value {
     Literal(Literal),
     Resource(Resource)
@@ -110,7 +111,7 @@ impl ToString for Resource {
 }
 
 /// A Resource (non-literal) RDF value
-#[derive(Debug,Clone,Eq,Hash)]
+#[derive(Debug,Clone,Eq)]
 pub enum NamedNode {
     URIRef(String),
     QName(Namespace, String)
@@ -152,6 +153,13 @@ impl PartialEq for NamedNode {
     }
 }
 
+// Must agree with `PartialEq`, so a QName hashes as its full URI
+impl Hash for NamedNode {
+    fn hash<H : Hasher>(&self, state : &mut H) {
+        self.uri().hash(state)
+    }
+}
+
 impl ToString for NamedNode {
     fn to_string(&self) -> String {
         match self {
@@ -162,7 +170,7 @@ impl ToString for NamedNode {
 }
 
 /// A literal RDF value
-#[derive(PartialEq,Debug,Clone,Eq,PartialOrd,Ord)]
+#[derive(PartialEq,Debug,Clone,Eq,PartialOrd,Ord,Hash)]
 pub enum Literal {
     PlainLiteral(String),
     LangLiteral(String, String),
@@ -213,6 +221,144 @@ impl Namespace {
     }
 }
 
-#[derive(PartialEq,Debug,Clone,Eq,PartialOrd,Ord)]
+#[derive(PartialEq,Debug,Clone,Eq,PartialOrd,Ord,Hash)]
 pub struct Triple(pub Resource,pub NamedNode,pub Value);
 
+
+/////////////////////////////////////////////////////////////////////////////////////////////
+// IRI resolution
+
+/// Resolve a (possibly relative) IRI against a base IRI following RFC 3986 §5.2.
+/// If the base is empty the reference is returned unchanged
+pub fn resolve_iri(base : &str, iri : &str) -> String {
+    let r = IriRef::split(iri);
+    if r.scheme.is_some() {
+        return format!("{}{}", r.scheme_authority(), remove_dot_segments(r.path)) + &r.query_fragment();
+    }
+    if base.is_empty() {
+        return iri.to_string();
+    }
+    let b = IriRef::split(base);
+    if let Some(authority) = r.authority {
+        return format!("{}://{}{}{}", b.scheme.unwrap_or(""), authority,
+            remove_dot_segments(r.path), r.query_fragment());
+    }
+    let mut target = b.scheme_authority();
+    if r.path.is_empty() {
+        target.push_str(b.path);
+        match r.query {
+            Some(q) => target.push_str(&format!("?{}", q)),
+            None => if let Some(q) = b.query { target.push_str(&format!("?{}", q)) }
+        }
+        if let Some(f) = r.fragment {
+            target.push_str(&format!("#{}", f));
+        }
+    } else {
+        if r.path.starts_with("/") {
+            target.push_str(&remove_dot_segments(r.path));
+        } else if b.authority.is_some() && b.path.is_empty() {
+            target.push_str(&remove_dot_segments(&format!("/{}", r.path)));
+        } else {
+            let dir = match b.path.rfind('/') {
+                Some(i) => &b.path[..(i+1)],
+                None => ""
+            };
+            target.push_str(&remove_dot_segments(&format!("{}{}", dir, r.path)));
+        }
+        target.push_str(&r.query_fragment());
+    }
+    target
+}
+
+struct IriRef<'a> {
+    scheme : Option<&'a str>,
+    authority : Option<&'a str>,
+    path : &'a str,
+    query : Option<&'a str>,
+    fragment : Option<&'a str>
+}
+
+impl<'a> IriRef<'a> {
+    fn split(iri : &'a str) -> IriRef<'a> {
+        let (rest, fragment) = match iri.find('#') {
+            Some(i) => (&iri[..i], Some(&iri[(i+1)..])),
+            None => (iri, None)
+        };
+        let (rest, query) = match rest.find('?') {
+            Some(i) => (&rest[..i], Some(&rest[(i+1)..])),
+            None => (rest, None)
+        };
+        let (scheme, rest) = match rest.find(':') {
+            Some(i) if i > 0 && rest[..i].chars().enumerate().all(|(j,c)|
+                c.is_ascii_alphabetic() || (j > 0 && (c.is_ascii_digit() || c == '+' || c == '-' || c == '.'))) =>
+                (Some(&rest[..i]), &rest[(i+1)..]),
+            _ => (None, rest)
+        };
+        let (authority, path) = if let Some(after) = rest.strip_prefix("//") {
+            match after.find('/') {
+                Some(i) => (Some(&after[..i]), &after[i..]),
+                None => (Some(after), "")
+            }
+        } else {
+            (None, rest)
+        };
+        IriRef { scheme, authority, path, query, fragment }
+    }
+
+    fn scheme_authority(&self) -> String {
+        let mut s = String::new();
+        if let Some(scheme) = self.scheme {
+            s.push_str(scheme);
+            s.push(':');
+        }
+        if let Some(authority) = self.authority {
+            s.push_str("//");
+            s.push_str(authority);
+        }
+        s
+    }
+
+    fn query_fragment(&self) -> String {
+        let mut s = String::new();
+        if let Some(q) = self.query {
+            s.push('?');
+            s.push_str(q);
+        }
+        if let Some(f) = self.fragment {
+            s.push('#');
+            s.push_str(f);
+        }
+        s
+    }
+}
+
+fn remove_dot_segments(path : &str) -> String {
+    let mut input = path;
+    let mut output = String::new();
+    while !input.is_empty() {
+        if input.starts_with("../") {
+            input = &input[3..];
+        } else if input.starts_with("./") || input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            match output.rfind('/') {
+                Some(i) => output.truncate(i),
+                None => output.clear()
+            }
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = if input.starts_with("/") { 1 } else { 0 };
+            let end = match input[start..].find('/') {
+                Some(i) => i + start,
+                None => input.len()
+            };
+            output.push_str(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output
+}
@@ -0,0 +1,317 @@
+use crate::rdf::model::*;
+use std::io::BufRead;
+use std::result;
+
+type Result<T> = result::Result<T, NTriplesParserError>;
+
+/// A line-oriented parser for N-Triples and N-Quads. Triples are produced one
+/// at a time as the input is read, so the document is never held in memory.
+/// A bad line produces an error for that line only and parsing continues with
+/// the next line. The graph label of a quad is read but not returned.
+pub struct NTriplesParser<R : BufRead> {
+    input : R,
+    line_no : usize,
+    buf : Vec<u8>
+}
+
+impl<R : BufRead> NTriplesParser<R> {
+    pub fn new(input : R) -> NTriplesParser<R> {
+        NTriplesParser {
+            input,
+            line_no : 0,
+            buf : Vec::new()
+        }
+    }
+}
+
+impl<R : BufRead> Iterator for NTriplesParser<R> {
+    type Item = Result<Triple>;
+
+    fn next(&mut self) -> Option<Result<Triple>> {
+        loop {
+            self.buf.clear();
+            match self.input.read_until(b'\n', &mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {},
+                Err(e) => return Some(Err(NTriplesParserError::Io(e)))
+            }
+            self.line_no += 1;
+            // A line that is not UTF-8 is a bad line like any other
+            let line = match std::str::from_utf8(&self.buf) {
+                Ok(line) => line.trim_end_matches(['\n', '\r']),
+                Err(e) => return Some(Err(NTriplesParserError::Syntax(self.line_no, format!("Invalid UTF-8: {}", e))))
+            };
+            match parse_line(line) {
+                Ok(Some(triple)) => return Some(Ok(triple)),
+                Ok(None) => {},
+                Err(msg) => return Some(Err(NTriplesParserError::Syntax(self.line_no, msg)))
+            }
+        }
+    }
+}
+
+/// Parse a single line of N-Triples/N-Quads, returning `None` for blank and comment lines
+pub fn parse_line(line : &str) -> result::Result<Option<Triple>, String> {
+    let mut c = Cursor { s : line, pos : 0 };
+    c.skip_ws();
+    if c.at_end() || c.peek() == Some('#') {
+        return Ok(None);
+    }
+    let subj = match c.peek() {
+        Some('<') => Resource::Named(NamedNode::URIRef(c.iri()?)),
+        Some('_') => Resource::BlankNode(c.bnode()?),
+        _ => return Err(c.error("Expected IRI or blank node as subject"))
+    };
+    c.skip_ws();
+    let pred = match c.peek() {
+        Some('<') => NamedNode::URIRef(c.iri()?),
+        _ => return Err(c.error("Expected IRI as predicate"))
+    };
+    c.skip_ws();
+    let obj = match c.peek() {
+        Some('<') => Value::make_uri(&c.iri()?),
+        Some('_') => Value::make_blank(&c.bnode()?),
+        Some('"') => Value::Literal(c.literal()?),
+        _ => return Err(c.error("Expected IRI, blank node or literal as object"))
+    };
+    c.skip_ws();
+    // N-Quads graph label
+    match c.peek() {
+        Some('<') => { c.iri()?; },
+        Some('_') => { c.bnode()?; },
+        _ => {}
+    }
+    c.skip_ws();
+    if c.next() != Some('.') {
+        return Err(c.error("Expected '.' at end of statement"));
+    }
+    c.skip_ws();
+    if !c.at_end() && c.peek() != Some('#') {
+        return Err(c.error("Unexpected content after '.'"));
+    }
+    Ok(Some(Triple(subj, pred, obj)))
+}
+
+struct Cursor<'a> {
+    s : &'a str,
+    pos : usize
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if let Some(c) = c {
+            self.pos += c.len_utf8();
+        }
+        c
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.s.len()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek() == Some(' ') || self.peek() == Some('\t') {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, msg : &str) -> String {
+        format!("{} (column {})", msg, self.s[..self.pos].chars().count() + 1)
+    }
+
+    fn iri(&mut self) -> result::Result<String, String> {
+        self.next();
+        let mut iri = String::new();
+        loop {
+            match self.next() {
+                Some('>') => return Ok(iri),
+                Some('\\') => iri.push(self.unicode_escape()?),
+                Some(c) if c == ' ' || c == '<' || c == '"' || c == '{' || c == '}' ||
+                    c == '|' || c == '^' || c == '`' || c <= '\u{20}' =>
+                        return Err(self.error(&format!("Illegal character in IRI: {:?}", c))),
+                Some(c) => iri.push(c),
+                None => return Err(self.error("Unterminated IRI"))
+            }
+        }
+    }
+
+    fn bnode(&mut self) -> result::Result<String, String> {
+        self.next();
+        if self.next() != Some(':') {
+            return Err(self.error("Expected '_:'"));
+        }
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' || c == ':' || c == '\u{00B7}' {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        // A label may not end with a '.'
+        while self.pos > start && self.s[..self.pos].ends_with('.') {
+            self.pos -= 1;
+        }
+        if self.pos == start {
+            Err(self.error("Empty blank node label"))
+        } else {
+            Ok(self.s[start..self.pos].to_string())
+        }
+    }
+
+    fn literal(&mut self) -> result::Result<Literal, String> {
+        self.next();
+        let mut lit = String::new();
+        loop {
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => {
+                    match self.peek() {
+                        Some('t') => { self.next(); lit.push('\t') },
+                        Some('b') => { self.next(); lit.push('\u{08}') },
+                        Some('n') => { self.next(); lit.push('\n') },
+                        Some('r') => { self.next(); lit.push('\r') },
+                        Some('f') => { self.next(); lit.push('\u{0C}') },
+                        Some('"') => { self.next(); lit.push('"') },
+                        Some('\'') => { self.next(); lit.push('\'') },
+                        Some('\\') => { self.next(); lit.push('\\') },
+                        _ => lit.push(self.unicode_escape()?)
+                    }
+                },
+                Some(c) => lit.push(c),
+                None => return Err(self.error("Unterminated string"))
+            }
+        }
+        match self.peek() {
+            Some('@') => {
+                self.next();
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphanumeric() || c == '-' {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if self.pos == start {
+                    Err(self.error("Empty language tag"))
+                } else {
+                    Ok(Literal::LangLiteral(lit, self.s[start..self.pos].to_string()))
+                }
+            },
+            Some('^') => {
+                self.next();
+                if self.next() != Some('^') || self.peek() != Some('<') {
+                    return Err(self.error("Expected '^^<' before datatype"));
+                }
+                Ok(Literal::TypedLiteral(lit, NamedNode::URIRef(self.iri()?)))
+            },
+            _ => Ok(Literal::PlainLiteral(lit))
+        }
+    }
+
+    /// Read a `\uXXXX` or `\UXXXXXXXX` escape (the backslash has already been read)
+    fn unicode_escape(&mut self) -> result::Result<char, String> {
+        let len = match self.next() {
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(self.error("Bad escape sequence"))
+        };
+        if self.pos + len > self.s.len() || !self.s.is_char_boundary(self.pos + len) {
+            return Err(self.error("Truncated unicode escape"));
+        }
+        let hex = &self.s[self.pos..(self.pos + len)];
+        self.pos += len;
+        u32::from_str_radix(hex, 16).ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| self.error(&format!("Bad unicode escape: {}", hex)))
+    }
+}
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum NTriplesParserError {
+        Io(err : std::io::Error) {
+            from()
+            display("Could not read N-Triples: {}", err)
+        }
+        Syntax(line : usize, msg : String) {
+            description("N-Triples syntax error")
+            display("Bad N-Triples at line {}: {}", line, msg)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_ntriples() {
+        let doc = "# A comment
+<http://example.org/foo> <http://example.org/bar> <http://example.org/baz> .
+
+_:b1 <http://example.org/bar> \"plain\" .
+_:b1 <http://example.org/bar> \"lang\"@en-GB .
+_:b1 <http://example.org/bar> \"3\"^^<http://www.w3.org/2001/XMLSchema#integer> . # trailing comment
+<http://example.org/foo> <http://example.org/bar> \"tab\\there \\u00E9\" .
+";
+        let triples : Vec<Triple> = NTriplesParser::new(doc.as_bytes()).map(|t| t.unwrap()).collect();
+        assert_eq!(triples, vec![
+            Triple(Resource::make_uri("http://example.org/foo"), NamedNode::make_uri("http://example.org/bar"), Value::make_uri("http://example.org/baz")),
+            Triple(Resource::make_blank("b1"), NamedNode::make_uri("http://example.org/bar"), Value::make_literal("plain")),
+            Triple(Resource::make_blank("b1"), NamedNode::make_uri("http://example.org/bar"), Value::make_lang_literal("lang", "en-GB")),
+            Triple(Resource::make_blank("b1"), NamedNode::make_uri("http://example.org/bar"), Value::make_typed_literal("3", NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#integer"))),
+            Triple(Resource::make_uri("http://example.org/foo"), NamedNode::make_uri("http://example.org/bar"), Value::make_literal("tab\there é"))
+        ]);
+    }
+
+    #[test]
+    fn test_nquads() {
+        let doc = "<http://example.org/foo> <http://example.org/bar> \"x\" <http://example.org/graph> .\n";
+        let triples : Vec<Triple> = NTriplesParser::new(doc.as_bytes()).map(|t| t.unwrap()).collect();
+        assert_eq!(triples, vec![
+            Triple(Resource::make_uri("http://example.org/foo"), NamedNode::make_uri("http://example.org/bar"), Value::make_literal("x"))
+        ]);
+    }
+
+    #[test]
+    fn test_bad_lines() {
+        let doc = "<http://example.org/a> <http://example.org/b> <http://example.org/c> .
+<http://example.org/a> \"bad\" <http://example.org/c> .
+<http://example.org/a> <http://example.org/b> \"unterminated .
+<http://example.org/a> <http://example.org/b> <http://example.org/d> .
+";
+        let results : Vec<Result<Triple>> = NTriplesParser::new(doc.as_bytes()).collect();
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok());
+        match results[1] {
+            Err(NTriplesParserError::Syntax(line, _)) => assert_eq!(line, 2),
+            _ => panic!("Expected error on line 2")
+        }
+        match results[2] {
+            Err(NTriplesParserError::Syntax(line, _)) => assert_eq!(line, 3),
+            _ => panic!("Expected error on line 3")
+        }
+        assert!(results[3].is_ok());
+    }
+    #[test]
+    fn test_invalid_utf8() {
+        let mut doc = b"<http://example.org/a> <http://example.org/b> <http://example.org/c> .\n".to_vec();
+        doc.extend_from_slice(b"<http://example.org/a> <http://example.org/b> \"caf\xe9\" .\n");
+        doc.extend_from_slice(b"<http://example.org/a> <http://example.org/b> <http://example.org/d> .\n");
+        let results : Vec<Result<Triple>> = NTriplesParser::new(&doc[..]).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        match results[1] {
+            Err(NTriplesParserError::Syntax(line, _)) => assert_eq!(line, 2),
+            _ => panic!("Expected error on line 2")
+        }
+        assert!(results[2].is_ok());
+    }
+}
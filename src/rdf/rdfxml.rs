@@ -0,0 +1,515 @@
+use xml::reader::{EventReader, XmlEvent};
+use xml::name::OwnedName;
+use xml::attribute::OwnedAttribute;
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
+use crate::rdf::model::*;
+use std::collections::HashMap;
+use std::io::Read;
+use std::result;
+
+type Result<T> = result::Result<T, RdfXmlParserError>;
+
+const RDF : &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML : &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Parse an RDF/XML document into a list of triples
+#[cfg(test)]
+pub fn parse_rdfxml<R : Read>(input : R) -> Result<Vec<Triple>> {
+    parse_rdfxml_with_base(input, "")
+}
+
+/// Parse an RDF/XML document, resolving relative IRIs against the given base
+#[cfg(test)]
+pub fn parse_rdfxml_with_base<R : Read>(input : R, base : &str) -> Result<Vec<Triple>> {
+    RdfXmlReader::with_base(input, base)?.collect()
+}
+
+/// Returns the triples of an RDF/XML document one top-level node at a time.
+/// The XML is read first, but the triples are only produced as they are
+/// needed, so that they need not all be held in memory. Reading stops at the
+/// first error
+pub struct RdfXmlReader {
+    nodes : std::vec::IntoIter<Element>,
+    base : String,
+    lang : Option<String>,
+    state : ParserState,
+    pending : std::vec::IntoIter<Triple>
+}
+
+impl RdfXmlReader {
+    pub fn new<R : Read>(input : R) -> Result<RdfXmlReader> {
+        RdfXmlReader::with_base(input, "")
+    }
+
+    pub fn with_base<R : Read>(input : R, base : &str) -> Result<RdfXmlReader> {
+        let root = read_document(input)?;
+        let (base, lang) = scope(&root, base, &None);
+        let nodes = if is_rdf(&root.name, "RDF") {
+            root.children.into_iter().filter_map(|c| match c {
+                Node::Element(e) => Some(e),
+                Node::Text(_) => None
+            }).collect()
+        } else {
+            vec![root]
+        };
+        Ok(RdfXmlReader {
+            nodes : nodes.into_iter(),
+            base, lang,
+            state : ParserState {
+                triples : Vec::new(),
+                bnodes : 0,
+                node_ids : HashMap::new()
+            },
+            pending : Vec::new().into_iter()
+        })
+    }
+}
+
+impl Iterator for RdfXmlReader {
+    type Item = Result<Triple>;
+
+    fn next(&mut self) -> Option<Result<Triple>> {
+        loop {
+            if let Some(triple) = self.pending.next() {
+                return Some(Ok(triple));
+            }
+            let node = self.nodes.next()?;
+            if let Err(e) = process_node(&node, &self.base, &self.lang, &mut self.state) {
+                self.nodes = Vec::new().into_iter();
+                return Some(Err(e));
+            }
+            self.pending = std::mem::take(&mut self.state.triples).into_iter();
+        }
+    }
+}
+
+struct ParserState {
+    triples : Vec<Triple>,
+    bnodes : usize,
+    /// The blank nodes given for each `rdf:nodeID` of the document
+    node_ids : HashMap<String, Resource>
+}
+
+impl ParserState {
+    fn fresh_bnode(&mut self) -> Resource {
+        let id = format!("nodeID{}", self.bnodes);
+        self.bnodes += 1;
+        Resource::make_blank(&id)
+    }
+
+    /// The blank node of an `rdf:nodeID`. These are also generated, so that
+    /// they cannot be the same as the blank nodes of nodes without an id
+    fn named_bnode(&mut self, node_id : &str) -> Resource {
+        if let Some(node) = self.node_ids.get(node_id) {
+            return node.clone();
+        }
+        let node = self.fresh_bnode();
+        self.node_ids.insert(node_id.to_string(), node.clone());
+        node
+    }
+}
+
+struct Element {
+    name : OwnedName,
+    attributes : Vec<OwnedAttribute>,
+    children : Vec<Node>
+}
+
+enum Node {
+    Element(Element),
+    Text(String)
+}
+
+impl Element {
+    fn elements(&self) -> impl Iterator<Item=&Element> {
+        self.children.iter().flat_map(|c| match c {
+            Node::Element(e) => Some(e),
+            Node::Text(_) => None
+        })
+    }
+
+    fn text(&self) -> String {
+        let mut s = String::new();
+        for c in self.children.iter() {
+            if let Node::Text(t) = c {
+                s.push_str(t);
+            }
+        }
+        s
+    }
+
+    fn rdf_attr(&self, local_name : &str) -> Option<&str> {
+        self.attributes.iter().find(|a| is_rdf(&a.name, local_name))
+            .map(|a| a.value.as_str())
+    }
+
+    /// The attributes that are to be read as properties
+    fn property_attrs(&self) -> impl Iterator<Item=&OwnedAttribute> {
+        self.attributes.iter().filter(|a| {
+            if a.name.namespace.as_deref() == Some(XML) ||
+                a.name.prefix.as_deref() == Some("xml") {
+                false
+            } else if a.name.namespace.as_deref() == Some(RDF) {
+                !["about", "ID", "nodeID", "resource", "parseType", "datatype", "aboutEach", "aboutEachPrefix", "bagID"]
+                    .contains(&a.name.local_name.as_str())
+            } else {
+                a.name.namespace.is_some()
+            }
+        })
+    }
+}
+
+fn read_document<R : Read>(input : R) -> Result<Element> {
+    let parser = EventReader::new(input);
+    let mut stack : Vec<Element> = Vec::new();
+    let mut root = None;
+    for e in parser {
+        match e? {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                stack.push(Element { name, attributes, children : Vec::new() });
+            },
+            XmlEvent::EndElement { .. } => {
+                let elem = stack.pop().ok_or(RdfXmlParserError::Syntax("Unbalanced end tag".to_string()))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Node::Element(elem)),
+                    None => root = Some(elem)
+                }
+            },
+            XmlEvent::Characters(s) | XmlEvent::CData(s) | XmlEvent::Whitespace(s) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(Node::Text(s));
+                }
+            },
+            _ => {}
+        }
+    }
+    root.ok_or(RdfXmlParserError::Syntax("Document has no root element".to_string()))
+}
+
+fn is_rdf(name : &OwnedName, local_name : &str) -> bool {
+    name.namespace.as_deref() == Some(RDF) && name.local_name == local_name
+}
+
+fn name_uri(name : &OwnedName) -> Result<String> {
+    match name.namespace {
+        Some(ref ns) => Ok(format!("{}{}", ns, name.local_name)),
+        None => Err(RdfXmlParserError::Syntax(format!("Element or attribute {} has no namespace", name.local_name)))
+    }
+}
+
+/// Apply the xml:base and xml:lang of an element to the inherited values
+fn scope(elem : &Element, base : &str, lang : &Option<String>) -> (String, Option<String>) {
+    let mut base = base.to_string();
+    let mut lang = lang.clone();
+    for attr in elem.attributes.iter() {
+        if attr.name.namespace.as_deref() == Some(XML) ||
+            attr.name.prefix.as_deref() == Some("xml") {
+            if attr.name.local_name == "base" {
+                base = resolve_iri(&base, &attr.value);
+            } else if attr.name.local_name == "lang" {
+                lang = if attr.value.is_empty() { None } else { Some(attr.value.to_lowercase()) };
+            }
+        }
+    }
+    (base, lang)
+}
+
+fn make_literal(value : &str, lang : &Option<String>) -> Value {
+    match lang {
+        Some(l) => Value::make_lang_literal(value, l),
+        None => Value::make_literal(value)
+    }
+}
+
+fn process_node(elem : &Element, base : &str, lang : &Option<String>,
+                state : &mut ParserState) -> Result<Resource> {
+    let (base, lang) = scope(elem, base, lang);
+    let subject = if let Some(about) = elem.rdf_attr("about") {
+        Resource::make_uri(&resolve_iri(&base, about))
+    } else if let Some(id) = elem.rdf_attr("ID") {
+        Resource::make_uri(&resolve_iri(&base, &format!("#{}", id)))
+    } else if let Some(node_id) = elem.rdf_attr("nodeID") {
+        state.named_bnode(node_id)
+    } else {
+        state.fresh_bnode()
+    };
+    fill_node(elem, subject, &base, &lang, state)
+}
+
+fn fill_node(elem : &Element, subject : Resource, base : &str, lang : &Option<String>,
+             state : &mut ParserState) -> Result<Resource> {
+    if !is_rdf(&elem.name, "Description") {
+        state.triples.push(Triple(subject.clone(),
+            NamedNode::make_uri(&format!("{}type", RDF)),
+            Value::make_uri(&name_uri(&elem.name)?)));
+    }
+    for attr in elem.property_attrs() {
+        if is_rdf(&attr.name, "type") {
+            state.triples.push(Triple(subject.clone(),
+                NamedNode::make_uri(&format!("{}type", RDF)),
+                Value::make_uri(&resolve_iri(base, &attr.value))));
+        } else {
+            state.triples.push(Triple(subject.clone(),
+                NamedNode::make_uri(&name_uri(&attr.name)?),
+                make_literal(&attr.value, lang)));
+        }
+    }
+    let mut li = 1;
+    for child in elem.elements() {
+        process_property(child, &subject, base, lang, &mut li, state)?;
+    }
+    Ok(subject)
+}
+
+fn process_property(elem : &Element, subject : &Resource, base : &str, lang : &Option<String>,
+                    li : &mut usize, state : &mut ParserState) -> Result<()> {
+    let (base, lang) = scope(elem, base, lang);
+    let pred = if is_rdf(&elem.name, "li") {
+        *li += 1;
+        NamedNode::make_uri(&format!("{}_{}", RDF, *li - 1))
+    } else {
+        NamedNode::make_uri(&name_uri(&elem.name)?)
+    };
+    let children : Vec<&Element> = elem.elements().collect();
+
+    let object = match elem.rdf_attr("parseType") {
+        Some("Resource") => {
+            let node = state.fresh_bnode();
+            state.triples.push(Triple(subject.clone(), pred.clone(), node.clone().as_value()));
+            let mut li2 = 1;
+            for child in children {
+                process_property(child, &node, &base, &lang, &mut li2, state)?;
+            }
+            node.as_value()
+        },
+        Some("Collection") => {
+            let mut items = Vec::new();
+            for child in children {
+                items.push(process_node(child, &base, &lang, state)?);
+            }
+            make_collection(items, state).as_value()
+        },
+        Some(_) => {
+            let mut content = String::new();
+            for child in elem.children.iter() {
+                write_xml(child, &mut content);
+            }
+            Value::make_typed_literal(&content, NamedNode::make_uri(&format!("{}XMLLiteral", RDF)))
+        },
+        None => {
+            if children.len() > 1 {
+                return Err(RdfXmlParserError::Syntax(format!("Property {} has more than one node element", pred.uri())));
+            } else if children.len() == 1 {
+                process_node(children[0], &base, &lang, state)?.as_value()
+            } else if elem.rdf_attr("resource").is_some() || elem.rdf_attr("nodeID").is_some() ||
+                elem.property_attrs().next().is_some() {
+                let node = if let Some(r) = elem.rdf_attr("resource") {
+                    Resource::make_uri(&resolve_iri(&base, r))
+                } else if let Some(n) = elem.rdf_attr("nodeID") {
+                    state.named_bnode(n)
+                } else {
+                    state.fresh_bnode()
+                };
+                for attr in elem.property_attrs() {
+                    if is_rdf(&attr.name, "type") {
+                        state.triples.push(Triple(node.clone(),
+                            NamedNode::make_uri(&format!("{}type", RDF)),
+                            Value::make_uri(&resolve_iri(&base, &attr.value))));
+                    } else {
+                        state.triples.push(Triple(node.clone(),
+                            NamedNode::make_uri(&name_uri(&attr.name)?),
+                            make_literal(&attr.value, &lang)));
+                    }
+                }
+                node.as_value()
+            } else if let Some(dt) = elem.rdf_attr("datatype") {
+                Value::make_typed_literal(&elem.text(), NamedNode::make_uri(&resolve_iri(&base, dt)))
+            } else {
+                make_literal(&elem.text(), &lang)
+            }
+        }
+    };
+
+    match elem.rdf_attr("parseType") {
+        Some("Resource") => {},
+        _ => state.triples.push(Triple(subject.clone(), pred.clone(), object.clone()))
+    }
+
+    if let Some(id) = elem.rdf_attr("ID") {
+        // Reify the statement
+        let stmt = Resource::make_uri(&resolve_iri(&base, &format!("#{}", id)));
+        state.triples.push(Triple(stmt.clone(), NamedNode::make_uri(&format!("{}type", RDF)),
+            Value::make_uri(&format!("{}Statement", RDF))));
+        state.triples.push(Triple(stmt.clone(), NamedNode::make_uri(&format!("{}subject", RDF)),
+            subject.clone().as_value()));
+        state.triples.push(Triple(stmt.clone(), NamedNode::make_uri(&format!("{}predicate", RDF)),
+            pred.as_value()));
+        state.triples.push(Triple(stmt, NamedNode::make_uri(&format!("{}object", RDF)),
+            object));
+    }
+    Ok(())
+}
+
+fn make_collection(items : Vec<Resource>, state : &mut ParserState) -> Resource {
+    let nil = Resource::make_uri(&format!("{}nil", RDF));
+    if items.is_empty() {
+        return nil;
+    }
+    let head = state.fresh_bnode();
+    let mut node = head.clone();
+    let n = items.len();
+    for (i, item) in items.into_iter().enumerate() {
+        state.triples.push(Triple(node.clone(),
+            NamedNode::make_uri(&format!("{}first", RDF)), item.as_value()));
+        let next = if i + 1 < n { state.fresh_bnode() } else { nil.clone() };
+        state.triples.push(Triple(node,
+            NamedNode::make_uri(&format!("{}rest", RDF)), next.clone().as_value()));
+        node = next;
+    }
+    head
+}
+
+fn write_xml(node : &Node, out : &mut String) {
+    match node {
+        Node::Text(t) => out.push_str(&escape_str_pcdata(t)),
+        Node::Element(e) => {
+            let qname = match e.name.prefix {
+                Some(ref p) => format!("{}:{}", p, e.name.local_name),
+                None => e.name.local_name.clone()
+            };
+            out.push('<');
+            out.push_str(&qname);
+            for attr in e.attributes.iter() {
+                out.push(' ');
+                if let Some(ref p) = attr.name.prefix {
+                    out.push_str(p);
+                    out.push(':');
+                }
+                out.push_str(&attr.name.local_name);
+                out.push_str("=\"");
+                out.push_str(&escape_str_attribute(&attr.value));
+                out.push('"');
+            }
+            out.push('>');
+            for c in e.children.iter() {
+                write_xml(c, out);
+            }
+            out.push_str("</");
+            out.push_str(&qname);
+            out.push('>');
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum RdfXmlParserError {
+        Xml(err : xml::reader::Error) {
+            from()
+            display("Could not read XML: {}", err)
+        }
+        Syntax(msg : String) {
+            description("RDF/XML syntax error")
+            display("Bad RDF/XML: {}", msg)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_description() {
+        let result = parse_rdfxml("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"
+    xmlns:ex=\"http://example.org/\">
+  <rdf:Description rdf:about=\"http://example.org/foo\" ex:label=\"foo\">
+    <ex:bar rdf:resource=\"http://example.org/bar\"/>
+    <ex:name xml:lang=\"EN\">Foo</ex:name>
+    <ex:count rdf:datatype=\"http://www.w3.org/2001/XMLSchema#integer\">3</ex:count>
+  </rdf:Description>
+</rdf:RDF>".as_bytes());
+        assert_eq!(result.unwrap(), vec![
+            Triple(Resource::make_uri("http://example.org/foo"), NamedNode::make_uri("http://example.org/label"), Value::make_literal("foo")),
+            Triple(Resource::make_uri("http://example.org/foo"), NamedNode::make_uri("http://example.org/bar"), Value::make_uri("http://example.org/bar")),
+            Triple(Resource::make_uri("http://example.org/foo"), NamedNode::make_uri("http://example.org/name"), Value::make_lang_literal("Foo", "en")),
+            Triple(Resource::make_uri("http://example.org/foo"), NamedNode::make_uri("http://example.org/count"), Value::make_typed_literal("3", NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#integer")))
+        ]);
+    }
+
+    #[test]
+    fn test_typed_nodes() {
+        let result = parse_rdfxml("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"
+    xmlns:ontolex=\"http://www.w3.org/ns/lemon/ontolex#\"
+    xml:base=\"http://example.org/dict/\">
+  <ontolex:LexicalEntry rdf:ID=\"cat\">
+    <ontolex:canonicalForm>
+      <ontolex:Form>
+        <ontolex:writtenRep xml:lang=\"en\">cat</ontolex:writtenRep>
+      </ontolex:Form>
+    </ontolex:canonicalForm>
+    <ontolex:sense rdf:parseType=\"Resource\">
+      <ontolex:reference rdf:resource=\"../ontology#Cat\"/>
+    </ontolex:sense>
+  </ontolex:LexicalEntry>
+</rdf:RDF>".as_bytes());
+        let entry = Resource::make_uri("http://example.org/dict/#cat");
+        assert_eq!(result.unwrap(), vec![
+            Triple(entry.clone(), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"), Value::make_uri("http://www.w3.org/ns/lemon/ontolex#LexicalEntry")),
+            Triple(Resource::make_blank("nodeID0"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"), Value::make_uri("http://www.w3.org/ns/lemon/ontolex#Form")),
+            Triple(Resource::make_blank("nodeID0"), NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#writtenRep"), Value::make_lang_literal("cat", "en")),
+            Triple(entry.clone(), NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#canonicalForm"), Value::make_blank("nodeID0")),
+            Triple(entry.clone(), NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#sense"), Value::make_blank("nodeID1")),
+            Triple(Resource::make_blank("nodeID1"), NamedNode::make_uri("http://www.w3.org/ns/lemon/ontolex#reference"), Value::make_uri("http://example.org/ontology#Cat"))
+        ]);
+    }
+
+    #[test]
+    fn test_reader() {
+        let mut reader = RdfXmlReader::new("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"
+    xmlns:ex=\"http://example.org/\">
+  <rdf:Description rdf:about=\"http://example.org/foo\" ex:label=\"foo\"/>
+  <rdf:Description rdf:about=\"http://example.org/bar\"><label>bar</label></rdf:Description>
+  <rdf:Description rdf:about=\"http://example.org/baz\" ex:label=\"baz\"/>
+</rdf:RDF>".as_bytes()).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap(), Triple(Resource::make_uri("http://example.org/foo"),
+            NamedNode::make_uri("http://example.org/label"), Value::make_literal("foo")));
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_collection() {
+        let result = parse_rdfxml("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"
+    xmlns:ex=\"http://example.org/\">
+  <rdf:Description rdf:about=\"http://example.org/foo\">
+    <ex:list rdf:parseType=\"Collection\">
+      <rdf:Description rdf:about=\"http://example.org/a\"/>
+      <rdf:Description rdf:about=\"http://example.org/b\"/>
+    </ex:list>
+  </rdf:Description>
+</rdf:RDF>".as_bytes()).unwrap();
+        assert_eq!(result.len(), 5);
+        assert_eq!(result[4], Triple(Resource::make_uri("http://example.org/foo"), NamedNode::make_uri("http://example.org/list"), Value::make_blank("nodeID0")));
+    }
+    #[test]
+    fn test_node_ids() {
+        let result = parse_rdfxml("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"
+    xmlns:ex=\"http://example.org/\">
+  <rdf:Description rdf:about=\"http://example.org/foo\">
+    <ex:anon><rdf:Description ex:label=\"anonymous\"/></ex:anon>
+    <ex:named rdf:nodeID=\"nodeID0\"/>
+  </rdf:Description>
+  <rdf:Description rdf:nodeID=\"nodeID0\" ex:label=\"named\"/>
+</rdf:RDF>".as_bytes()).unwrap();
+        let object = |pred : &str| result.iter().find(|t| t.1 == NamedNode::make_uri(pred)).unwrap().2.clone();
+        let anon = object("http://example.org/anon");
+        let named = object("http://example.org/named");
+        assert_ne!(anon, named);
+        let label = |node : &Value| result.iter()
+            .find(|t| t.0.clone().as_value() == *node && t.1 == NamedNode::make_uri("http://example.org/label"))
+            .map(|t| t.2.clone());
+        assert_eq!(label(&anon), Some(Value::make_literal("anonymous")));
+        assert_eq!(label(&named), Some(Value::make_literal("named")));
+    }
+}
@@ -1,264 +1,773 @@
-use pest::Parser;
-use pest::iterators::Pair;
-use pest;
 use crate::rdf::model::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, BufRead, BufReader};
 use std::result;
 
 type Result<T> = result::Result<T, TurtleParserError>;
 
-#[derive(Parser)]
-#[grammar = "rdf/turtle.pest"]
-struct TurtleParser;
-
+/// Parse a Turtle document into a list of triples
+#[cfg(test)]
 pub fn parse_turtle(data : &str) -> Result<Vec<Triple>> {
-    let pairs = TurtleParser::parse(Rule::turtle_doc, data)
-        .map_err(|e| TurtleParserError::Parse(format!("{}", e)))?;
+    parse_turtle_with_base(data, "")
+}
 
-    let mut state = ParserState {
-        triples : Vec::new(),
-        namespaces : HashMap::new(),
-        bnodes : 0
-    };
+/// Parse a Turtle document, resolving relative IRIs against `base` until
+/// the document sets its own base
+#[cfg(test)]
+pub fn parse_turtle_with_base(data : &str, base : &str) -> Result<Vec<Triple>> {
+    TurtleReader::with_base(data.as_bytes(), base).collect()
+}
 
-    let mut read = 0;
+/// An incremental Turtle parser. The input is read only as far as needed and
+/// the triples of each statement are returned as soon as the statement is
+/// complete, so memory use depends on the largest statement rather than the
+/// size of the document. Parsing stops at the first error
+pub struct TurtleReader<R : Read> {
+    lexer : Lexer<R>,
+    namespaces : HashMap<String, Namespace>,
+    base : String,
+    bnodes : usize,
+    pending : VecDeque<Triple>,
+    done : bool
+}
 
+impl<R : Read> TurtleReader<R> {
+    pub fn new(input : R) -> TurtleReader<R> {
+        TurtleReader::with_base(input, "")
+    }
+
+    pub fn with_base(input : R, base : &str) -> TurtleReader<R> {
+        TurtleReader {
+            lexer : Lexer::new(input),
+            namespaces : HashMap::new(),
+            base : base.to_string(),
+            bnodes : 0,
+            pending : VecDeque::new(),
+            done : false
+        }
+    }
 
-    for pair in pairs {
-        read = pair.clone().into_span().end();
-        for pair2 in pair.into_inner() {
-            if pair2.as_rule() == Rule::directive {
-                process_directive(pair2, &mut state);
-            } else if pair2.as_rule() == Rule::triples {
-                process_triples(pair2, &mut state)?;
+    /// Read one statement, returning false at the end of the input
+    fn statement(&mut self) -> Result<bool> {
+        let tok = match self.lexer.next_token()? {
+            Some(tok) => tok,
+            None => return Ok(false)
+        };
+        match tok {
+            Token::Directive(ref d) if d == "prefix" => {
+                self.prefix()?;
+                self.expect('.')?;
+            },
+            Token::Directive(ref d) if d == "base" => {
+                self.base()?;
+                self.expect('.')?;
+            },
+            Token::Keyword(ref k) if k.eq_ignore_ascii_case("prefix") => self.prefix()?,
+            Token::Keyword(ref k) if k.eq_ignore_ascii_case("base") => self.base()?,
+            Token::Punct('[') => {
+                let (subj, has_preds) = self.blank_node_property_list()?;
+                if !has_preds || self.lexer.peek_token()? != Some(&Token::Punct('.')) {
+                    self.predicate_object_list(&subj)?;
+                }
+                self.expect('.')?;
+            },
+            tok => {
+                let subj = match tok {
+                    Token::Iri(_) | Token::PName(_, _) => self.named_node(tok)?.as_resource(),
+                    Token::Blank(label) => Resource::BlankNode(label),
+                    Token::Punct('(') => self.collection()?,
+                    tok => return Err(self.lexer.error(&format!("Expected subject but found {:?}", tok)))
+                };
+                self.predicate_object_list(&subj)?;
+                self.expect('.')?;
             }
         }
+        Ok(true)
+    }
+
+    fn prefix(&mut self) -> Result<()> {
+        let prefix = match self.lexer.next_token()? {
+            Some(Token::PName(ref prefix, ref local)) if local.is_empty() => prefix.clone(),
+            _ => return Err(self.lexer.error("Expected prefix name"))
+        };
+        match self.lexer.next_token()? {
+            Some(Token::Iri(iri)) => {
+                let iri = resolve_iri(&self.base, &iri);
+                self.namespaces.insert(prefix.clone(), Namespace(prefix, iri));
+                Ok(())
+            },
+            _ => Err(self.lexer.error("Expected IRI"))
+        }
+    }
 
+    fn base(&mut self) -> Result<()> {
+        match self.lexer.next_token()? {
+            Some(Token::Iri(iri)) => {
+                self.base = resolve_iri(&self.base, &iri);
+                Ok(())
+            },
+            _ => Err(self.lexer.error("Expected IRI"))
+        }
     }
-    if read < data.len() {
-        Err(TurtleParserError::ParseIncomplete(read))
-    } else {
-        Ok(state.triples)
+
+    fn expect(&mut self, c : char) -> Result<()> {
+        match self.lexer.next_token()? {
+            Some(Token::Punct(c2)) if c == c2 => Ok(()),
+            Some(tok) => Err(self.lexer.error(&format!("Expected '{}' but found {:?}", c, tok))),
+            None => Err(self.lexer.error(&format!("Expected '{}' but found end of input", c)))
+        }
     }
-}
 
-#[derive(Debug)]
-struct ParserState {
-    triples : Vec<Triple>,
-    namespaces : HashMap<String, Namespace>,
-    bnodes : usize
+    fn new_bnode(&mut self) -> Resource {
+        let id = format!("nodeID{}", self.bnodes);
+        self.bnodes += 1;
+        Resource::make_blank(&id)
+    }
+
+    fn named_node(&self, tok : Token) -> Result<NamedNode> {
+        match tok {
+            Token::Iri(iri) => Ok(NamedNode::make_uri(&resolve_iri(&self.base, &iri))),
+            Token::PName(prefix, local) => match self.namespaces.get(&prefix) {
+                Some(ns) => Ok(ns.make_named_node(&local)),
+                None => Err(TurtleParserError::NamespaceNotFound(prefix))
+            },
+            tok => Err(self.lexer.error(&format!("Expected IRI but found {:?}", tok)))
+        }
+    }
+
+    fn predicate_object_list(&mut self, subj : &Resource) -> Result<()> {
+        loop {
+            let verb = match self.lexer.next_token()? {
+                Some(Token::Keyword(ref k)) if k == "a" =>
+                    NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"),
+                Some(tok @ Token::Iri(_)) | Some(tok @ Token::PName(_, _)) => self.named_node(tok)?,
+                Some(tok) => return Err(self.lexer.error(&format!("Expected predicate but found {:?}", tok))),
+                None => return Err(self.lexer.error("Expected predicate but found end of input"))
+            };
+            loop {
+                let obj = self.object()?;
+                self.pending.push_back(Triple(subj.clone(), verb.clone(), obj));
+                if self.lexer.peek_token()? == Some(&Token::Punct(',')) {
+                    self.lexer.next_token()?;
+                } else {
+                    break;
+                }
+            }
+            if self.lexer.peek_token()? != Some(&Token::Punct(';')) {
+                return Ok(());
+            }
+            while self.lexer.peek_token()? == Some(&Token::Punct(';')) {
+                self.lexer.next_token()?;
+            }
+            match self.lexer.peek_token()? {
+                Some(Token::Iri(_)) | Some(Token::PName(_, _)) => {},
+                Some(Token::Keyword(ref k)) if k == "a" => {},
+                _ => return Ok(())
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value> {
+        match self.lexer.next_token()? {
+            Some(tok @ Token::Iri(_)) | Some(tok @ Token::PName(_, _)) => Ok(self.named_node(tok)?.as_value()),
+            Some(Token::Blank(label)) => Ok(Value::make_blank(&label)),
+            Some(Token::Punct('[')) => Ok(self.blank_node_property_list()?.0.as_value()),
+            Some(Token::Punct('(')) => Ok(self.collection()?.as_value()),
+            Some(Token::Str(s)) => {
+                match self.lexer.peek_token()? {
+                    Some(Token::LangTag(_)) => {
+                        match self.lexer.next_token()? {
+                            Some(Token::LangTag(lang)) => Ok(Literal::LangLiteral(s, lang).as_value()),
+                            _ => Err(self.lexer.error("Expected language tag"))
+                        }
+                    },
+                    Some(Token::DataType) => {
+                        self.lexer.next_token()?;
+                        let dtype = match self.lexer.next_token()? {
+                            Some(tok) => self.named_node(tok)?,
+                            None => return Err(self.lexer.error("Expected datatype"))
+                        };
+                        Ok(Literal::TypedLiteral(s, dtype).as_value())
+                    },
+                    _ => Ok(Literal::PlainLiteral(s).as_value())
+                }
+            },
+            Some(Token::Integer(n)) => Ok(Value::make_typed_literal(&n,
+                NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#integer"))),
+            Some(Token::Decimal(n)) => Ok(Value::make_typed_literal(&n,
+                NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#decimal"))),
+            Some(Token::Double(n)) => Ok(Value::make_typed_literal(&n,
+                NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#double"))),
+            Some(Token::Keyword(ref k)) if k == "true" || k == "false" => Ok(Value::make_typed_literal(k,
+                NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#boolean"))),
+            Some(tok) => Err(self.lexer.error(&format!("Expected object but found {:?}", tok))),
+            None => Err(self.lexer.error("Expected object but found end of input"))
+        }
+    }
+
+    /// Read a blank node after the opening `[`, returning the node and
+    /// whether it had any properties
+    fn blank_node_property_list(&mut self) -> Result<(Resource, bool)> {
+        let node = self.new_bnode();
+        if self.lexer.peek_token()? == Some(&Token::Punct(']')) {
+            self.lexer.next_token()?;
+            return Ok((node, false));
+        }
+        self.predicate_object_list(&node)?;
+        self.expect(']')?;
+        Ok((node, true))
+    }
+
+    /// Read a collection after the opening `(`, returning its first node
+    fn collection(&mut self) -> Result<Resource> {
+        let mut items = Vec::new();
+        while self.lexer.peek_token()? != Some(&Token::Punct(')')) {
+            items.push(self.object()?);
+        }
+        self.lexer.next_token()?;
+        let mut list = Resource::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#nil");
+        let nodes : Vec<Resource> = items.iter().map(|_| self.new_bnode()).collect();
+        for (item, node) in items.into_iter().zip(nodes).rev() {
+            self.pending.push_back(Triple(node.clone(),
+                NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#first"), item));
+            self.pending.push_back(Triple(node.clone(),
+                NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#rest"), list.as_value()));
+            list = node;
+        }
+        Ok(list)
+    }
 }
 
-fn process_directive<'i>(p : Pair<'i, Rule>, state : &mut ParserState) {
-    for p2 in p.into_inner() {
-        if p2.as_rule() == Rule::prefix_id {
-            let mut p3 = p2.into_inner();
-            let prefix = p3.next().expect("grammar error (prefix)");
-            let uriref = p3.next().expect("grammar error (prefix uriref)");
-            let full = process_uriref(uriref);
-            let ns = Namespace(prefix.as_str().to_string(), full[1..(full.len()-1)].to_string());
-            state.namespaces.insert(prefix.as_str().to_string(), ns);
-        } else if p2.as_rule() == Rule::base {
-            let mut p3 = p2.into_inner();
-            let uriref = p3.next().expect("grammar error (base uriref)");
-            let ns = Namespace("@base".to_string(), process_uriref(uriref));
-            state.namespaces.insert("@base".to_string(), ns);
-        } else {
-            panic!("grammar error (directive)");
+impl<R : Read> Iterator for TurtleReader<R> {
+    type Item = Result<Triple>;
+
+    fn next(&mut self) -> Option<Result<Triple>> {
+        loop {
+            if let Some(triple) = self.pending.pop_front() {
+                return Some(Ok(triple));
+            }
+            if self.done {
+                return None;
+            }
+            match self.statement() {
+                Ok(true) => {},
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    self.pending.clear();
+                    return Some(Err(e));
+                }
+            }
         }
     }
+}
 
+#[derive(Debug,Clone,PartialEq)]
+enum Token {
+    /// An IRI reference, unescaped but not yet resolved
+    Iri(String),
+    PName(String, String),
+    Blank(String),
+    Str(String),
+    LangTag(String),
+    DataType,
+    Integer(String),
+    Decimal(String),
+    Double(String),
+    /// `a`, `true`, `false` and the SPARQL-style `PREFIX` and `BASE`
+    Keyword(String),
+    /// `@prefix` or `@base`
+    Directive(String),
+    Punct(char)
 }
 
-fn process_uriref<'i>(p : Pair<'i, Rule>) -> String {
-    //p.into_inner().next().expect("grammar error (uriref)").as_str().to_string()
-    p.as_str().to_string()
+struct Lexer<R : Read> {
+    input : BufReader<R>,
+    lookahead : VecDeque<char>,
+    peeked : Option<Token>,
+    line : usize,
+    col : usize,
+    token_line : usize,
+    token_col : usize
 }
 
-fn process_triples<'i>(p : Pair<'i, Rule>, state : &mut ParserState) -> Result<()> {
-    let mut p3 = p.into_inner();
-    let subject = {
-        let p4 = p3.next().expect("grammar error (subject)");
-        if p4.as_rule() == Rule::resource {
-            process_resource(p4, state)?.as_resource()
-        } else if p4.as_rule() == Rule::blank {
-            process_blank(p4.into_inner().next().expect("grammar error (subject/blank"), state)?
-        } else {
-            panic!("grammar error (subject2)")
-        }
-    };
-    process_predicate_object_list(p3.next().expect("grammar error (predicate_object_list)"),
-        state, subject)
+fn is_pn_chars_base(c : char) -> bool {
+    matches!(c, 'A'..='Z' | 'a'..='z' | '\u{00C0}'..='\u{00D6}' | '\u{00D8}'..='\u{00F6}' |
+        '\u{00F8}'..='\u{02FF}' | '\u{0370}'..='\u{037D}' | '\u{037F}'..='\u{1FFF}' |
+        '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' |
+        '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' |
+        '\u{10000}'..='\u{EFFFF}')
 }
 
-fn process_resource<'i>(p : Pair<'i, Rule>, state : &mut ParserState) -> Result<NamedNode> {
-    process_named_node(p.into_inner().next().unwrap(), state)
+fn is_pn_chars_u(c : char) -> bool {
+    is_pn_chars_base(c) || c == '_'
 }
 
-fn process_named_node<'i>(p : Pair<'i, Rule>, state : &mut ParserState) -> Result<NamedNode> {
-    if p.as_rule() == Rule::uriref {
-        let s = p.as_str();
-        Ok(NamedNode::make_uri(&s[1..(s.len()-1)]))
-    } else if p.as_rule() == Rule::qname {
-        let mut p2 = p.into_inner();
-        let s1 = p2.next().expect("grammar error (qname prefix)").as_str();
-        let (pre, suf) = match p2.next() {
-            Some(suffix) => (s1.to_string(), suffix.as_str().to_string()),
-            None => ("".to_string(), s1.to_string())
+fn is_pn_chars(c : char) -> bool {
+    match c {
+        '-' | '0'..='9' | '\u{00B7}' | '\u{0300}'..='\u{036F}' | '\u{203F}'..='\u{2040}' => true,
+        c => is_pn_chars_u(c)
+    }
+}
+
+fn is_local_esc(c : char) -> bool {
+    "_~.-!$&'()*+,;=/?#@%".contains(c)
+}
+
+impl<R : Read> Lexer<R> {
+    fn new(input : R) -> Lexer<R> {
+        Lexer {
+            input : BufReader::new(input),
+            lookahead : VecDeque::new(),
+            peeked : None,
+            line : 1,
+            col : 1,
+            token_line : 1,
+            token_col : 1
+        }
+    }
+
+    fn error(&self, msg : &str) -> TurtleParserError {
+        TurtleParserError::Parse(format!("line {}, column {}: {}", self.token_line, self.token_col, msg))
+    }
+
+    /// Decode the next UTF-8 character from the input buffer
+    fn read_char(&mut self) -> Result<Option<char>> {
+        let mut bytes = [0u8; 4];
+        let mut len = 0;
+        let mut need = 1;
+        // A character may be split over two fills of the buffer
+        while len < need {
+            let buf = self.input.fill_buf().map_err(TurtleParserError::Io)?;
+            if buf.is_empty() {
+                if len == 0 {
+                    return Ok(None);
+                }
+                break;
+            }
+            if len == 0 {
+                let b0 = buf[0];
+                need = if b0 < 0x80 { 1 } else if b0 >> 5 == 0x6 { 2 } else if b0 >> 4 == 0xE { 3 } else if b0 >> 3 == 0x1E { 4 } else { 1 };
+            }
+            let n = (need - len).min(buf.len());
+            bytes[len..len + n].copy_from_slice(&buf[..n]);
+            self.input.consume(n);
+            len += n;
+        }
+        match std::str::from_utf8(&bytes[..len]) {
+            Ok(s) => Ok(s.chars().next()),
+            Err(_) => Err(self.error("Invalid UTF-8"))
+        }
+    }
+
+    /// Move a run of ASCII characters accepted by `f` straight from the
+    /// input buffer to `out`, rather than decoding them one at a time
+    fn ascii_run<F : Fn(u8) -> bool>(&mut self, mut out : Option<&mut String>, f : F) -> Result<()> {
+        if !self.lookahead.is_empty() {
+            return Ok(());
+        }
+        loop {
+            let buf = self.input.fill_buf().map_err(TurtleParserError::Io)?;
+            let mut n = 0;
+            for &b in buf.iter() {
+                if !b.is_ascii() || !f(b) {
+                    break;
+                }
+                if b == b'\n' {
+                    self.line += 1;
+                    self.col = 1;
+                } else {
+                    self.col += 1;
+                }
+                n += 1;
+            }
+            if n == 0 {
+                return Ok(());
+            }
+            if let Some(ref mut out) = out {
+                out.extend(buf[..n].iter().map(|b| *b as char));
+            }
+            self.input.consume(n);
+        }
+    }
+
+    /// The next character, which has already been peeked
+    fn peeked_char(&mut self) -> Result<char> {
+        match self.next_char()? {
+            Some(c) => Ok(c),
+            None => Err(self.error("Unexpected end of input"))
+        }
+    }
+
+    fn peek_char(&mut self, n : usize) -> Result<Option<char>> {
+        while self.lookahead.len() <= n {
+            match self.read_char()? {
+                Some(c) => self.lookahead.push_back(c),
+                None => return Ok(None)
+            }
+        }
+        Ok(Some(self.lookahead[n]))
+    }
+
+    fn next_char(&mut self) -> Result<Option<char>> {
+        let c = match self.lookahead.pop_front() {
+            Some(c) => Some(c),
+            None => self.read_char()?
         };
-        match state.namespaces.get(&pre) {
-            Some(ns) => Ok(ns.make_named_node(&suf)),
-            None => Err(TurtleParserError::NamespaceNotFound(pre.to_string()))
+        if c == Some('\n') {
+            self.line += 1;
+            self.col = 1;
+        } else if c.is_some() {
+            self.col += 1;
         }
+        Ok(c)
+    }
 
-    } else {
-        eprintln!("{:?}", p.as_str());
-        eprintln!("{:?}", p.as_rule());
-        panic!("grammar error (resource)");
+    /// Check if the character after a run of dots starting at `n` satisfies `f`
+    fn dots_followed_by<F : Fn(char) -> bool>(&mut self, mut n : usize, f : F) -> Result<bool> {
+        while self.peek_char(n)? == Some('.') {
+            n += 1;
+        }
+        Ok(self.peek_char(n)?.map(f).unwrap_or(false))
     }
-}
 
-fn process_blank<'i>(p : Pair<'i, Rule>, state : &mut ParserState) -> Result<Resource> {
-    if p.as_rule() == Rule::blank_node_id {
-        let s = p.as_str();
-        Ok(Resource::make_blank(&s[2..]))
-    } else if p.as_rule() == Rule::blank_node_empty {
-        let id = format!("nodeID{}", state.bnodes);
-        state.bnodes += 1;
-        Ok(Resource::make_blank(&id))
-    } else if p.as_rule() == Rule::blank_node_preds {
-        let id = format!("nodeID{}", state.bnodes);
-        state.bnodes += 1;
-        let subj = Resource::make_blank(&id);
-        process_predicate_object_list(p.into_inner().next().unwrap(), state, subj.clone())?;
-        Ok(subj)
-    } else if p.as_rule() == Rule::blank_node_collection {
-        let id = format!("nodeID{}", state.bnodes);
-        state.bnodes += 1;
-        let mut node = Resource::make_blank(&id);
-
-        let objects = process_object_list(p.into_inner().next().unwrap(), state)?;
-        let mut first = true;
-        for obj in objects {
-            if !first {
-                let id2 = format!("nodeID{}", state.bnodes);
-                state.bnodes += 1;
-                let node2 = Resource::make_blank(&id2);
-                state.triples.push(Triple(node.clone(),
-                                          NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#rest"), node2.clone().as_value()));
-                node = node2;
-                state.triples.push(Triple(
-                    node.clone(), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#first"), obj));
-            } else {
-                state.triples.push(Triple(
-                    node.clone(), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#first"), obj));
-                first = false;
+    fn peek_token(&mut self) -> Result<Option<&Token>> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        match self.peeked.take() {
+            Some(tok) => Ok(Some(tok)),
+            None => self.read_token()
+        }
+    }
+
+    fn read_token(&mut self) -> Result<Option<Token>> {
+        loop {
+            match self.peek_char(0)? {
+                Some(' ') | Some('\t') | Some('\n') | Some('\r') => { self.next_char()?; },
+                Some('#') => {
+                    loop {
+                        self.ascii_run(None, |b| b != b'\n' && b != b'\r')?;
+                        match self.peek_char(0)? {
+                            Some('\n') | Some('\r') | None => break,
+                            Some(_) => { self.next_char()?; }
+                        }
+                    }
+                },
+                _ => break
             }
         }
-        if first {
-            Ok(Resource::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#nil"))
-        } else {
-            state.triples.push(Triple(node,
-                                      NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#rest"),
-                                      Resource::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#nil").as_value()));
-            Ok(Resource::make_blank(&id))
+        self.token_line = self.line;
+        self.token_col = self.col;
+        let c = match self.peek_char(0)? {
+            Some(c) => c,
+            None => return Ok(None)
+        };
+        let tok = match c {
+            '<' => Token::Iri(self.iri()?),
+            '"' | '\'' => Token::Str(self.string()?),
+            '_' => {
+                self.next_char()?;
+                if self.next_char()? != Some(':') {
+                    return Err(self.error("Expected ':' after '_'"));
+                }
+                Token::Blank(self.blank_label()?)
+            },
+            '@' => {
+                self.next_char()?;
+                let tag = self.lang_tag()?;
+                if tag == "prefix" || tag == "base" {
+                    Token::Directive(tag)
+                } else {
+                    Token::LangTag(tag)
+                }
+            },
+            '^' => {
+                self.next_char()?;
+                if self.next_char()? != Some('^') {
+                    return Err(self.error("Expected '^^'"));
+                }
+                Token::DataType
+            },
+            '.' => {
+                match self.peek_char(1)? {
+                    Some(d) if d.is_ascii_digit() => self.number()?,
+                    _ => { self.next_char()?; Token::Punct('.') }
+                }
+            },
+            '0'..='9' | '+' | '-' => self.number()?,
+            ';' | ',' | '[' | ']' | '(' | ')' => { self.next_char()?; Token::Punct(c) },
+            ':' => {
+                self.next_char()?;
+                Token::PName(String::new(), self.local_name()?)
+            },
+            c if is_pn_chars_base(c) => self.word()?,
+            c => return Err(self.error(&format!("Unexpected character {:?}", c)))
+        };
+        Ok(Some(tok))
+    }
+
+    fn iri(&mut self) -> Result<String> {
+        self.next_char()?;
+        let mut iri = String::new();
+        loop {
+            self.ascii_run(Some(&mut iri), |b| b > b' ' && !b"<>\"{}|^`\\".contains(&b))?;
+            match self.next_char()? {
+                Some('>') => return Ok(iri),
+                Some(c) => {
+                    // An escape may not produce a character that is illegal in an IRI
+                    let c = if c == '\\' { self.unicode_escape()? } else { c };
+                    if c <= ' ' || "<>\"{}|^`\\".contains(c) {
+                        return Err(self.error(&format!("Illegal character in IRI: {:?}", c)));
+                    }
+                    iri.push(c);
+                },
+                None => return Err(self.error("Unterminated IRI"))
+            }
         }
-    } else if p.as_rule() == Rule::blank_node_collection_empty {
-        Ok(Resource::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#nil"))
-    } else {
-        panic!("")
     }
-}
 
-fn process_predicate_object_list<'i>(p : Pair<'i, Rule>, state : &mut ParserState,
-                subj : Resource) -> Result<()> {
-    let mut p2 = p.into_inner();
-    loop {
-        let verb = match p2.next() {
-            Some(v) => process_verb(v, state)?,
-            None => break
+    /// Read a `\u` or `\U` escape after the backslash
+    fn unicode_escape(&mut self) -> Result<char> {
+        let len = match self.next_char()? {
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(self.error("Bad escape sequence"))
         };
-        let objects = process_object_list(p2.next().expect("Grammar error (objects)"), state)?;
-        for obj in objects {
-            state.triples.push(Triple(subj.clone(), verb.clone(), obj))
+        let mut hex = String::new();
+        for _ in 0..len {
+            match self.next_char()? {
+                Some(c) if c.is_ascii_hexdigit() => hex.push(c),
+                _ => return Err(self.error("Bad unicode escape"))
+            }
         }
+        u32::from_str_radix(&hex, 16).ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| self.error(&format!("Bad unicode escape: {}", hex)))
     }
-    Ok(())
-}
 
-fn process_verb<'i>(p : Pair<'i, Rule>, state : &mut ParserState) -> Result<NamedNode> {
-    let mut p2 = p.into_inner();
-    match p2.next() {
-        Some(res) => process_named_node(res.into_inner().next().expect("grammar error (verb/resource)"), state),
-        None => Ok(NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"))
+    fn string_escape(&mut self) -> Result<char> {
+        match self.peek_char(0)? {
+            Some('t') => { self.next_char()?; Ok('\t') },
+            Some('b') => { self.next_char()?; Ok('\u{08}') },
+            Some('n') => { self.next_char()?; Ok('\n') },
+            Some('r') => { self.next_char()?; Ok('\r') },
+            Some('f') => { self.next_char()?; Ok('\u{0C}') },
+            Some('"') => { self.next_char()?; Ok('"') },
+            Some('\'') => { self.next_char()?; Ok('\'') },
+            Some('\\') => { self.next_char()?; Ok('\\') },
+            _ => self.unicode_escape()
+        }
     }
-}
 
-fn process_object_list<'i>(p : Pair<'i, Rule>, state : &mut ParserState) -> Result<Vec<Value>> {
-    let mut objects = Vec::new();
-    for p2 in p.into_inner() {
-       objects.push(process_object(p2, state)?);
+    fn string(&mut self) -> Result<String> {
+        let q = self.peeked_char()?;
+        let qb = q as u8;
+        let mut s = String::new();
+        if self.peek_char(0)? == Some(q) && self.peek_char(1)? == Some(q) {
+            self.next_char()?;
+            self.next_char()?;
+            loop {
+                self.ascii_run(Some(&mut s), |b| b != qb && b != b'\\')?;
+                match self.next_char()? {
+                    Some(c) if c == q => {
+                        if self.peek_char(0)? == Some(q) && self.peek_char(1)? == Some(q) {
+                            self.next_char()?;
+                            self.next_char()?;
+                            return Ok(s);
+                        }
+                        s.push(c);
+                    },
+                    Some('\\') => s.push(self.string_escape()?),
+                    Some(c) => s.push(c),
+                    None => return Err(self.error("Unterminated string"))
+                }
+            }
+        } else {
+            loop {
+                self.ascii_run(Some(&mut s), |b| b != qb && b != b'\\' && b != b'\n' && b != b'\r')?;
+                match self.next_char()? {
+                    Some(c) if c == q => return Ok(s),
+                    Some('\\') => s.push(self.string_escape()?),
+                    Some('\n') | Some('\r') => return Err(self.error("Line break in string")),
+                    Some(c) => s.push(c),
+                    None => return Err(self.error("Unterminated string"))
+                }
+            }
+        }
     }
-    Ok(objects)
-}
 
-fn process_object<'i>(p : Pair<'i, Rule>, state : &mut ParserState) -> Result<Value> {
-   if p.as_rule() == Rule::resource {
-       Ok(process_named_node(p.into_inner().next().expect("grammar error (object/resource)"), state)?.as_value())
-   } else if p.as_rule() == Rule::blank {
-       Ok(process_blank(p.into_inner().next().expect("grammar error (object/blank)"), state)?.as_value())
-   } else if p.as_rule() == Rule::lit {
-       Ok(process_lit(p.into_inner().next().expect("grammar error (object/lit)"), state)?.as_value())
-   } else {
-       panic!("grammar error (object)")
-   }
-}
+    fn blank_label(&mut self) -> Result<String> {
+        let mut label = String::new();
+        match self.next_char()? {
+            Some(c) if is_pn_chars_u(c) || c.is_ascii_digit() => label.push(c),
+            _ => return Err(self.error("Bad blank node label"))
+        }
+        loop {
+            match self.peek_char(0)? {
+                Some(c) if is_pn_chars(c) => { self.next_char()?; label.push(c); },
+                Some('.') if self.dots_followed_by(0, is_pn_chars)? => { self.next_char()?; label.push('.'); },
+                _ => return Ok(label)
+            }
+        }
+    }
 
-fn process_lit<'i>(p : Pair<'i, Rule>, state : &mut ParserState) -> Result<Literal> {
-    if p.as_rule() == Rule::datatype_string {
-        let mut p2 = p.into_inner();
-        let str = process_quoted_string(p2.next().expect("grammar error (lit/qstring1)"));
-        let dtype = process_resource(p2.next().expect("grammar error (lit/dtype)"), state)?;
-        Ok(Literal::TypedLiteral(str, dtype))
-    } else if p.as_rule() == Rule::lang_string {
-        let mut p2 = p.into_inner();
-        let str = process_quoted_string(p2.next().unwrap());
-        let lang = p2.next().map(|x| {
-            let s = x.as_str();
-            s[1..].to_string()
-        });
-        match lang {
-            Some(l) => Ok(Literal::LangLiteral(str, l)),
-            None => Ok(Literal::PlainLiteral(str))
-        }
-    } else if p.as_rule() == Rule::integer {
-        Ok(Literal::TypedLiteral(p.as_str().to_string(),
-                                 NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#integer")))
-    } else if p.as_rule() == Rule::duble {
-        Ok(Literal::TypedLiteral(p.as_str().to_string(),
-                                 NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#double")))
-    } else if p.as_rule() == Rule::bool {
-        Ok(Literal::TypedLiteral(p.as_str().to_string(),
-                                 NamedNode::make_uri("http://www.w3.org/2001/XMLSchema#boolean")))
-    } else {
-        eprintln!("{:?}", p.as_rule());
-        panic!("grammar error (lit)")
+    fn lang_tag(&mut self) -> Result<String> {
+        let mut tag = String::new();
+        while let Some(c) = self.peek_char(0)? {
+            if c.is_ascii_alphabetic() {
+                self.next_char()?;
+                tag.push(c);
+            } else {
+                break;
+            }
+        }
+        if tag.is_empty() {
+            return Err(self.error("Bad language tag"));
+        }
+        while self.peek_char(0)? == Some('-') && self.peek_char(1)?.map(|c| c.is_ascii_alphanumeric()).unwrap_or(false) {
+            self.next_char()?;
+            tag.push('-');
+            while let Some(c) = self.peek_char(0)? {
+                if c.is_ascii_alphanumeric() {
+                    self.next_char()?;
+                    tag.push(c);
+                } else {
+                    break;
+                }
+            }
+        }
+        Ok(tag)
+    }
+
+    fn digits(&mut self, s : &mut String) -> Result<usize> {
+        let mut n = 0;
+        while let Some(c) = self.peek_char(0)? {
+            if c.is_ascii_digit() {
+                self.next_char()?;
+                s.push(c);
+                n += 1;
+            } else {
+                break;
+            }
+        }
+        Ok(n)
     }
-}
 
-fn process_quoted_string<'i>(p : Pair<'i, Rule>) -> String {
-    if p.as_rule() == Rule::string {
-        let s = p.as_str();
-        s[1..(s.len() - 1)].to_string()
-    } else if p.as_rule() == Rule::long_string {
-        let s = p.as_str();
-        s[3..(s.len() - 3)].to_string()
-    } else {
-        eprintln!("{:?}", p.as_rule());
-        panic!("grammar error (quoted_string)")
+    /// Check if an exponent starts `n` characters ahead
+    fn is_exponent(&mut self, n : usize) -> Result<bool> {
+        match self.peek_char(n)? {
+            Some('e') | Some('E') => {},
+            _ => return Ok(false)
+        }
+        Ok(match self.peek_char(n + 1)? {
+            Some('+') | Some('-') => self.peek_char(n + 2)?.map(|c| c.is_ascii_digit()).unwrap_or(false),
+            Some(c) => c.is_ascii_digit(),
+            None => false
+        })
     }
-}
 
+    fn number(&mut self) -> Result<Token> {
+        let mut s = String::new();
+        if let Some(c) = self.peek_char(0)? {
+            if c == '+' || c == '-' {
+                self.next_char()?;
+                s.push(c);
+            }
+        }
+        let int_digits = self.digits(&mut s)?;
+        let mut decimal = false;
+        if self.peek_char(0)? == Some('.') {
+            if self.peek_char(1)?.map(|c| c.is_ascii_digit()).unwrap_or(false) {
+                self.next_char()?;
+                s.push('.');
+                self.digits(&mut s)?;
+                decimal = true;
+            } else if int_digits > 0 && self.is_exponent(1)? {
+                self.next_char()?;
+                s.push('.');
+            }
+        }
+        if !decimal && int_digits == 0 {
+            return Err(self.error("Bad number"));
+        }
+        if self.is_exponent(0)? {
+            s.push(self.peeked_char()?);
+            if let Some(c) = self.peek_char(0)? {
+                if c == '+' || c == '-' {
+                    self.next_char()?;
+                    s.push(c);
+                }
+            }
+            self.digits(&mut s)?;
+            Ok(Token::Double(s))
+        } else if decimal {
+            Ok(Token::Decimal(s))
+        } else {
+            Ok(Token::Integer(s))
+        }
+    }
+
+    /// Read a prefixed name or a keyword
+    fn word(&mut self) -> Result<Token> {
+        let mut word = String::new();
+        word.push(self.peeked_char()?);
+        loop {
+            match self.peek_char(0)? {
+                Some(c) if is_pn_chars(c) => { self.next_char()?; word.push(c); },
+                Some('.') if self.dots_followed_by(0, is_pn_chars)? => { self.next_char()?; word.push('.'); },
+                _ => break
+            }
+        }
+        if self.peek_char(0)? == Some(':') {
+            self.next_char()?;
+            Ok(Token::PName(word, self.local_name()?))
+        } else if word == "a" || word == "true" || word == "false" ||
+            word.eq_ignore_ascii_case("prefix") || word.eq_ignore_ascii_case("base") {
+            Ok(Token::Keyword(word))
+        } else {
+            Err(self.error(&format!("Unexpected word: {}", word)))
+        }
+    }
+
+    /// Read the (possibly empty) local part of a prefixed name
+    fn local_name(&mut self) -> Result<String> {
+        let mut local = String::new();
+        let starts_local = |c : char| is_pn_chars(c) || c == ':' || c == '%' || c == '\\';
+        match self.peek_char(0)? {
+            Some(c) if is_pn_chars_u(c) || c == ':' || c.is_ascii_digit() || c == '%' || c == '\\' => {},
+            _ => return Ok(local)
+        }
+        loop {
+            match self.peek_char(0)? {
+                Some('%') => {
+                    self.next_char()?;
+                    local.push('%');
+                    for _ in 0..2 {
+                        match self.next_char()? {
+                            Some(h) if h.is_ascii_hexdigit() => local.push(h),
+                            _ => return Err(self.error("Bad percent escape in local name"))
+                        }
+                    }
+                },
+                Some('\\') => {
+                    self.next_char()?;
+                    match self.next_char()? {
+                        Some(e) if is_local_esc(e) => local.push(e),
+                        _ => return Err(self.error("Bad escape in local name"))
+                    }
+                },
+                Some(c) if is_pn_chars(c) || c == ':' => { self.next_char()?; local.push(c); },
+                Some('.') if self.dots_followed_by(0, starts_local)? => { self.next_char()?; local.push('.'); },
+                _ => return Ok(local)
+            }
+        }
+    }
+}
 
 quick_error! {
     #[derive(Debug)]
@@ -271,9 +780,9 @@ quick_error! {
             description("Namespace was not declared")
             display("Namespace not found ({})", namespace)
         }
-        ParseIncomplete(read : usize) {
-            description("Parse did not complete")
-            display("Parse failed at {}", read)
+        Io(err : std::io::Error) {
+            from()
+            display("Could not read Turtle: {}", err)
         }
     }
 }
@@ -312,7 +821,21 @@ mod test {
 
     #[test]
     fn test_base() {
-        // TODO
+        let result = parse_turtle_with_base("<a> <#b> <../c> . @base <http://example.org/x/> . <d> <p> <o> .", "http://example.com/dir/doc");
+        assert_eq!(result.unwrap(), vec![
+            Triple(Resource::make_uri("http://example.com/dir/a"), NamedNode::make_uri("http://example.com/dir/doc#b"), Value::make_uri("http://example.com/c")),
+            Triple(Resource::make_uri("http://example.org/x/d"), NamedNode::make_uri("http://example.org/x/p"), Value::make_uri("http://example.org/x/o"))
+        ]);
+    }
+
+    #[test]
+    fn test_strings() {
+        let result = parse_turtle("PREFIX : <http://example.org/>\n:s :p 'single', '''long\n'single''', \"\\u00E9\\t\" .");
+        assert_eq!(result.unwrap(), vec![
+            Triple(Resource::make_uri("http://example.org/s"), NamedNode::make_uri("http://example.org/p"), Value::make_literal("single")),
+            Triple(Resource::make_uri("http://example.org/s"), NamedNode::make_uri("http://example.org/p"), Value::make_literal("long\n'single")),
+            Triple(Resource::make_uri("http://example.org/s"), NamedNode::make_uri("http://example.org/p"), Value::make_literal("\u{e9}\t"))
+        ]);
     }
 
     #[test]
@@ -341,14 +864,14 @@ mod test {
         //let result = parse_turtle("<foo> <bar> ( <foo> <bar> ) .");
         let result = parse_turtle("<foo> <bar> _:test , [ ] , [ <foo> <bar> ] , ( <foo> <bar> ) .");
         assert_eq!(result.unwrap(), vec![
-            Triple(Resource::make_blank("nodeID1"), NamedNode::make_uri("foo"), Value::make_uri("bar")),
-            Triple(Resource::make_blank("nodeID2"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#first"), Value::make_uri("foo")),
-            Triple(Resource::make_blank("nodeID2"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#rest"), Value::make_blank("nodeID3")),
-            Triple(Resource::make_blank("nodeID3"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#first"), Value::make_uri("bar")),
-            Triple(Resource::make_blank("nodeID3"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#rest"), Value::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#nil")),
             Triple(Resource::make_uri("foo"), NamedNode::make_uri("bar"), Value::make_blank("test")),
             Triple(Resource::make_uri("foo"), NamedNode::make_uri("bar"), Value::make_blank("nodeID0")),
+            Triple(Resource::make_blank("nodeID1"), NamedNode::make_uri("foo"), Value::make_uri("bar")),
             Triple(Resource::make_uri("foo"), NamedNode::make_uri("bar"), Value::make_blank("nodeID1")),
+            Triple(Resource::make_blank("nodeID3"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#first"), Value::make_uri("bar")),
+            Triple(Resource::make_blank("nodeID3"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#rest"), Value::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#nil")),
+            Triple(Resource::make_blank("nodeID2"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#first"), Value::make_uri("foo")),
+            Triple(Resource::make_blank("nodeID2"), NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#rest"), Value::make_blank("nodeID3")),
             Triple(Resource::make_uri("foo"), NamedNode::make_uri("bar"), Value::make_blank("nodeID2"))
             ]);
 
@@ -390,4 +913,177 @@ mod test {
     foaf:name \"Spiderman\", \"Человек-паук\"@ru .";
         eprintln!("{:?}", parse_turtle(data).unwrap());
     }
+
+    #[test]
+    fn test_streaming() {
+        let data = "@prefix ex: <http://example.org/> .\nex:a ex:b ex:c ;\n  ex:d [ ex:e \"f\" ] .\nex:g ex:h = .";
+        let results : Vec<Result<Triple>> = TurtleReader::new(data.as_bytes()).collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap(), &Triple(Resource::make_uri("http://example.org/a"),
+            NamedNode::make_uri("http://example.org/b"), Value::make_uri("http://example.org/c")));
+        assert_eq!(results[1].as_ref().unwrap(), &Triple(Resource::make_blank("nodeID0"),
+            NamedNode::make_uri("http://example.org/e"), Value::make_literal("f")));
+        match results[3] {
+            Err(TurtleParserError::Parse(ref msg)) => assert!(msg.starts_with("line 4, column 11:"), "{}", msg),
+            _ => panic!("Expected a parse error")
+        }
+    }
+
+    /// A reader that returns one byte at a time, so that every character
+    /// is split over several fills of the buffer
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf : &mut [u8]) -> std::io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_split_input() {
+        let data = "# ünïcode\n<http://example.org/ä> <http://example.org/b> \"\"\"x\ny ☃\"\"\", 'z 𝄞' .\n";
+        let split : Vec<Triple> = TurtleReader::new(Trickle(data.as_bytes())).collect::<Result<_>>().unwrap();
+        assert_eq!(split, parse_turtle(data).unwrap());
+        assert_eq!(split[0].2, Value::make_literal("x\ny ☃"));
+        assert_eq!(split[1].2, Value::make_literal("z 𝄞"));
+        assert_eq!(split[0].0, Resource::make_uri("http://example.org/ä"));
+    }
+
+    const TEST_SUITE_BASE : &str = "http://www.w3.org/2013/TurtleTests/";
+
+    /// Read a file of a test suite in `tests/fixtures`, by its IRI
+    fn test_suite_file(dir : &str, iri : &str) -> String {
+        let path = format!("{}/tests/fixtures/{}/{}", env!("CARGO_MANIFEST_DIR"), dir,
+            &iri[TEST_SUITE_BASE.len()..]);
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Could not read {}: {}", path, e))
+    }
+
+    /// Compare two graphs, allowing the blank nodes to be renamed
+    fn isomorphic(a : &[Triple], b : &[Triple]) -> bool {
+        fn map_resource(r1 : &Resource, r2 : &Resource, map : &mut HashMap<String, String>) -> bool {
+            match (r1, r2) {
+                (Resource::BlankNode(b1), Resource::BlankNode(b2)) => {
+                    match map.get(b1) {
+                        Some(b) => b == b2,
+                        None => {
+                            if map.values().any(|b| b == b2) {
+                                false
+                            } else {
+                                map.insert(b1.clone(), b2.clone());
+                                true
+                            }
+                        }
+                    }
+                },
+                _ => r1 == r2
+            }
+        }
+        fn matches(t1 : &Triple, t2 : &Triple, map : &mut HashMap<String, String>) -> bool {
+            t1.1 == t2.1 && map_resource(&t1.0, &t2.0, map) && match (&t1.2, &t2.2) {
+                (Value::Resource(r1), Value::Resource(r2)) => map_resource(r1, r2, map),
+                (v1, v2) => v1 == v2
+            }
+        }
+        fn search(a : &[Triple], b : &[Triple], used : &mut Vec<bool>, map : &HashMap<String, String>) -> bool {
+            match a.split_first() {
+                None => true,
+                Some((t1, rest)) => {
+                    for (j, t2) in b.iter().enumerate() {
+                        let mut map2 = map.clone();
+                        if !used[j] && matches(t1, t2, &mut map2) {
+                            used[j] = true;
+                            if search(rest, b, used, &map2) {
+                                return true;
+                            }
+                            used[j] = false;
+                        }
+                    }
+                    false
+                }
+            }
+        }
+        // Only the triples with blank nodes need the search
+        fn split(triples : &[Triple]) -> (Vec<&Triple>, Vec<Triple>) {
+            let (mut ground, blank) : (Vec<&Triple>, Vec<&Triple>) = triples.iter().partition(|t|
+                !matches!(t.0, Resource::BlankNode(_)) && !matches!(t.2, Value::Resource(Resource::BlankNode(_))));
+            ground.sort();
+            (ground, blank.into_iter().cloned().collect())
+        }
+        let ((ground_a, blank_a), (ground_b, blank_b)) = (split(a), split(b));
+        ground_a == ground_b && blank_a.len() == blank_b.len() &&
+            search(&blank_a, &blank_b, &mut vec![false; blank_b.len()], &HashMap::new())
+    }
+
+    /// Run the tests of a manifest in the W3C format, returning the number
+    /// of tests run and the failures
+    fn run_test_suite(dir : &str) -> (usize, Vec<String>) {
+        use crate::rdf::ntriples::NTriplesParser;
+        let rdf_type = NamedNode::make_uri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type");
+        let action = NamedNode::make_uri("http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#action");
+        let result = NamedNode::make_uri("http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#result");
+        let mut failures = Vec::new();
+        let mut count = 0;
+        let manifest_iri = format!("{}manifest.ttl", TEST_SUITE_BASE);
+        let manifest = parse_turtle_with_base(&test_suite_file(dir, &manifest_iri), &manifest_iri).unwrap();
+        let object = |s : &Resource, p : &NamedNode| -> String {
+            match manifest.iter().find(|t| t.0 == *s && t.1 == *p).map(|t| &t.2) {
+                Some(Value::Resource(Resource::Named(n))) => n.uri(),
+                _ => panic!("Bad manifest entry {:?}", s)
+            }
+        };
+        for t in manifest.iter().filter(|t| t.1 == rdf_type) {
+            let test_type = match t.2 {
+                Value::Resource(Resource::Named(ref n)) => n.uri(),
+                _ => continue
+            };
+            if !test_type.starts_with("http://www.w3.org/ns/rdftest#TestTurtle") {
+                continue;
+            }
+            let action_iri = object(&t.0, &action);
+            count += 1;
+            let parsed = parse_turtle_with_base(&test_suite_file(dir, &action_iri), &action_iri);
+            match &test_type["http://www.w3.org/ns/rdftest#".len()..] {
+                "TestTurtleEval" => {
+                    let expected : Vec<Triple> = NTriplesParser::new(test_suite_file(dir, &object(&t.0, &result)).as_bytes())
+                        .map(|t| t.unwrap()).collect();
+                    match parsed {
+                        Ok(ref triples) if isomorphic(triples, &expected) => {},
+                        _ => failures.push(format!("{} (got {:?})", action_iri, parsed))
+                    }
+                },
+                "TestTurtlePositiveSyntax" => {
+                    if let Err(e) = parsed {
+                        failures.push(format!("{} ({})", action_iri, e));
+                    }
+                },
+                "TestTurtleNegativeSyntax" | "TestTurtleNegativeEval" => {
+                    if parsed.is_ok() {
+                        failures.push(format!("{} (should not parse)", action_iri));
+                    }
+                },
+                _ => panic!("Unknown test type {}", test_type)
+            }
+        }
+        (count, failures)
+    }
+
+    #[test]
+    fn test_local_suite() {
+        let (count, failures) = run_test_suite("turtle");
+        assert!(count > 0);
+        assert!(failures.is_empty(), "Failed tests:\n{}", failures.join("\n"));
+    }
+
+    #[test]
+    #[ignore = "the W3C suite must first be unpacked in tests/fixtures/w3c-turtle (see the README there)"]
+    fn test_w3c_suite() {
+        let (count, failures) = run_test_suite("w3c-turtle");
+        assert!(count > 0);
+        assert!(failures.is_empty(), "Failed tests:\n{}", failures.join("\n"));
+    }
 }
@@ -1,10 +1,15 @@
-use http::{Response, StatusCode};
+use http::{Response, StatusCode, HeaderMap, HeaderValue};
+use http::header::{ACCEPT_RANGES, CONTENT_RANGE, RANGE};
 use gotham::state::State;
 use hyper::Body;
 use gotham::helpers::http::response::create_response;
-use mime;
+use mime::{self, Mime};
 use crate::model::{Backend, BackendError};
-use crate::{AboutParams, ListQueryParams, ListPathParams, LookupQueryParams, LookupPathParams, EntryPathParams, BackendImpl};
+use crate::media::{Media, ByteRange, byte_range};
+use crate::tei;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use crate::{AboutParams, ListQueryParams, ListPathParams, LookupQueryParams, LookupPathParams, PronPathParams, PronQueryParams, DefinitionPathParams, TranslatePathParams, MwePathParams, AnalysisPathParams, EntryPathParams, BackendImpl};
 use gotham::state::FromState;
 
 #[derive(Serialize)]
@@ -73,13 +78,77 @@ pub fn about(state : State) -> (State, Response<Body>) {
     (state, res)
 }
 
+/// Handle the "Tagset" request
+pub fn tagset(state : State) -> (State, Response<Body>) {
+    let data = BackendImpl::borrow_from(&state);
+    let params = AboutParams::borrow_from(&state);
+
+    let res = match data.tagset(&params.dictionary) {
+        Ok(tags) => {
+            create_response(
+                &state,
+                StatusCode::OK,
+                mime::APPLICATION_JSON,
+                serde_json::to_vec(&tags).expect("Cannot serialize tagset"))
+        },
+        Err(BackendError::NotFound) => {
+            create_response(
+                &state,
+                StatusCode::NOT_FOUND,
+                mime::TEXT_PLAIN,
+                "Dictionary not found")
+        },
+        Err(e) => {
+            create_response(
+                &state,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                mime::TEXT_PLAIN,
+                format!("{}",e))
+        }
+
+    };
+    (state, res)
+}
+
+/// Handle the "About the dictionary" request, giving the metadata as a TEI header
+pub fn about_tei(state : State) -> (State, Response<Body>) {
+    let data = BackendImpl::borrow_from(&state);
+    let params = AboutParams::borrow_from(&state);
+
+    let res = match data.about(&params.dictionary) {
+        Ok(dict) => {
+            create_response(
+                &state,
+                StatusCode::OK,
+                mime::TEXT_XML,
+                tei::header(&dict))
+        },
+        Err(BackendError::NotFound) => {
+            create_response(
+                &state,
+                StatusCode::NOT_FOUND,
+                mime::TEXT_PLAIN,
+                "Dictionary not found")
+        },
+        Err(e) => {
+            create_response(
+                &state,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                mime::TEXT_PLAIN,
+                format!("{}",e))
+        }
+
+    };
+    (state, res)
+}
+
 /// Handle the "Get all lemmas" request
 pub fn list(state : State) -> (State, Response<Body>) {
     let data = BackendImpl::borrow_from(&state);
     let params1 = ListPathParams::borrow_from(&state);
     let params2 = ListQueryParams::borrow_from(&state);
 
-    let res = match data.list(&params1.dictionary, params2.offset, params2.limit) {
+    let res = match data.list(&params1.dictionary, params2.offset, params2.limit, &params2.frequency()) {
         Ok(entries) => {
             create_response(
                 &state,
@@ -113,9 +182,8 @@ pub fn lookup(state : State) -> (State, Response<Body>) {
         let params1 = LookupPathParams::borrow_from(&state);
         let params2 = LookupQueryParams::borrow_from(&state);
 
-        match data.lookup(&params1.dictionary, &params1.headword,
-            params2.offset, params2.limit,
-            params2.part_of_speech.clone(), params2.inflected.unwrap_or(false)) {
+        match params2.query().map_err(BackendError::Other).and_then(|query| 
+            data.lookup(&params1.dictionary, &params1.headword, &query)) {
             Ok(entries) => {
                 create_response(
                     &state,
@@ -130,6 +198,188 @@ pub fn lookup(state : State) -> (State, Response<Body>) {
                     mime::TEXT_PLAIN,
                     "Dictionary or entry not found")
             }
+            Err(BackendError::Other(msg)) => {
+                create_response(
+                    &state,
+                    StatusCode::BAD_REQUEST,
+                    mime::TEXT_PLAIN,
+                    msg)
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
+
+/// Handle the "Lookup by pronunciation" request
+pub fn lookup_pron(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = PronPathParams::borrow_from(&state);
+        let params2 = PronQueryParams::borrow_from(&state);
+
+        match data.lookup_pron(&params1.dictionary, &params1.ipa,
+            params2.distance.unwrap_or(1.0), params2.offset, params2.limit) {
+            Ok(entries) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&entries).expect("Cannot serialize entries"))
+            }
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
+
+/// Handle the "Lookup by definition" request
+pub fn lookup_definition(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = DefinitionPathParams::borrow_from(&state);
+        let params2 = ListQueryParams::borrow_from(&state);
+
+        match data.lookup_definition(&params1.dictionary, &params1.word,
+            params2.offset, params2.limit) {
+            Ok(entries) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&entries).expect("Cannot serialize entries"))
+            }
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
+
+/// Handle the "Lookup by translation" request
+pub fn lookup_translation(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = TranslatePathParams::borrow_from(&state);
+        let params2 = ListQueryParams::borrow_from(&state);
+
+        match data.lookup_translation(&params1.dictionary, &params1.lang, &params1.word,
+            params2.offset, params2.limit) {
+            Ok(entries) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&entries).expect("Cannot serialize entries"))
+            }
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
+
+/// Handle the "Lookup multi-word expressions" request
+pub fn lookup_mwe(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = MwePathParams::borrow_from(&state);
+        let params2 = ListQueryParams::borrow_from(&state);
+
+        match data.lookup_mwe(&params1.dictionary, &params1.lemma,
+            params2.offset, params2.limit) {
+            Ok(entries) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&entries).expect("Cannot serialize entries"))
+            }
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
+
+/// Handle the "Form analysis" request
+pub fn analyse(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = AnalysisPathParams::borrow_from(&state);
+        let params2 = ListQueryParams::borrow_from(&state);
+
+        match data.analyse(&params1.dictionary, &params1.form,
+            params2.offset, params2.limit) {
+            Ok(analyses) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&analyses).expect("Cannot serialize analyses"))
+            }
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary not found")
+            }
             Err(e) => {
                 create_response(
                     &state,
@@ -238,6 +488,231 @@ pub fn entry_tei(state : State) -> (State, Response<Body>) {
     (state, res)
 }
 
+/// Handle the "Senses of an entry" request
+pub fn entry_senses(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = EntryPathParams::borrow_from(&state);
+        match data.entry_senses(&params1.dictionary, &params1.id) {
+            Ok(senses) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&senses).expect("Cannot serialize senses"))
+            },
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary or entry not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
 
+/// Handle the "Sense by ID" request
+pub fn sense(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = EntryPathParams::borrow_from(&state);
+        match data.sense(&params1.dictionary, &params1.id) {
+            Ok(sense) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&sense).expect("Cannot serialize sense"))
+            },
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary or sense not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
 
+/// Handle the "Related entries" request
+pub fn entry_relations(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = EntryPathParams::borrow_from(&state);
+        match data.entry_relations(&params1.dictionary, &params1.id) {
+            Ok(relations) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&relations).expect("Cannot serialize relations"))
+            },
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary or entry not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
+
+/// Handle the "Etymology" request
+pub fn entry_etymology(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = EntryPathParams::borrow_from(&state);
+        match data.entry_etymology(&params1.dictionary, &params1.id) {
+            Ok(etymology) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&etymology).expect("Cannot serialize etymology"))
+            },
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary or entry not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
+
+/// Handle the "Paradigm" request
+pub fn paradigm(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = EntryPathParams::borrow_from(&state);
+        match data.paradigm(&params1.dictionary, &params1.id) {
+            Ok(paradigm) => {
+                create_response(
+                    &state,
+                    StatusCode::OK,
+                    mime::APPLICATION_JSON,
+                    serde_json::to_vec(&paradigm).expect("Cannot serialize paradigm"))
+            },
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary or entry not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
+
+/// Handle the "Media file" request
+pub fn media(state : State) -> (State, Response<Body>) {
+    let res = {
+        let data = BackendImpl::borrow_from(&state);
+        let params1 = EntryPathParams::borrow_from(&state);
+        match data.media(&params1.dictionary, &params1.id) {
+            Ok(media) => {
+                media_response(&state, &media).unwrap_or_else(|e| {
+                    create_response(
+                        &state,
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        mime::TEXT_PLAIN,
+                        format!("Could not read media file: {}", e))
+                })
+            },
+            Err(BackendError::NotFound) => {
+                create_response(
+                    &state,
+                    StatusCode::NOT_FOUND,
+                    mime::TEXT_PLAIN,
+                    "Dictionary or media not found")
+            }
+            Err(e) => {
+                create_response(
+                    &state,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    mime::TEXT_PLAIN,
+                    format!("{}",e))
+            }
+        }
+    };
+    (state, res)
+}
 
+/// Send a media file, or the single byte range of it given in the `Range` header
+fn media_response(state : &State, media : &Media) -> io::Result<Response<Body>> {
+    let mut file = File::open(media.path.as_ref().ok_or(io::ErrorKind::NotFound)?)?;
+    let len = file.metadata()?.len();
+    let range = HeaderMap::borrow_from(state).get(RANGE)
+        .and_then(|r| r.to_str().ok())
+        .map(|r| byte_range(r, len))
+        .unwrap_or(ByteRange::Full);
+    let mime_type : Mime = media.mime_type.parse().unwrap_or(mime::APPLICATION_OCTET_STREAM);
+    let mut body = Vec::new();
+    let mut res = match range {
+        ByteRange::Full => {
+            file.read_to_end(&mut body)?;
+            create_response(state, StatusCode::OK, mime_type, body)
+        },
+        ByteRange::Partial(start, end) => {
+            file.seek(SeekFrom::Start(start))?;
+            file.take(end - start + 1).read_to_end(&mut body)?;
+            let mut res = create_response(state, StatusCode::PARTIAL_CONTENT, mime_type, body);
+            res.headers_mut().insert(CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, len)).unwrap());
+            res
+        },
+        ByteRange::Unsatisfiable => {
+            let mut res = create_response(state, StatusCode::RANGE_NOT_SATISFIABLE, mime::TEXT_PLAIN,
+                "Requested range not satisfiable");
+            res.headers_mut().insert(CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes */{}", len)).unwrap());
+            res
+        }
+    };
+    res.headers_mut().insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    Ok(res)
+}
@@ -1,11 +1,15 @@
 use rusqlite::{Connection};
 
-use crate::model::{Backend,Dictionary,Entry,JsonEntry,PartOfSpeech,BackendError,Release,EntryContent,Format};
-#[cfg(test)]
-use crate::model::{Agent,Genre};
-use std::collections::HashMap;
+use crate::model::{self,Backend,Dictionary,Entry,JsonEntry,BackendError,Release,EntryContent,EntryStream,Format,Etymology,Etymon,PronMatch,Pronunciation,TranslationMatch,FrequencyFilter,LookupQuery,contains_word,mwe_contains,Paradigm,FormAnalysis,TagUsage};
+use crate::ipa;
+use crate::ud;
+use crate::media::{self, Media, MediaImporter};
+use crate::tei::{TeiEntry,TeiSense,TeiRelations};
 #[cfg(test)]
+use crate::model::{Agent,PartOfSpeech,Genre,Equivalent,Sense,Component,Frequency,Attestation,ParadigmForm,SourceTag,TagMapping};
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use crate::ontolex::OntoLexEntry;
 
 #[derive(Clone,StateData)]
 pub struct RusqliteState {
@@ -20,21 +24,48 @@ impl RusqliteState {
     pub fn load(&self,
         release : Release,
         dictionaries : HashMap<String, Dictionary>,
-        dict_entries : HashMap<String, Vec<EntryContent>>) -> Result<(),rusqlite::Error> {
+        dict_entries : HashMap<String, Vec<EntryContent>>) -> Result<(),BackendError> {
         let db = Connection::open(&self.path)?;
         self.create_tables(&db)?;
+        let mut importer = MediaImporter::new(Some(media::store_dir(&self.path)));
         for (dict_id, dict) in dictionaries {
             self.insert_dict(&db, &dict_id, dict)?;
         }
         for (dict_id, entries) in dict_entries {
             for entry in entries {
-                self.insert_entry(&db, &dict_id, entry, release.clone())?;
+                self.insert_entry(&db, &dict_id, entry, release.clone(), &mut importer)?;
             }
+            number_homographs(&db, &dict_id)?;
         }
         Ok(())
 
     }
 
+    /// Load entries as they are produced, in a single transaction
+    pub fn load_stream(&self,
+        release : Release,
+        dictionaries : HashMap<String, Dictionary>,
+        entries : &mut EntryStream) -> Result<(),BackendError> {
+        let mut db = Connection::open(&self.path)?;
+        self.create_tables(&db)?;
+        let tx = db.transaction()?;
+        let mut importer = MediaImporter::new(Some(media::store_dir(&self.path)));
+        for (dict_id, dict) in dictionaries {
+            self.insert_dict(&tx, &dict_id, dict)?;
+        }
+        let mut dict_ids = HashSet::new();
+        for entry in entries {
+            let (dict_id, entry) = entry?;
+            self.insert_entry(&tx, &dict_id, entry, release.clone(), &mut importer)?;
+            dict_ids.insert(dict_id);
+        }
+        for dict_id in dict_ids {
+            number_homographs(&tx, &dict_id)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
     fn create_tables(&self, db : &rusqlite::Connection) -> Result<(),rusqlite::Error> {
         db.execute("CREATE TABLE IF NOT EXISTS dictionaries
                 (id TEXT UNIQUE,
@@ -65,6 +96,14 @@ impl RusqliteState {
                 (entry_id INTEGER,
                  form TEXT,
                  FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS form_features
+                (entry_id INTEGER,
+                 form_no INTEGER,
+                 form TEXT,
+                 feature TEXT,
+                 value TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS form_features_idx ON form_features (entry_id)", [])?;
         db.execute("CREATE TABLE IF NOT EXISTS json_entries
                 (entry_id INTEGER,
                  json TEXT,
@@ -75,11 +114,90 @@ impl RusqliteState {
                  ontolex TEXT,
                  FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
         db.execute("CREATE INDEX IF NOT EXISTS ontolex_entries_idx ON ontolex_entries (entry_id)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS ontolex_structures
+                (entry_id INTEGER,
+                 json TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS ontolex_structures_idx ON ontolex_structures (entry_id)", [])?;
         db.execute("CREATE TABLE IF NOT EXISTS tei_entries
                 (entry_id INTEGER,
                  tei TEXT,
                  FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
         db.execute("CREATE INDEX IF NOT EXISTS tei_entries_idx ON tei_entries (entry_id)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS tei_structures
+                (entry_id INTEGER,
+                 json TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS tei_structures_idx ON tei_structures (entry_id)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS senses
+                (entry_id INTEGER,
+                 sense_id TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS senses_idx ON senses (sense_id)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS etymons
+                (row_id INTEGER PRIMARY KEY,
+                 entry_id INTEGER,
+                 parent INTEGER,
+                 cognate INTEGER,
+                 language TEXT,
+                 language_name TEXT,
+                 forms TEXT,
+                 glosses TEXT,
+                 etym_type TEXT,
+                 entry_ref TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id),
+                 FOREIGN KEY (parent) REFERENCES etymons(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS etymons_idx ON etymons (entry_id)", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS etymons_idx2 ON etymons (language)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS pronunciations
+                (entry_id INTEGER,
+                 form TEXT,
+                 ipa TEXT,
+                 length INTEGER,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS pronunciations_idx ON pronunciations (entry_id)", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS pronunciations_idx2 ON pronunciations (length)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS definitions
+                (entry_id INTEGER,
+                 definition TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS definitions_idx ON definitions (entry_id)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS translations
+                (entry_id INTEGER,
+                 sense TEXT,
+                 language TEXT,
+                 text TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS translations_idx ON translations (entry_id)", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS translations_idx2 ON translations (text)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS components
+                (entry_id INTEGER,
+                 lemma TEXT,
+                 key TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS components_idx ON components (entry_id)", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS components_idx2 ON components (key)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS entry_frequencies
+                (entry_id INTEGER,
+                 frequency INTEGER,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS entry_frequencies_idx ON entry_frequencies (entry_id)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS entry_tags
+                (entry_id INTEGER,
+                 tag TEXT,
+                 mapping TEXT,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE INDEX IF NOT EXISTS entry_tags_idx ON entry_tags (entry_id)", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS entry_homographs
+                (entry_id INTEGER UNIQUE,
+                 source INTEGER,
+                 homograph INTEGER,
+                 FOREIGN KEY (entry_id) REFERENCES entries(row_id))", [])?;
+        db.execute("CREATE TABLE IF NOT EXISTS media
+                (dict TEXT,
+                 id TEXT,
+                 mime_type TEXT,
+                 UNIQUE(dict,id))", [])?;
         Ok(())
     }
 
@@ -101,7 +219,20 @@ impl RusqliteState {
         }
         Ok(())
     }
-    fn insert_entry(&self, db : &Connection, dict_id : &str, entry_content : EntryContent, release : Release) -> Result<(),rusqlite::Error> {
+    fn insert_entry(&self, db : &Connection, dict_id : &str, mut entry_content : EntryContent, release : Release,
+                    importer : &mut MediaImporter) -> Result<(),BackendError> {
+        // Local media files are copied to the store before the entry is saved.
+        // Missing files were reported when the data was read, so a failure
+        // here is an error writing the store
+        let mut stmt0 = db.prepare("INSERT OR REPLACE INTO media (dict, id, mime_type) VALUES (?,?,?)")?;
+        for m in entry_content.media_mut().iter_mut() {
+            importer.import(dict_id, m).map_err(|e|
+                BackendError::Other(format!("Could not import media file {}: {}", m.url, e)))?;
+            if let Some(ref id) = m.id {
+                stmt0.execute([dict_id, id, &m.mime_type])?;
+            }
+        }
+
         let row_id : u32 = if let Ok(r) = db.query_row("SELECT row_id FROM entries WHERE id=? AND dict=?", &[entry_content.id(), dict_id], |r| r.get(0)) {
             r
         } else {
@@ -135,16 +266,81 @@ impl RusqliteState {
                 let mut stmt4 = db.prepare("INSERT INTO json_entries (entry_id, json) VALUES(?,?)")?;
                 stmt4.execute(&[&format!("{}",row_id), &entry_content.content()])?;
             }
-            EntryContent::Tei(_,_,_,_,_) => {
+            EntryContent::Tei { ref content, entry : ref tei, .. } => {
                 let mut stmt4 = db.prepare("INSERT INTO tei_entries (entry_id, tei) VALUES(?,?)")?;
-                stmt4.execute(&[&format!("{}",row_id), &entry_content.content()])?;
+                stmt4.execute([&format!("{}",row_id), content])?;
+                let mut stmt5 = db.prepare("INSERT INTO tei_structures (entry_id, json) VALUES(?,?)")?;
+                stmt5.execute([&format!("{}",row_id), &serde_json::to_string(tei).unwrap()])?;
+                let mut stmt6 = db.prepare("INSERT INTO senses (entry_id, sense_id) VALUES(?,?)")?;
+                for sense_id in tei.sense_ids() {
+                    stmt6.execute([&format!("{}",row_id), &sense_id])?;
+                }
             }
-            EntryContent::OntoLex(_,_,_,_,_) => {
+            EntryContent::OntoLex { ref id, ref lemma, ref pos, variants : ref vars, entry : ref ontolex, .. } => {
                 let mut stmt4 = db.prepare("INSERT INTO ontolex_entries (entry_id, ontolex) VALUES(?,?)")?;
                 stmt4.execute(&[&format!("{}",row_id), &entry_content.content()])?;
+                // The Json view of the entry is stored with it
+                let mut stmt5 = db.prepare("INSERT INTO json_entries (entry_id, json) VALUES(?,?)")?;
+                stmt5.execute([&format!("{}",row_id), 
+                    &serde_json::to_string(&JsonEntry::from_ontolex(id, lemma, pos, vars, ontolex)).unwrap()])?;
+                let mut stmt6 = db.prepare("INSERT INTO ontolex_structures (entry_id, json) VALUES(?,?)")?;
+                stmt6.execute([&format!("{}",row_id), &serde_json::to_string(ontolex).unwrap()])?;
             }
         }
 
+        let mut stmt7 = db.prepare("INSERT INTO form_features (entry_id, form_no, form, feature, value) VALUES(?,?,?,?,?)")?;
+        for (form_no, form) in entry_content.paradigm().forms.iter().enumerate() {
+            for (feature, value) in form.features.iter() {
+                stmt7.execute([&format!("{}",row_id), &format!("{}",form_no), &form.written_rep, feature, value])?;
+            }
+        }
+
+        if let Some(etymology) = entry_content.etymology() {
+            for etymon in etymology.ancestors.iter() {
+                insert_etymon(db, row_id, None, false, etymon)?;
+            }
+            for etymon in etymology.cognates.iter() {
+                insert_etymon(db, row_id, None, true, etymon)?;
+            }
+        }
+
+        let mut stmt8 = db.prepare("INSERT INTO pronunciations (entry_id, form, ipa, length) VALUES (?,?,?,?)")?;
+        for pron in entry_content.pronunciations() {
+            stmt8.execute(rusqlite::params![row_id, pron.form, pron.ipa, ipa::length(&pron.ipa) as u32])?;
+        }
+
+        let mut stmt10 = db.prepare("INSERT INTO definitions (entry_id, definition) VALUES (?,?)")?;
+        for definition in entry_content.definitions() {
+            stmt10.execute(rusqlite::params![row_id, definition])?;
+        }
+
+        let mut stmt9 = db.prepare("INSERT INTO translations (entry_id, sense, language, text) VALUES (?,?,?,?)")?;
+        for t in entry_content.translations() {
+            stmt9.execute(rusqlite::params![row_id, t.sense, t.language, t.text])?;
+        }
+
+        if let Some(frequency) = entry_content.frequency() {
+            db.execute("INSERT INTO entry_frequencies (entry_id, frequency) VALUES (?,?)", 
+                rusqlite::params![row_id, frequency as i64])?;
+        }
+
+        // The entries are numbered when the dictionary is loaded
+        if !entry_content.groups_homographs() {
+            db.execute("INSERT OR REPLACE INTO entry_homographs (entry_id, source, homograph) VALUES (?,?,?)",
+                rusqlite::params![row_id, entry_content.homograph(), entry_content.homograph()])?;
+        }
+
+        let mut stmt12 = db.prepare("INSERT INTO entry_tags (entry_id, tag, mapping) VALUES (?,?,?)")?;
+        for tag in entry_content.tags() {
+            stmt12.execute(rusqlite::params![row_id, tag.tag, serde_json::to_string(&tag.mapping).unwrap()])?;
+        }
+
+        // Components are matched ignoring case
+        let mut stmt11 = db.prepare("INSERT INTO components (entry_id, lemma, key) VALUES (?,?,?)")?;
+        for c in entry_content.components() {
+            stmt11.execute(rusqlite::params![row_id, c.lemma, c.lemma.as_ref().map(|l| l.trim().to_lowercase())])?;
+        }
+
 
         Ok(())
 
@@ -155,8 +351,25 @@ impl RusqliteState {
         db.execute("DELETE FROM dictionaries WHERE id=?", &[dict_id])?;
         db.execute("DELETE FROM dictionary_dc WHERE id=?", &[dict_id])?;
         db.execute("DELETE FROM variants WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", &[dict_id])?;
+        db.execute("DELETE FROM form_features WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
         db.execute("DELETE FROM json_entries WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", &[dict_id])?;
         db.execute("DELETE FROM tei_entries WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", &[dict_id])?;
+        db.execute("DELETE FROM tei_structures WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM senses WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM etymons WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM pronunciations WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM definitions WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM translations WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM components WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM entry_frequencies WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM entry_tags WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM entry_homographs WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
+        db.execute("DELETE FROM media WHERE dict=?", [dict_id])?;
+        let media_dir = media::store_dir(&self.path).join(media::safe_name(dict_id));
+        if media_dir.exists() {
+            fs::remove_dir_all(media_dir)?;
+        }
+        db.execute("DELETE FROM ontolex_structures WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", [dict_id])?;
         db.execute("DELETE FROM ontolex_entries WHERE entry_id IN (SELECT row_id FROM entries WHERE dict=?)", &[dict_id])?;
         db.execute("DELETE FROM entries WHERE dict=?", &[dict_id])?;
         
@@ -217,43 +430,14 @@ impl Backend for RusqliteState {
     }
     /// List all entries in a dictrionary
     fn list(&self, dictionary : &str, offset : Option<usize>, 
-            limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
+            limit : Option<usize>, frequency : &FrequencyFilter) -> Result<Vec<Entry>,BackendError> {
         let db = Connection::open(&self.path)?;
-        let mut stmt = match offset {
-            Some(_) => match limit {
-                Some(_) =>
-                    db.prepare("SELECT release, lemma, id, part_of_speech, row_id FROM entries WHERE dict=? LIMIT ? OFFSET ?")?,
-                None =>
-                    db.prepare("SELECT release, lemma, id, part_of_speech, row_id FROM entries WHERE dict=? LIMIT -1 OFFSET ?")?
-            },
-            None => match limit {
-                Some(_) =>
-                    db.prepare("SELECT release, lemma, id, part_of_speech, row_id FROM entries WHERE dict=? LIMIT ?")?,
-                None =>
-                    db.prepare("SELECT release, lemma, id, part_of_speech, row_id FROM entries WHERE dict=?")?
-            }
-        };
-        let mut result = match offset {
-            Some(o) => match limit {
-                Some(l) => {
-                    let lstr = format!("{}",l);
-                    let ostr = format!("{}",o);
-                    stmt.query(&[dictionary, &lstr, &ostr])?
-                },
-                None => {
-                    let ostr = format!("{}",o);
-                    stmt.query(&[dictionary, &ostr])?
-                }
-            },
-            None => match limit {
-                Some(l) => {
-                    let lstr = format!("{}",l);
-                    stmt.query(&[dictionary, &lstr])?
-                },
-                None =>
-                    stmt.query(&[dictionary])?
-            }
-        };
+        let mut stmt = db.prepare(&format!("SELECT release, lemma, id, part_of_speech, row_id, frequency, homograph FROM entries 
+            LEFT JOIN entry_frequencies ON entry_frequencies.entry_id == entries.row_id
+            LEFT JOIN entry_homographs ON entry_homographs.entry_id == entries.row_id
+            WHERE dict=?1 AND (?2 IS NULL OR frequency >= ?2) ORDER BY {}row_id LIMIT ?3 OFFSET ?4", frequency_order(frequency)))?;
+        let mut result = stmt.query(rusqlite::params![dictionary, frequency.min.map(|m| m as i64),
+            limit.map(|l| l as i64).unwrap_or(-1), offset.unwrap_or(0) as i64])?;
 
         let mut entries = Vec::new();
 
@@ -266,7 +450,9 @@ impl Backend for RusqliteState {
                 lemma: r.get(1)?,
                 id: r.get(2)?,
                 part_of_speech: serde_json::from_str(&pos_str)?,
-                formats: extract_formats(row_id, &db)
+                formats: extract_formats(row_id, &db),
+                frequency: r.get::<_,Option<i64>>(5)?.map(|f| f as u64),
+                homograph: r.get(6)?
             })
         }
 
@@ -283,12 +469,13 @@ impl Backend for RusqliteState {
 
     /// Search the dictionary by headword
     fn lookup(&self, dictionary : &str, headword : &str,
-              offset : Option<usize>, limit : Option<usize>,
-              part_of_speech : Option<PartOfSpeech>, inflected : bool) -> Result<Vec<Entry>,BackendError> {
+              query : &LookupQuery) -> Result<Vec<Entry>,BackendError> {
         let db = Connection::open(&self.path)?;
-        let mut q = String::from("SELECT release, lemma, id, part_of_speech, row_id FROM entries");
+        let mut q = String::from("SELECT release, lemma, id, part_of_speech, row_id, frequency, homograph FROM entries
+            LEFT JOIN entry_frequencies ON entry_frequencies.entry_id == entries.row_id
+            LEFT JOIN entry_homographs ON entry_homographs.entry_id == entries.row_id");
         
-        if inflected {
+        if query.inflected {
             q.push_str(" JOIN variants ON variants.entry_id == entries.row_id WHERE dict=?");
         } else {
             q.push_str(" WHERE dict=?");
@@ -296,20 +483,53 @@ impl Backend for RusqliteState {
         let mut params = Vec::new();
         params.push(dictionary);
         let mut pos_str = String::new();
-        if let Some(pos) = part_of_speech {
+        if let Some(ref pos) = query.part_of_speech {
             q.push_str(" AND part_of_speech=?");
             pos_str.push_str(&format!("{:?}", pos));
             params.push(&pos_str);
         }
-        if inflected {
+        let mut hom_str = String::new();
+        if let Some(n) = query.homograph {
+            q.push_str(" AND homograph=?");
+            hom_str.push_str(&format!("{}", n));
+            params.push(&hom_str);
+        }
+        if query.inflected {
             q.push_str(" AND variants.form=?");
         } else {
             q.push_str(" AND entries.lemma=?");
         }
         params.push(headword);
+        // All the features must be on a single form with this headword. The
+        // features are stored as UD features
+        let mut features : Vec<(String, String)> = query.features.iter().map(|(k, v)| ud::feature(k, v)).collect();
+        features.sort();
+        features.dedup();
+        if !features.is_empty() {
+            q.push_str(" AND EXISTS (SELECT form_no FROM form_features WHERE form_features.entry_id == entries.row_id AND form=? AND (");
+            params.push(headword);
+            for (i, (feature, value)) in features.iter().enumerate() {
+                if i > 0 {
+                    q.push_str(" OR ");
+                }
+                q.push_str("(feature=? AND value=?)");
+                params.push(feature);
+                params.push(value);
+            }
+            q.push_str(&format!(") GROUP BY form_no HAVING COUNT(DISTINCT feature) = {})", features.len()));
+        }
+        let mut min_str = String::new();
+        if let Some(min) = query.frequency.min {
+            q.push_str(" AND frequency >= ?");
+            min_str.push_str(&format!("{}", min));
+            params.push(&min_str);
+        }
+        if query.frequency.sort {
+            q.push_str(&format!(" ORDER BY {}row_id", frequency_order(&query.frequency)));
+        }
         let mut o_str = String::new();
         let mut l_str = String::new();
-        if let Some(l) = limit {
+        if let Some(l) = query.limit {
             q.push_str(" LIMIT ?");
             l_str.push_str(&format!("{}", l));
             params.push(&l_str);
@@ -317,7 +537,7 @@ impl Backend for RusqliteState {
             q.push_str(" LIMIT -1");
         }
 
-        if let Some(o) = offset {
+        if let Some(o) = query.offset {
             q.push_str(" OFFSET ?");
             o_str.push_str(&format!("{}", o));
             params.push(&o_str);
@@ -334,7 +554,9 @@ impl Backend for RusqliteState {
                 lemma: r.get(1)?,
                 id: r.get(2)?,
                 part_of_speech: serde_json::from_str(&pos_str)?,
-                formats: extract_formats(row_id, &db)
+                formats: extract_formats(row_id, &db),
+                frequency: r.get::<_,Option<i64>>(5)?.map(|f| f as u64),
+                homograph: r.get(6)?
             })
         }
  
@@ -352,13 +574,31 @@ impl Backend for RusqliteState {
     /// Get the content as Json
     fn entry_json(&self, dictionary : &str, id : &str) -> Result<JsonEntry,BackendError> {
         let db = Connection::open(&self.path)?;
-        let mut stmt = db.prepare("SELECT json FROM json_entries JOIN entries ON entries.row_id == json_entries.entry_id WHERE dict=? AND id=?")?;
+        let mut stmt = db.prepare("SELECT json, row_id FROM json_entries JOIN entries ON entries.row_id == json_entries.entry_id WHERE dict=? AND id=?")?;
         let mut result = stmt.query(&[dictionary, id])?;
         if let Some(r) = result.next()? {
             let json_str : String = r.get(0)?;
-            Ok(serde_json::from_str(&json_str)?)
+            let mut entry = serde_json::from_str::<JsonEntry>(&json_str)?;
+            entry.homograph = entry_homograph(r.get(1)?, &db);
+            Ok(entry.link_media(dictionary))
         } else {
-            Err(BackendError::NotFound)
+            let mut stmt = db.prepare("SELECT json, lemma, part_of_speech, row_id FROM tei_structures JOIN entries ON entries.row_id == tei_structures.entry_id WHERE dict=? AND id=?")?;
+            let mut result = stmt.query([dictionary, id])?;
+            if let Some(r) = result.next()? {
+                let json_str : String = r.get(0)?;
+                let tei : TeiEntry = serde_json::from_str(&json_str)?;
+                let lemma : String = r.get(1)?;
+                let pos : Vec<_> = serde_json::from_str(&r.get::<_,String>(2)?)?;
+                let row_id : u32 = r.get(3)?;
+                let mut stmt = db.prepare("SELECT form FROM variants WHERE entry_id=? AND form != ?")?;
+                let variants = stmt.query_map(rusqlite::params![row_id, lemma], |r| r.get(0))?
+                    .collect::<Result<Vec<String>,rusqlite::Error>>()?;
+                let mut entry = JsonEntry::from_tei(id, &lemma, &pos, &variants, &tei);
+                entry.homograph = entry_homograph(row_id, &db);
+                Ok(entry.link_media(dictionary))
+            } else {
+                Err(BackendError::NotFound)
+            }
         }
     }
     /// Get the content as OntoLex
@@ -383,20 +623,362 @@ impl Backend for RusqliteState {
             Err(BackendError::NotFound)
         }
      }
+    /// Get the structured senses of a TEI entry
+    fn entry_senses(&self, dictionary : &str, id : &str) -> Result<Vec<TeiSense>,BackendError> {
+        let db = Connection::open(&self.path)?;
+        let mut stmt = db.prepare("SELECT json FROM tei_structures JOIN entries ON entries.row_id == tei_structures.entry_id WHERE dict=? AND id=?")?;
+        let mut result = stmt.query([dictionary, id])?;
+        if let Some(r) = result.next()? {
+            let json_str : String = r.get(0)?;
+            let tei : TeiEntry = serde_json::from_str(&json_str)?;
+            Ok(tei.senses)
+        } else {
+            Err(BackendError::NotFound)
+        }
+    }
+    /// Get a sense of a TEI entry by its identifier
+    fn sense(&self, dictionary : &str, sense_id : &str) -> Result<TeiSense,BackendError> {
+        let db = Connection::open(&self.path)?;
+        let mut stmt = db.prepare("SELECT json FROM tei_structures JOIN senses ON senses.entry_id == tei_structures.entry_id JOIN entries ON entries.row_id == senses.entry_id WHERE dict=? AND sense_id=?")?;
+        let mut result = stmt.query([dictionary, sense_id])?;
+        if let Some(r) = result.next()? {
+            let json_str : String = r.get(0)?;
+            let tei : TeiEntry = serde_json::from_str(&json_str)?;
+            tei.find_sense(sense_id).cloned().ok_or(BackendError::NotFound)
+        } else {
+            Err(BackendError::NotFound)
+        }
+    }
+    /// Get the entries a TEI entry is nested in or contains
+    fn entry_relations(&self, dictionary : &str, id : &str) -> Result<TeiRelations,BackendError> {
+        let db = Connection::open(&self.path)?;
+        let mut stmt = db.prepare("SELECT json FROM tei_structures JOIN entries ON entries.row_id == tei_structures.entry_id WHERE dict=? AND id=?")?;
+        let mut result = stmt.query([dictionary, id])?;
+        if let Some(r) = result.next()? {
+            let json_str : String = r.get(0)?;
+            let tei : TeiEntry = serde_json::from_str(&json_str)?;
+            return Ok(tei.relations);
+        }
+        let mut stmt = db.prepare("SELECT json FROM ontolex_structures JOIN entries ON entries.row_id == ontolex_structures.entry_id WHERE dict=? AND id=?")?;
+        let mut result = stmt.query([dictionary, id])?;
+        if let Some(r) = result.next()? {
+            let json_str : String = r.get(0)?;
+            let ontolex : OntoLexEntry = serde_json::from_str(&json_str)?;
+            Ok(ontolex.relations)
+        } else {
+            Err(BackendError::NotFound)
+        }
+    }
+    /// Get the etymology of an entry
+    fn entry_etymology(&self, dictionary : &str, id : &str) -> Result<Etymology,BackendError> {
+        let db = Connection::open(&self.path)?;
+        let row_id : u32 = db.query_row("SELECT row_id FROM entries WHERE dict=? AND id=?", [dictionary, id], |r| r.get(0))
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => BackendError::NotFound,
+                e => e.into()
+            })?;
+        let mut stmt = db.prepare("SELECT row_id, parent, cognate, language, language_name, forms, glosses, etym_type, entry_ref FROM etymons WHERE entry_id=? ORDER BY row_id")?;
+        let mut result = stmt.query([&row_id])?;
+        let mut etymons : Vec<(u32, Option<u32>, bool, Etymon)> = Vec::new();
+        while let Some(r) = result.next()? {
+            let forms : String = r.get(5)?;
+            let glosses : String = r.get(6)?;
+            etymons.push((r.get(0)?, r.get(1)?, r.get(2)?, Etymon {
+                language : r.get(3)?,
+                language_name : r.get(4)?,
+                forms : serde_json::from_str(&forms)?,
+                glosses : serde_json::from_str(&glosses)?,
+                etym_type : r.get(7)?,
+                entry : r.get(8)?,
+                ancestors : Vec::new()
+            }));
+        }
+        // Children were inserted after their parents, so attach them in reverse
+        let mut ancestors = Vec::new();
+        let mut cognates = Vec::new();
+        while let Some((_, parent, cognate, etymon)) = etymons.pop() {
+            match parent.and_then(|p| etymons.iter_mut().find(|e| e.0 == p)) {
+                Some(parent) => parent.3.ancestors.insert(0, etymon),
+                None if cognate => cognates.insert(0, etymon),
+                None => ancestors.insert(0, etymon)
+            }
+        }
+        Ok(Etymology::new(ancestors, cognates))
+    }
+    /// Get the paradigm of an entry
+    fn paradigm(&self, dictionary : &str, id : &str) -> Result<Paradigm,BackendError> {
+        let db = Connection::open(&self.path)?;
+        let mut stmt = db.prepare("SELECT json, lemma FROM tei_structures JOIN entries ON entries.row_id == tei_structures.entry_id WHERE dict=? AND id=?")?;
+        let mut result = stmt.query([dictionary, id])?;
+        if let Some(r) = result.next()? {
+            let json_str : String = r.get(0)?;
+            let lemma : String = r.get(1)?;
+            let tei : TeiEntry = serde_json::from_str(&json_str)?;
+            return Ok(Paradigm::from_tei(id, &lemma, &tei));
+        }
+        let mut stmt = db.prepare("SELECT json, lemma FROM ontolex_structures JOIN entries ON entries.row_id == ontolex_structures.entry_id WHERE dict=? AND id=?")?;
+        let mut result = stmt.query([dictionary, id])?;
+        if let Some(r) = result.next()? {
+            let json_str : String = r.get(0)?;
+            let lemma : String = r.get(1)?;
+            let ontolex : OntoLexEntry = serde_json::from_str(&json_str)?;
+            return Ok(Paradigm::from_ontolex(id, &lemma, &ontolex));
+        }
+        let mut stmt = db.prepare("SELECT json FROM json_entries JOIN entries ON entries.row_id == json_entries.entry_id WHERE dict=? AND id=?")?;
+        let mut result = stmt.query([dictionary, id])?;
+        if let Some(r) = result.next()? {
+            let json_str : String = r.get(0)?;
+            let entry : JsonEntry = serde_json::from_str(&json_str)?;
+            Ok(Paradigm::from_json(&entry))
+        } else {
+            Err(BackendError::NotFound)
+        }
+    }
+    /// Search the dictionary by pronunciation
+    fn lookup_pron(&self, dictionary : &str, ipa : &str, distance : f64,
+                   offset : Option<usize>, limit : Option<usize>) -> Result<Vec<PronMatch>,BackendError> {
+        let db = Connection::open(&self.path)?;
+        check_dictionary(&db, dictionary)?;
+        let ipa = ipa::normalize(ipa);
+        let length = ipa::length(&ipa) as f64;
+        // Each inserted or deleted segment costs 1, so only pronunciations
+        // of a similar length need to be compared
+        let mut stmt = db.prepare("SELECT row_id, form, pronunciations.ipa FROM pronunciations 
+            JOIN entries ON entries.row_id == pronunciations.entry_id 
+            WHERE dict=? AND length >= ? AND length <= ? ORDER BY row_id")?;
+        let mut result = stmt.query(rusqlite::params![dictionary, 
+            (length - distance).floor() as i64, (length + distance).ceil() as i64])?;
+        let mut prons : Vec<(u32, Vec<Pronunciation>)> = Vec::new();
+        while let Some(r) = result.next()? {
+            let row_id : u32 = r.get(0)?;
+            let pron = Pronunciation { form : r.get(1)?, ipa : r.get(2)? };
+            match prons.last_mut() {
+                Some((id, p)) if *id == row_id => p.push(pron),
+                _ => prons.push((row_id, vec![pron]))
+            }
+        }
+        let mut matches = Vec::new();
+        for (row_id, p) in prons {
+            if let Some((pron, d)) = PronMatch::closest(&ipa, p, distance) {
+                matches.push(PronMatch::new(entry_by_row_id(&db, row_id)?, pron, d));
+            }
+        }
+        Ok(PronMatch::rank(matches, offset, limit))
+    }
+    /// Get a media file from the store
+    fn media(&self, dictionary : &str, id : &str) -> Result<Media,BackendError> {
+        let db = Connection::open(&self.path)?;
+        let mime_type : String = db.query_row("SELECT mime_type FROM media WHERE dict=? AND id=?", [dictionary, id], |r| r.get(0))
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => BackendError::NotFound,
+                e => e.into()
+            })?;
+        let mut media = Media::new(id, Some(mime_type));
+        media.id = Some(id.to_string());
+        media.path = Some(media::store_dir(&self.path).join(media::safe_name(dictionary)).join(id));
+        Ok(media)
+    }
+    /// Search the dictionary by a word in the definitions
+    fn lookup_definition(&self, dictionary : &str, word : &str,
+                         offset : Option<usize>, limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
+        let db = Connection::open(&self.path)?;
+        check_dictionary(&db, dictionary)?;
+        // LIKE finds the definitions containing the word, which are then
+        // checked for the whole word
+        let mut stmt = db.prepare("SELECT row_id, definition FROM definitions
+            JOIN entries ON entries.row_id == definitions.entry_id
+            WHERE dict=? AND definition LIKE ? ESCAPE '\\' ORDER BY lemma, entries.id")?;
+        let pattern = format!("%{}%", word.trim().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        let mut result = stmt.query(rusqlite::params![dictionary, pattern])?;
+        let mut entries : Vec<(u32, Entry)> = Vec::new();
+        while let Some(r) = result.next()? {
+            let row_id : u32 = r.get(0)?;
+            let definition : String = r.get(1)?;
+            if entries.last().map(|e| e.0 == row_id).unwrap_or(false) || !contains_word(&definition, word) {
+                continue;
+            }
+            entries.push((row_id, entry_by_row_id(&db, row_id)?));
+        }
+        Ok(entries.into_iter().map(|e| e.1).skip(offset.unwrap_or(0)).take(limit.unwrap_or(usize::MAX)).collect())
+    }
+    /// Search the dictionary by a translation
+    fn lookup_translation(&self, dictionary : &str, language : &str, word : &str,
+                          offset : Option<usize>, limit : Option<usize>) -> Result<Vec<TranslationMatch>,BackendError> {
+        let db = Connection::open(&self.path)?;
+        check_dictionary(&db, dictionary)?;
+        // A language also matches its more specific tags, e.g., de-AT for de
+        let mut stmt = db.prepare("SELECT row_id, sense, text FROM translations
+            JOIN entries ON entries.row_id == translations.entry_id
+            WHERE dict=? AND text=? AND (language LIKE ? OR language LIKE ?)
+            ORDER BY lemma, entries.id, translations.rowid LIMIT ? OFFSET ?")?;
+        let mut result = stmt.query(rusqlite::params![dictionary, word, language, format!("{}-%", language),
+            limit.map(|l| l as i64).unwrap_or(-1), offset.unwrap_or(0) as i64])?;
+        let mut matches = Vec::new();
+        while let Some(r) = result.next()? {
+            let row_id : u32 = r.get(0)?;
+            matches.push(TranslationMatch {
+                entry : entry_by_row_id(&db, row_id)?,
+                sense : r.get(1)?,
+                translation : r.get(2)?
+            });
+        }
+        Ok(matches)
+    }
+    /// Search the dictionary for the multi-word expressions containing a lemma
+    fn lookup_mwe(&self, dictionary : &str, lemma : &str,
+                  offset : Option<usize>, limit : Option<usize>) -> Result<Vec<Entry>,BackendError> {
+        let db = Connection::open(&self.path)?;
+        check_dictionary(&db, dictionary)?;
+        // Entries with components are matched by their components, other
+        // entries by the words of their lemma
+        let mut stmt = db.prepare("SELECT row_id, lemma,
+            EXISTS (SELECT 1 FROM components WHERE entry_id=row_id) FROM entries
+            WHERE dict=? AND (row_id IN (SELECT entry_id FROM components WHERE key=?) OR lemma LIKE ? ESCAPE '\\')
+            ORDER BY lemma, id")?;
+        let pattern = format!("%{}%", lemma.trim().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        let mut result = stmt.query(rusqlite::params![dictionary, lemma.trim().to_lowercase(), pattern])?;
+        let mut entries = Vec::new();
+        while let Some(r) = result.next()? {
+            let row_id : u32 = r.get(0)?;
+            let entry_lemma : String = r.get(1)?;
+            let has_components : bool = r.get(2)?;
+            if !has_components && !mwe_contains(&entry_lemma, &[], lemma) {
+                continue;
+            }
+            entries.push(entry_by_row_id(&db, row_id)?);
+        }
+        Ok(entries.into_iter().skip(offset.unwrap_or(0)).take(limit.unwrap_or(usize::MAX)).collect())
+    }
+    /// Find the entries that have a form, with its features
+    fn analyse(&self, dictionary : &str, form : &str,
+               offset : Option<usize>, limit : Option<usize>) -> Result<Vec<FormAnalysis>,BackendError> {
+        let db = Connection::open(&self.path)?;
+        check_dictionary(&db, dictionary)?;
+        let mut stmt = db.prepare("SELECT DISTINCT row_id, lemma, id FROM entries
+            JOIN variants ON variants.entry_id == entries.row_id 
+            WHERE dict=? AND variants.form=? ORDER BY lemma, id")?;
+        let mut result = stmt.query([dictionary, form])?;
+        let mut analyses = Vec::new();
+        while let Some(r) = result.next()? {
+            let entry = entry_by_row_id(&db, r.get(0)?)?;
+            let paradigm = self.paradigm(dictionary, &entry.id)?;
+            analyses.extend(FormAnalysis::from_paradigm(entry, &paradigm, form));
+        }
+        Ok(FormAnalysis::rank(analyses, offset, limit))
+    }
+    /// The part-of-speech tags used in the dictionary and how they were mapped
+    fn tagset(&self, dictionary : &str) -> Result<Vec<TagUsage>,BackendError> {
+        let db = Connection::open(&self.path)?;
+        check_dictionary(&db, dictionary)?;
+        let mut stmt = db.prepare("SELECT tag, mapping, COUNT(DISTINCT entry_id) FROM entry_tags
+            JOIN entries ON entries.row_id == entry_tags.entry_id
+            WHERE dict=? GROUP BY tag, mapping ORDER BY tag")?;
+        let mut result = stmt.query([dictionary])?;
+        let mut tags = Vec::new();
+        while let Some(r) = result.next()? {
+            tags.push(TagUsage {
+                tag : r.get(0)?,
+                mapping : serde_json::from_str(&r.get::<_,String>(1)?)?,
+                entries : r.get::<_,u32>(2)? as usize
+            });
+        }
+        Ok(tags)
+    }
+
+}
+
+fn insert_etymon(db : &Connection, entry_id : u32, parent : Option<u32>, cognate : bool, 
+                 etymon : &Etymon) -> Result<(),rusqlite::Error> {
+    let mut stmt = db.prepare("INSERT INTO etymons (entry_id, parent, cognate, language, language_name, forms, glosses, etym_type, entry_ref) VALUES (?,?,?,?,?,?,?,?,?)")?;
+    stmt.execute(rusqlite::params![entry_id, parent, cognate, etymon.language, etymon.language_name,
+        serde_json::to_string(&etymon.forms).unwrap(), serde_json::to_string(&etymon.glosses).unwrap(),
+        etymon.etym_type, etymon.entry])?;
+    let row_id = db.last_insert_rowid() as u32;
+    for ancestor in etymon.ancestors.iter() {
+        insert_etymon(db, entry_id, Some(row_id), cognate, ancestor)?;
+    }
+    Ok(())
+}
 
+/// Fail with `NotFound` if there is no dictionary with an id
+fn check_dictionary(db : &Connection, dictionary : &str) -> Result<(),BackendError> {
+    match db.query_row("SELECT 1 FROM dictionaries WHERE id=?", [dictionary], |_| Ok(())) {
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(BackendError::NotFound),
+        r => Ok(r?)
+    }
+}
+
+/// The entry stored in a row of the entries table
+fn entry_by_row_id(db : &Connection, row_id : u32) -> Result<Entry,BackendError> {
+    let (release, lemma, id, pos) = db.query_row("SELECT release, lemma, id, part_of_speech FROM entries WHERE row_id=?",
+        [&row_id], |r| Ok((r.get::<_,String>(0)?, r.get(1)?, r.get(2)?, r.get::<_,String>(3)?)))?;
+    Ok(Entry {
+        release : serde_json::from_str(&release)?,
+        lemma, id,
+        part_of_speech : serde_json::from_str(&pos)?,
+        formats : extract_formats(row_id, db),
+        frequency : entry_frequency(row_id, db),
+        homograph : entry_homograph(row_id, db)
+    })
+}
+
+/// The order by frequency, with entries without a frequency last
+fn frequency_order(frequency : &FrequencyFilter) -> &'static str {
+    if frequency.sort {
+        "frequency IS NULL, frequency DESC, "
+    } else {
+        ""
+    }
+}
+
+/// The frequency of an entry
+fn entry_frequency(row_id : u32, connection : &Connection) -> Option<u64> {
+    connection.query_row("SELECT frequency FROM entry_frequencies WHERE entry_id=?", [&row_id], 
+        |r| r.get::<_,i64>(0)).ok().map(|f| f as u64)
+}
+
+/// The homograph number of an entry
+fn entry_homograph(row_id : u32, connection : &Connection) -> Option<u32> {
+    connection.query_row("SELECT homograph FROM entry_homographs WHERE entry_id=?", [&row_id],
+        |r| r.get(0)).ok().flatten()
+}
+
+/// Number the homographs of a dictionary that are not numbered in the
+/// source, updating the numbers that have changed
+fn number_homographs(db : &Connection, dict_id : &str) -> Result<(),rusqlite::Error> {
+    let mut stmt = db.prepare("SELECT row_id, lemma, part_of_speech, source, homograph FROM entries
+        JOIN entry_homographs ON entry_homographs.entry_id == entries.row_id
+        WHERE dict=? ORDER BY row_id")?;
+    let mut result = stmt.query([dict_id])?;
+    let mut rows = Vec::new();
+    let mut entries = Vec::new();
+    while let Some(r) = result.next()? {
+        let pos_str : String = r.get(2)?;
+        rows.push((r.get::<_,u32>(0)?, r.get::<_,Option<u32>>(4)?));
+        entries.push((r.get(1)?, r.get(3)?, serde_json::from_str(&pos_str).unwrap_or_default(), false));
+    }
+    let mut stmt = db.prepare("UPDATE entry_homographs SET homograph=? WHERE entry_id=?")?;
+    for ((row_id, old), new) in rows.into_iter().zip(model::number_homographs(&entries)) {
+        if old != new {
+            stmt.execute(rusqlite::params![new, row_id])?;
+        }
+    }
+    Ok(())
 }
 
 fn extract_formats(row_id : u32, connection : &Connection) -> Vec<Format> {
     let mut formats = Vec::new();
-    if connection.query_row("SELECT * FROM json_entries WHERE entry_id=?", &[&row_id], |_| Ok(())).is_ok() {
-        formats.push(Format::json);
-    }
     if connection.query_row("SELECT * FROM tei_entries WHERE entry_id=?", &[&row_id], |_| Ok(())).is_ok() {
         formats.push(Format::tei);
     }
     if connection.query_row("SELECT * FROM ontolex_entries WHERE entry_id=?", &[&row_id], |_| Ok(())).is_ok() {
         formats.push(Format::ontolex);
     }
+    // TEI and OntoLex entries can also be returned as Json
+    if connection.query_row("SELECT * FROM json_entries WHERE entry_id=?", [&row_id], |_| Ok(())).is_ok() ||
+        connection.query_row("SELECT * FROM tei_structures WHERE entry_id=?", [&row_id], |_| Ok(())).is_ok() {
+        formats.push(Format::json);
+    }
 
     formats
 }
@@ -503,32 +1085,32 @@ fn test_backend() {
         url: None }]);
     assert_eq!(meta.publisher, Vec::new());
 
-    let list = state.list("dict1",None,None).unwrap();
+    let list = state.list("dict1",None,None, &FrequencyFilter::default()).unwrap();
     assert_eq!(list.len(), 1);
-    let list = state.list("dict1",Some(0),None).unwrap();
+    let list = state.list("dict1",Some(0),None, &FrequencyFilter::default()).unwrap();
     assert_eq!(list.len(), 1);
-    let list = state.list("dict1",None,Some(1)).unwrap();
+    let list = state.list("dict1",None,Some(1), &FrequencyFilter::default()).unwrap();
     assert_eq!(list.len(), 1);
-    let list = state.list("dict1",Some(0),Some(1)).unwrap();
+    let list = state.list("dict1",Some(0),Some(1), &FrequencyFilter::default()).unwrap();
     assert_eq!(list.len(), 1);
 
   
-    let _lookup = state.lookup("dict1", "example", None, None, None, false).unwrap();
-    let _lookup = state.lookup("dict1", "example", Some(0), None, None, false).unwrap();
-    let _lookup = state.lookup("dict1", "example", None, Some(1), None, false).unwrap();
-    let _lookup = state.lookup("dict1", "example", Some(0), Some(1), None, false).unwrap();
-    let _lookup = state.lookup("dict1", "example", None, None, Some(PartOfSpeech::ADJ), false).unwrap();
-    let _lookup = state.lookup("dict1", "example", Some(0), None, Some(PartOfSpeech::ADJ), false).unwrap();
-    let _lookup = state.lookup("dict1", "example", None, Some(1), Some(PartOfSpeech::ADJ), false).unwrap();
-    let _lookup = state.lookup("dict1", "example", Some(0), Some(1), Some(PartOfSpeech::ADJ), false).unwrap();
-    let _lookup = state.lookup("dict1", "example", None, None, None, true).unwrap();
-    let _lookup = state.lookup("dict1", "example", Some(0), None, None, true).unwrap();
-    let _lookup = state.lookup("dict1", "example", None, Some(1), None, true).unwrap();
-    let _lookup = state.lookup("dict1", "example", Some(0), Some(1), None, true).unwrap();
-    let _lookup = state.lookup("dict1", "example", None, None, Some(PartOfSpeech::ADJ), true).unwrap();
-    let _lookup = state.lookup("dict1", "example", Some(0), None, Some(PartOfSpeech::ADJ), true).unwrap();
-    let _lookup = state.lookup("dict1", "example", None, Some(1), Some(PartOfSpeech::ADJ), true).unwrap();
-    let _lookup = state.lookup("dict1", "example", Some(0), Some(1), Some(PartOfSpeech::ADJ), true).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery::default()).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { offset : Some(0), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { limit : Some(1), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { offset : Some(0), limit : Some(1), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADJ), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADJ), offset : Some(0), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADJ), limit : Some(1), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADJ), offset : Some(0), limit : Some(1), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { inflected : true, ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { inflected : true, offset : Some(0), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { inflected : true, limit : Some(1), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { inflected : true, offset : Some(0), limit : Some(1), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADJ), inflected : true, ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADJ), inflected : true, offset : Some(0), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADJ), inflected : true, limit : Some(1), ..LookupQuery::default() }).unwrap();
+    let _lookup = state.lookup("dict1", "example", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADJ), inflected : true, offset : Some(0), limit : Some(1), ..LookupQuery::default() }).unwrap();
 
     let _entry_json = state.entry_json("dict1", "test").unwrap();
     state.entry_ontolex("dict1","test").err().unwrap();
@@ -537,3 +1119,526 @@ fn test_backend() {
 }
 
 
+
+#[test]
+fn test_load_stream() {
+    let state = RusqliteState::new("test-tmp4.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let mut entries = vec![
+        Ok(("dict1".to_string(), EntryContent::OntoLex { id : "cat".to_string(), lemma : "cat".to_string(),
+            pos : vec![PartOfSpeech::NOUN], variants : Vec::new(), content : "<#cat> a ontolex:LexicalEntry .".to_string(), entry : OntoLexEntry::default() })),
+        Ok(("dict1".to_string(), EntryContent::OntoLex { id : "dog".to_string(), lemma : "dog".to_string(),
+            pos : vec![PartOfSpeech::NOUN], variants : Vec::new(), content : "<#dog> a ontolex:LexicalEntry .".to_string(), entry : OntoLexEntry::default() }))
+    ].into_iter();
+    state.load_stream(Release::PUBLIC, dictionaries, &mut entries).unwrap();
+    let result = state.lookup("dict1", "dog", &LookupQuery::default()).unwrap();
+    fs::remove_file("test-tmp4.db").unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].id, "dog");
+}
+
+#[test]
+fn test_tei_senses() {
+    let state = RusqliteState::new("test-tmp5.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let sense : TeiSense = serde_json::from_str("{\"id\":\"cat-1\",\"definitions\":[\"A small feline\"]}").unwrap();
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::Tei { id : "cat".to_string(), lemma : "cat".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : vec!["cats".to_string()], content : "<entry/>".to_string(), entry : TeiEntry { senses : vec![sense.clone()], ..TeiEntry::default() } }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let senses = state.entry_senses("dict1", "cat");
+    let found = state.sense("dict1", "cat-1");
+    let json = state.entry_json("dict1", "cat");
+    let formats = state.lookup("dict1", "cat", &LookupQuery::default()).map(|e| e[0].formats.clone());
+    fs::remove_file("test-tmp5.db").unwrap();
+    assert_eq!(senses.unwrap(), vec![sense.clone()]);
+    assert_eq!(found.unwrap(), sense);
+    let json = json.unwrap();
+    assert_eq!(json.other_form.unwrap().len(), 1);
+    assert_eq!(json.senses.len(), 1);
+    assert_eq!(formats.unwrap(), vec![Format::tei, Format::json]);
+}
+
+#[test]
+fn test_form_features() {
+    let state = RusqliteState::new("test-tmp6.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let tei : TeiEntry = serde_json::from_str("{\"forms\":[
+        {\"type\":\"lemma\",\"writtenRep\":\"sheep\",\"features\":{\"number\":\"singular\"}},
+        {\"type\":\"inflected\",\"writtenRep\":\"sheep\",\"features\":{\"number\":\"plural\",\"case\":\"nominative\"}}],
+        \"senses\":[]}").unwrap();
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::Tei { id : "sheep".to_string(), lemma : "sheep".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : tei }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let f = |feats : &[(&str, &str)]| feats.iter().map(|(k,v)| (k.to_string(), v.to_string())).collect::<Vec<_>>();
+    let plural = state.lookup("dict1", "sheep", &LookupQuery { inflected : true, features : f(&[("number", "plural")]), ..LookupQuery::default() });
+    let both = state.lookup("dict1", "sheep", &LookupQuery { inflected : true, features : f(&[("number", "plural"), ("case", "nominative")]), ..LookupQuery::default() });
+    let mixed = state.lookup("dict1", "sheep", &LookupQuery { inflected : true, features : f(&[("number", "singular"), ("case", "nominative")]), ..LookupQuery::default() });
+    let ud = state.lookup("dict1", "sheep", &LookupQuery { inflected : true, features : f(&[("Case", "Nom"), ("Number", "Plur")]), ..LookupQuery::default() });
+    let analyses = state.analyse("dict1", "sheep", None, None);
+    fs::remove_file("test-tmp6.db").unwrap();
+    assert_eq!(plural.unwrap().len(), 1);
+    assert_eq!(both.unwrap().len(), 1);
+    assert_eq!(mixed.unwrap().len(), 0);
+    assert_eq!(ud.unwrap().len(), 1);
+    assert_eq!(analyses.unwrap().iter().map(|a| a.features.as_str()).collect::<Vec<_>>(), vec!["Number=Sing", "Case=Nom|Number=Plur"]);
+}
+
+#[test]
+fn test_etymology() {
+    let state = RusqliteState::new("test-tmp7.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let etymology : Etymology = serde_json::from_str("{\"ancestors\":[{\"language\":\"enm\",\"forms\":[\"gurle\"],\"type\":\"borrowing\",
+        \"ancestors\":[{\"language\":\"ang\",\"forms\":[\"gyrela\"],\"glosses\":[\"dress\"]}]},{\"language\":\"nl\",\"forms\":[\"gör\"]}],
+        \"cognates\":[{\"language\":\"nds\",\"forms\":[\"Göre\"]}]}").unwrap();
+    let etymology = Etymology::new(etymology.ancestors, etymology.cognates);
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::Tei { id : "girl".to_string(), lemma : "girl".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { etymology : etymology.clone(), ..TeiEntry::default() } },
+        EntryContent::Tei { id : "boy".to_string(), lemma : "boy".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry::default() }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let girl = state.entry_etymology("dict1", "girl");
+    let boy = state.entry_etymology("dict1", "boy");
+    let missing = state.entry_etymology("dict1", "man");
+    fs::remove_file("test-tmp7.db").unwrap();
+    assert_eq!(girl.unwrap(), etymology);
+    assert!(boy.unwrap().is_empty());
+    assert!(missing.is_err());
+}
+
+#[test]
+fn test_lookup_pron() {
+    let state = RusqliteState::new("test-tmp8.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let form = |w : &str, p : &str| serde_json::from_str(&format!("{{\"type\":\"lemma\",\"writtenRep\":\"{}\",\"pronunciations\":[\"{}\"]}}", w, p)).unwrap();
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::Tei { id : "cat".to_string(), lemma : "cat".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { forms : vec![form("cat", "kæt")], ..TeiEntry::default() } },
+        EntryContent::Tei { id : "cut".to_string(), lemma : "cut".to_string(), pos : vec![PartOfSpeech::VERB],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { forms : vec![form("cut", "kʌt")], ..TeiEntry::default() } },
+        EntryContent::Tei { id : "cattle".to_string(), lemma : "cattle".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { forms : vec![form("cattle", "kætəl")], ..TeiEntry::default() } }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let close = state.lookup_pron("dict1", "/kæt/", 1.0, None, None);
+    let far = state.lookup_pron("dict1", "kæt", 3.0, Some(2), None);
+    let missing = state.lookup_pron("dict2", "kæt", 1.0, None, None);
+    fs::remove_file("test-tmp8.db").unwrap();
+    let close = close.unwrap();
+    assert_eq!(close.len(), 2);
+    assert_eq!(close[0].entry.id, "cat");
+    assert_eq!(close[0].distance, 0.0);
+    assert_eq!(close[1].entry.id, "cut");
+    assert_eq!(close[1].entry.formats, vec![Format::tei, Format::json]);
+    assert_eq!(far.unwrap()[0].entry.id, "cattle");
+    assert!(missing.is_err());
+}
+
+#[test]
+fn test_media() {
+    let state = RusqliteState::new("test-tmp9.db");
+    fs::create_dir_all("test-tmp-media2/a").unwrap();
+    fs::create_dir_all("test-tmp-media2/b").unwrap();
+    fs::write("test-tmp-media2/a/cat.png", b"cat").unwrap();
+    fs::write("test-tmp-media2/b/cat.png", b"another cat").unwrap();
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let media = |path : &str| {
+        let mut m = Media::new(path, None);
+        m.resolve(Some(std::path::Path::new("test-tmp-media2")), None).unwrap();
+        m
+    };
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::Tei { id : "cat".to_string(), lemma : "cat".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { media : vec![media("a/cat.png"), media("b/cat.png")], ..TeiEntry::default() } }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let first = state.media("dict1", "cat.png");
+    let second = state.media("dict1", "cat-2.png");
+    let json = state.entry_json("dict1", "cat");
+    let stored = fs::read("test-tmp9.media/dict1/cat-2.png");
+    state.delete("dict1").unwrap();
+    let deleted = std::path::Path::new("test-tmp9.media/dict1").exists();
+    fs::remove_file("test-tmp9.db").unwrap();
+    fs::remove_dir_all("test-tmp9.media").unwrap();
+    fs::remove_dir_all("test-tmp-media2").unwrap();
+    let first = first.unwrap();
+    assert_eq!(first.mime_type, "image/png");
+    assert_eq!(first.path, Some(std::path::PathBuf::from("test-tmp9.media/dict1/cat.png")));
+    assert!(second.is_ok());
+    assert_eq!(stored.unwrap(), b"another cat");
+    assert_eq!(json.unwrap().media.iter().map(|m| m.url.as_str()).collect::<Vec<_>>(),
+        vec!["/media/dict1/cat.png", "/media/dict1/cat-2.png"]);
+    assert!(!deleted);
+}
+
+#[test]
+fn test_media_missing() {
+    let state = RusqliteState::new("test-tmp10.db");
+    let mut m = Media::new("dog.png", None);
+    m.resolve(Some(std::path::Path::new(".")), None).unwrap();
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::Tei { id : "dog".to_string(), lemma : "dog".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { media : vec![m], ..TeiEntry::default() } }]);
+    let result = state.load(Release::PUBLIC, HashMap::new(), entries);
+    fs::remove_file("test-tmp10.db").unwrap();
+    assert!(matches!(result, Err(BackendError::Other(ref msg)) if msg.starts_with("Could not import media file dog.png")));
+}
+
+#[test]
+fn test_lookup_translation() {
+    let state = RusqliteState::new("test-tmp10.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["de".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let equivalent = |sense : &str, language : &str, text : &str| Equivalent {
+        sense : Some(sense.to_string()), language : language.to_string(), text : text.to_string()
+    };
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::OntoLex { id : "shore".to_string(), lemma : "shore".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry { 
+                translations : vec![equivalent("shore-1", "de-AT", "Ufer")],
+                ..OntoLexEntry::default() } },
+        EntryContent::OntoLex { id : "bank".to_string(), lemma : "bank".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry { 
+                translations : vec![equivalent("bank-1", "de", "Bank"), equivalent("bank-2", "de", "Ufer")],
+                ..OntoLexEntry::default() } }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let all = state.lookup_translation("dict1", "de", "Ufer", None, None);
+    let paged = state.lookup_translation("dict1", "de", "Ufer", Some(1), Some(1));
+    let none = state.lookup_translation("dict1", "fr", "Ufer", None, None);
+    let missing = state.lookup_translation("dict2", "de", "Ufer", None, None);
+    state.delete("dict1").unwrap();
+    let deleted : u32 = Connection::open("test-tmp10.db").unwrap()
+        .query_row("SELECT COUNT(*) FROM translations", [], |r| r.get(0)).unwrap();
+    fs::remove_file("test-tmp10.db").unwrap();
+    let all = all.unwrap();
+    assert_eq!(all.iter().map(|m| m.entry.id.as_str()).collect::<Vec<_>>(), vec!["bank", "shore"]);
+    assert_eq!(all[0].sense, Some("bank-2".to_string()));
+    assert_eq!(all[0].entry.formats, vec![Format::ontolex, Format::json]);
+    assert_eq!(paged.unwrap()[0].entry.id, "shore");
+    assert!(none.unwrap().is_empty());
+    assert!(missing.is_err());
+    assert_eq!(deleted, 0);
+}
+
+#[test]
+fn test_lookup_definition() {
+    let state = RusqliteState::new("test-tmp11.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let sense = |definition : &str| Sense {
+        id : None, definition : Some(definition.to_string()), reference : None, usage : None, examples : Vec::new(),
+        frequencies : Vec::new(), attestations : Vec::new()
+    };
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::OntoLex { id : "cat".to_string(), lemma : "cat".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : vec!["cats".to_string()], content : "".to_string(), entry : OntoLexEntry { 
+                senses : vec![sense("A small feline"), sense("A 100% jazz fan")],
+                ..OntoLexEntry::default() } },
+        EntryContent::OntoLex { id : "lion".to_string(), lemma : "lion".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry { 
+                senses : vec![sense("A large feline"), sense("A brave person, like a feline")],
+                ..OntoLexEntry::default() } }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let feline = state.lookup_definition("dict1", "Feline", None, None);
+    let paged = state.lookup_definition("dict1", "feline", Some(1), Some(1));
+    let percent = state.lookup_definition("dict1", "100%", None, None);
+    let partial = state.lookup_definition("dict1", "line", None, None);
+    let json = state.entry_json("dict1", "cat");
+    state.delete("dict1").unwrap();
+    fs::remove_file("test-tmp11.db").unwrap();
+    let feline = feline.unwrap();
+    assert_eq!(feline.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["cat", "lion"]);
+    assert_eq!(feline[0].formats, vec![Format::ontolex, Format::json]);
+    assert_eq!(paged.unwrap()[0].id, "lion");
+    assert_eq!(percent.unwrap().len(), 1);
+    assert!(partial.unwrap().is_empty());
+    let json = json.unwrap();
+    assert_eq!(json.senses.len(), 2);
+    assert_eq!(json.other_form.unwrap().len(), 1);
+}
+
+#[test]
+fn test_lookup_mwe() {
+    let state = RusqliteState::new("test-tmp12.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let component = |lemma : &str| Component { id : None, entry : None, lemma : Some(lemma.to_string()) };
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::OntoLex { id : "hand".to_string(), lemma : "hand".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry::default() },
+        EntryContent::OntoLex { id : "hand-over-fist".to_string(), lemma : "hand over fist".to_string(), pos : vec![PartOfSpeech::ADV],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry { 
+                components : vec![component("hand"), component("over"), component("fist")],
+                ..OntoLexEntry::default() } },
+        EntryContent::OntoLex { id : "handbag".to_string(), lemma : "handbag".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry { 
+                subterms : vec![component("Hand")],
+                ..OntoLexEntry::default() } },
+        EntryContent::OntoLex { id : "handy".to_string(), lemma : "handy man".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry::default() },
+        EntryContent::OntoLex { id : "second-hand".to_string(), lemma : "second hand".to_string(), pos : vec![PartOfSpeech::ADJ],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry::default() }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let hand = state.lookup_mwe("dict1", "hand", None, None);
+    let paged = state.lookup_mwe("dict1", "HAND", Some(1), Some(1));
+    let fist = state.lookup_mwe("dict1", "fist", None, None);
+    let missing = state.lookup_mwe("dict2", "hand", None, None);
+    let json = state.entry_json("dict1", "hand-over-fist");
+    state.delete("dict1").unwrap();
+    fs::remove_file("test-tmp12.db").unwrap();
+    assert_eq!(hand.unwrap().iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["hand-over-fist", "handbag", "second-hand"]);
+    assert_eq!(paged.unwrap()[0].id, "handbag");
+    assert_eq!(fist.unwrap()[0].id, "hand-over-fist");
+    assert!(missing.is_err());
+    assert_eq!(json.unwrap().components.len(), 3);
+}
+
+#[test]
+fn test_frequency() {
+    let state = RusqliteState::new("test-tmp13.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let frequency = |count : u64| vec![Frequency { count : Some(count), label : None, corpus : None }];
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::OntoLex { id : "bank-1".to_string(), lemma : "bank".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry { frequencies : frequency(120), ..OntoLexEntry::default() } },
+        EntryContent::OntoLex { id : "bank-2".to_string(), lemma : "bank".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry::default() },
+        EntryContent::OntoLex { id : "bank-3".to_string(), lemma : "bank".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry { 
+                senses : vec![Sense { id : None, definition : None, reference : None, usage : None, examples : Vec::new(),
+                    frequencies : frequency(4500), 
+                    attestations : vec![Attestation { text : Some("On the bank".to_string()), citation : None, locus : None }] }],
+                ..OntoLexEntry::default() } }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let sorted = FrequencyFilter { min : None, sort : true };
+    let list = state.list("dict1", None, None, &sorted);
+    let paged = state.list("dict1", Some(1), Some(1), &sorted);
+    let frequent = state.lookup("dict1", "bank", &LookupQuery { frequency : FrequencyFilter { min : Some(1000), sort : false }, ..LookupQuery::default() });
+    let lookup = state.lookup("dict1", "bank", &LookupQuery { frequency : sorted.clone(), ..LookupQuery::default() });
+    let json = state.entry_json("dict1", "bank-3");
+    state.delete("dict1").unwrap();
+    fs::remove_file("test-tmp13.db").unwrap();
+    let list = list.unwrap();
+    assert_eq!(list.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["bank-3", "bank-1", "bank-2"]);
+    assert_eq!(list[0].frequency, Some(4500));
+    assert_eq!(list[2].frequency, None);
+    assert_eq!(paged.unwrap()[0].id, "bank-1");
+    assert_eq!(frequent.unwrap().iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["bank-3"]);
+    assert_eq!(lookup.unwrap()[1].id, "bank-1");
+    assert_eq!(json.unwrap().senses[0].attestations.len(), 1);
+}
+
+#[test]
+fn test_paradigm() {
+    let state = RusqliteState::new("test-tmp14.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let mut plural = std::collections::BTreeMap::new();
+    plural.insert("number".to_string(), "plural".to_string());
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::OntoLex { id : "city".to_string(), lemma : "city".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : vec!["cities".to_string()], content : "".to_string(), entry : OntoLexEntry { 
+                pattern : Some("noun".to_string()),
+                forms : vec![ParadigmForm { written_rep : "city".to_string(), features : std::collections::BTreeMap::new(), rule : None },
+                    ParadigmForm { written_rep : "cities".to_string(), features : plural, rule : Some("plural".to_string()) }],
+                ..OntoLexEntry::default() } }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let paradigm = state.paradigm("dict1", "city");
+    let missing = state.paradigm("dict1", "town");
+    let lookup = state.lookup("dict1", "cities", &LookupQuery { inflected : true, features : vec![("number".to_string(), "plural".to_string())], ..LookupQuery::default() });
+    state.delete("dict1").unwrap();
+    fs::remove_file("test-tmp14.db").unwrap();
+    let paradigm = paradigm.unwrap();
+    assert_eq!(paradigm.pattern, Some("noun".to_string()));
+    assert_eq!(paradigm.forms[1].written_rep, "cities");
+    assert!(missing.is_err());
+    assert_eq!(lookup.unwrap().len(), 1);
+}
+
+#[test]
+fn test_tagset() {
+    let state = RusqliteState::new("test-tmp15.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let tag = |tag : &str, pos : Option<PartOfSpeech>| vec![SourceTag { tag : tag.to_string(), 
+        mapping : TagMapping { pos, lexinfo : None, features : None } }];
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        EntryContent::Tei { id : "cat".to_string(), lemma : "cat".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { tags : tag("n", Some(PartOfSpeech::NOUN)), ..TeiEntry::default() } },
+        EntryContent::Tei { id : "dog".to_string(), lemma : "dog".to_string(), pos : vec![PartOfSpeech::NOUN],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { tags : tag("n", Some(PartOfSpeech::NOUN)), ..TeiEntry::default() } },
+        EntryContent::Tei { id : "fast".to_string(), lemma : "fast".to_string(), pos : vec![PartOfSpeech::X],
+            variants : Vec::new(), content : "<entry/>".to_string(), entry : TeiEntry { tags : tag("avv.", None), ..TeiEntry::default() } }]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let tags = state.tagset("dict1");
+    let missing = state.tagset("dict2");
+    state.delete("dict1").unwrap();
+    fs::remove_file("test-tmp15.db").unwrap();
+    let tags = tags.unwrap();
+    assert_eq!(tags.iter().map(|t| (t.tag.as_str(), t.entries)).collect::<Vec<_>>(), vec![("avv.", 1), ("n", 2)]);
+    assert_eq!(tags[0].mapping.pos, None);
+    assert!(missing.is_err());
+}
+
+#[test]
+fn test_homographs() {
+    let state = RusqliteState::new("test-tmp16.db");
+    let mut dictionaries = HashMap::new();
+    dictionaries.insert("dict1".to_string(),
+        Dictionary::new(
+            Release::PUBLIC,
+            "en".to_string(),
+            vec!["en".to_string()],
+            vec![Genre::gen],
+            "http://license.url/".to_string(),
+            Vec::new(),
+            Vec::new()
+        ));
+    let entry = |id : &str, pos : PartOfSpeech, homograph : Option<u32>| EntryContent::OntoLex { id : id.to_string(), lemma : "bank".to_string(),
+        pos : vec![pos], variants : Vec::new(), content : "".to_string(), entry : OntoLexEntry { homograph, ..OntoLexEntry::default() } };
+    let mut entries = HashMap::new();
+    entries.insert("dict1".to_string(), vec![
+        entry("bank1", PartOfSpeech::NOUN, None),
+        entry("bank2", PartOfSpeech::VERB, None),
+        entry("bank3", PartOfSpeech::NOUN, Some(1)),
+        entry("bank4", PartOfSpeech::NOUN, None)]);
+    state.load(Release::PUBLIC, dictionaries, entries).unwrap();
+    let all = state.lookup("dict1", "bank", &LookupQuery::default());
+    let second = state.lookup("dict1", "bank", &LookupQuery { homograph : Some(2), ..LookupQuery::default() });
+    let json = state.entry_json("dict1", "bank2");
+    state.delete("dict1").unwrap();
+    fs::remove_file("test-tmp16.db").unwrap();
+    let mut all : Vec<(String, Option<u32>)> = all.unwrap().into_iter().map(|e| (e.id, e.homograph)).collect();
+    all.sort();
+    assert_eq!(all, vec![("bank1".to_string(), Some(2)), ("bank2".to_string(), Some(4)),
+        ("bank3".to_string(), Some(1)), ("bank4".to_string(), Some(3))]);
+    assert_eq!(second.unwrap().iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["bank1"]);
+    assert_eq!(json.unwrap().homograph, Some(4));
+}
@@ -1,258 +1,1483 @@
 use std::io::Read;
-use crate::model::{Agent, Release, Genre, Format, Entry, Dictionary, PartOfSpeech, EntryContent};
+use crate::model::{Agent, Release, Genre, ParseOptions, Format, Entry, Dictionary, PartOfSpeech, EntryContent, Etymology, Etymon, Equivalent, Frequency, SourceTag, target_languages};
 use crate::BackendImpl;
 use crate::config::Config;
+use crate::ipa;
+use crate::ud;
+use crate::ids::IdGenerator;
+use crate::media::Media;
 
 use xml::reader::{EventReader, XmlEvent};
+use xml::common::{Position, TextPosition};
 use xml::name::OwnedName;
 use xml::attribute::OwnedAttribute;
-use xml::namespace::Namespace;
-use xml::escape::escape_str_attribute;
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, BTreeMap};
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-pub fn parse<R : Read,F>(input : R, id : &str, release : Release,
-                   genre : Vec<Genre>, config : &Config, foo : F) -> BackendImpl 
+const XINCLUDE : &str = "http://www.w3.org/2001/XInclude";
+
+/// Parse a TEI document, collecting the problems found in `report`. If 
+/// `strict` is set and there are any errors the data is not loaded and 
+/// `None` is returned. XIncludes are resolved against `base`
+pub fn parse_with_report<'a, R : Read + 'a,F>(input : R, base : Option<&Path>,
+                   options : ParseOptions, report : &mut ValidationReport, foo : F) -> Option<BackendImpl> 
+    where F : FnOnce(Release, HashMap<String,Dictionary>, HashMap<String, Vec<EntryContent>>) -> BackendImpl {
+    parse_inputs(vec![Input::Reader(Box::new(input), base.map(|b| b.to_path_buf()))],
+        options, report, foo)
+}
+
+/// Parse a dictionary split over several TEI files as a single dictionary. 
+/// The metadata is taken from the header of the first file, and files that 
+/// have already been read by an XInclude are skipped
+pub fn parse_files<F>(files : &[PathBuf], options : ParseOptions,
+                   report : &mut ValidationReport, foo : F) -> Option<BackendImpl> 
+    where F : FnOnce(Release, HashMap<String,Dictionary>, HashMap<String, Vec<EntryContent>>) -> BackendImpl {
+    parse_inputs(files.iter().map(|f| Input::File(f.clone())).collect(),
+        options, report, foo)
+}
+
+/// A document to be read
+enum Input<'a> {
+    Reader(Box<dyn Read + 'a>, Option<PathBuf>),
+    File(PathBuf)
+}
+
+/// A document being read, with the directory its XIncludes are relative to
+struct Part<'a> {
+    reader : EventReader<Box<dyn Read + 'a>>,
+    file : Option<String>,
+    dir : Option<PathBuf>
+}
+
+impl<'a> Part<'a> {
+    fn open(path : &Path, report : &mut ValidationReport) -> Option<Part<'a>> {
+        match File::open(path) {
+            Ok(f) => Some(Part {
+                reader : EventReader::new(Box::new(f)),
+                file : Some(path.display().to_string()),
+                dir : path.parent().map(|p| p.to_path_buf())
+            }),
+            Err(e) => {
+                report.add(Severity::Error, None, TextPosition::new(), "file",
+                    format!("Could not open {}: {}", path.display(), e));
+                None
+            }
+        }
+    }
+}
+
+fn parse_inputs<'a, F>(inputs : Vec<Input<'a>>, options : ParseOptions,
+                   report : &mut ValidationReport, foo : F) -> Option<BackendImpl> 
     where F : FnOnce(Release, HashMap<String,Dictionary>, HashMap<String, Vec<EntryContent>>) -> BackendImpl {
-    let parser = EventReader::new(input);
-
-    let mut target_language = Vec::new();
-    let mut licence = None;
-    let mut creators = Vec::new();
-    let mut publishers = Vec::new();
-    let mut creator = Agent::new();
-    let mut publisher = Agent::new();
-    
-    let mut state = State::Empty;
-
-    let mut lemma = String::new();
-    let mut variants = Vec::new();
-    let mut variant = String::new();
-    let mut entry_id = None;
-    let mut part_of_speech = Vec::new();
-    let mut content = String::new();
-    let mut language = None;
-
-    let mut pos_string = String::new();
+    let ParseOptions { id, release, genre, config, strict } = options;
+    let mut inputs = inputs.into_iter();
+    // The documents being read, the innermost XInclude last
+    let mut parts : Vec<Part<'a>> = Vec::new();
+    let mut read_files = HashSet::new();
+    // Only the first teiHeader is read, the depth in any other is tracked to skip it
+    let mut header_read = false;
+    let mut skip_depth = 0;
+
+    let mut header = HeaderParser::new();
+
+    // The entries currently open, innermost last
+    let mut stack : Vec<EntryBuilder> = Vec::new();
+    // The open superEntries, with the indexes of their entries
+    let mut super_entries : Vec<Vec<usize>> = Vec::new();
 
     let mut entries = Vec::new();
+    let mut entry_ids = HashSet::new();
     let mut anon_count = 0u32;
+    // The entries without an id that are given one by the id strategy, with
+    // where they are
+    let mut anonymous = Vec::new();
+    let sequential = IdGenerator::new(config, HashSet::new()).is_sequential();
+    // An entry without an id is only an error if its id depends on the
+    // order of the entries
+    let missing_id = if sequential {
+        Severity::Error
+    } else {
+        Severity::Warning
+    };
 
-    for e in parser {
+    loop {
+        if parts.is_empty() {
+            match inputs.next() {
+                Some(Input::Reader(r, dir)) => {
+                    parts.push(Part { reader : EventReader::new(r), file : None, dir });
+                },
+                Some(Input::File(path)) => {
+                    if read_files.insert(path.canonicalize().unwrap_or_else(|_| path.clone())) {
+                        parts.extend(Part::open(&path, report));
+                    }
+                },
+                None => break
+            }
+            report.file = parts.last().and_then(|p| p.file.clone());
+            continue;
+        }
+        let e = parts.last_mut().unwrap().reader.next();
+        let pos = parts.last().unwrap().reader.position();
         match e { 
-            Ok(XmlEvent::StartElement { name, attributes, namespace }) => {
-                if name.local_name == "author" {
-                    state = State::Author;
-                } else if name.local_name == "publisher" {
-                    state = State::Publisher;
-                } else if name.local_name == "licence" {
-                    match attributes.iter().find(|x| x.name.local_name == "target") {
-                        Some(attr) => {
-                            licence = Some(attr.value.to_string());
+            Ok(XmlEvent::StartElement { .. }) if skip_depth > 0 => {
+                skip_depth += 1;
+            },
+            Ok(XmlEvent::EndElement { .. }) if skip_depth > 0 => {
+                skip_depth -= 1;
+            },
+            Ok(XmlEvent::Characters(_)) | Ok(XmlEvent::Whitespace(_)) if skip_depth > 0 => {},
+            Ok(XmlEvent::StartElement { ref name, .. }) if name.local_name == "teiHeader" && header_read => {
+                skip_depth = 1;
+            },
+            Ok(XmlEvent::StartElement { ref name, ref attributes, .. }) if name.namespace_ref() == Some(XINCLUDE) => {
+                if name.local_name == "include" {
+                    match attributes.iter().find(|x| x.name.local_name == "href") {
+                        Some(href) => {
+                            let path = match parts.last().unwrap().dir {
+                                Some(ref dir) => dir.join(&href.value),
+                                None => PathBuf::from(&href.value)
+                            };
+                            if read_files.insert(path.canonicalize().unwrap_or_else(|_| path.clone())) {
+                                if let Some(part) = Part::open(&path, report) {
+                                    report.file = part.file.clone();
+                                    parts.push(part);
+                                }
+                            }
                         },
                         None => {
-                            eprintln!("<licence> without target");
+                            report.add(Severity::Error, None, pos, "xinclude", 
+                                "<xi:include> without href".to_string());
                         }
                     }
-                } else if name.local_name == "entry" {
-                    state = State::Entry;
-                    match attributes.iter().find(|x| x.name.local_name == "lang") {
-                        Some(attr) => {
-                            language = Some(attr.value.to_string());
+                }
+                // the content of an xi:fallback is not used
+                if name.local_name == "fallback" {
+                    skip_depth = 1;
+                }
+            },
+            Ok(XmlEvent::EndElement { ref name }) if name.namespace_ref() == Some(XINCLUDE) => {},
+            Ok(XmlEvent::StartElement { name, attributes, .. }) => {
+                if name.local_name == "entry" || ((name.local_name == "re" || name.local_name == "hom") && !stack.is_empty()) {
+                    for b in stack.iter_mut() {
+                        extend_content_tag(&mut b.content, &name, &attributes);
+                    }
+                    let attr = |n : &str| attributes.iter().find(|x| x.name.local_name == n).map(|x| x.value.to_string());
+                    let id = match (attr("id"), stack.last_mut()) {
+                        (Some(id), _) => id,
+                        (None, Some(parent)) => {
+                            // Numbered within the parent, unless the strategy
+                            // gives ids that do not depend on the order
+                            parent.nested += 1;
+                            let id = format!("{}_{}", parent.id, parent.nested);
+                            if !sequential {
+                                anonymous.push((id.clone(), pos, report.file.clone()));
+                            }
+                            id
                         },
-                        None => {}
+                        (None, None) => {
+                            anon_count += 1;
+                            let id = format!("unidentified_entry_{}", anon_count);
+                            report.add(missing_id.clone(), Some(&id), pos, "entry-id",
+                                "Entry has no xml:id".to_string());
+                            anonymous.push((id.clone(), pos, report.file.clone()));
+                            id
+                        }
                     };
-                    match attributes.iter().find(|x| x.name.local_name == "id") {
-                        Some(attr) => {
-                            entry_id = Some(attr.value.to_string());
-                        },
-                        None => {}
+                    if !entry_ids.insert(id.clone()) {
+                        report.add(Severity::Error, Some(&id), pos, "entry-id-unique",
+                            format!("Duplicate entry id: {}", id));
+                    }
+                    let relation = match name.local_name.as_str() {
+                        "hom" => "homograph".to_string(),
+                        "re" => attr("type").unwrap_or_else(|| "related".to_string()),
+                        _ => attr("type").unwrap_or_else(|| "nested".to_string())
                     };
-                    content.clear();
-                    extend_content_tag(&mut content, name, attributes, namespace);
-                } else if state == State::Entry || state == State::Lemma {
-                    if name.local_name == "form" &&
-                        attributes.iter().any(|x| x.name.local_name == "type" &&
-                                                  x.value == "lemma") {
-                        state = State::Lemma
+                    let mut builder = EntryBuilder::new(&name.local_name, id,
+                        attr("lang").or_else(|| stack.last().and_then(|p| p.language.clone())), pos);
+                    if let Some(parent) = stack.last() {
+                        builder.relations.parent = Some(RelatedEntry { id : parent.id.clone(), relation });
+                        // A <hom> is a homograph of the lemma of its entry
+                        if name.local_name == "hom" {
+                            builder.lemma = parent.forms.lemma();
+                        }
                     }
-                    if name.local_name == "form" &&
-                        attributes.iter().any(|x| x.name.local_name == "type" &&
-                                                  x.value == "variant") {
-                        variant.clear();
-                        state = State::Variant
+                    if let Some(n) = attr("n") {
+                        match n.trim().parse::<u32>() {
+                            Ok(n) => builder.homograph = Some(n),
+                            Err(_) => report.add(Severity::Warning, Some(&builder.id), pos, "homograph-number",
+                                format!("Homograph number is not a number: {}", n))
+                        }
+                    }
+                    extend_content_tag(&mut builder.content, &name, &attributes);
+                    stack.push(builder);
+                } else if !stack.is_empty() {
+                    for b in stack.iter_mut() {
+                        extend_content_tag(&mut b.content, &name, &attributes);
+                    }
+                    let top = stack.last_mut().unwrap();
+                    top.senses.start(&name, &attributes);
+                    top.forms.start(&name, &attributes);
+                    top.etymology.start(&name, &attributes);
+                    // Frequencies in senses are read as usage labels
+                    if name.local_name == "usg" && !top.senses.in_sense() &&
+                        attributes.iter().any(|x| x.name.local_name == "type" && x.value == "frequency") {
+                        top.frequency = Some(String::new());
+                    }
+                    if name.local_name == "media" || name.local_name == "graphic" {
+                        let attr = |n : &str| attributes.iter().find(|x| x.name.local_name == n).map(|x| x.value.to_string());
+                        if let Some(url) = attr("url") {
+                            let mut media = Media::new(&url, attr("mimeType"));
+                            if let Err(msg) = media.resolve_file(parts.last().unwrap().dir.as_deref(),
+                                    config.media_root.as_ref().map(Path::new)) {
+                                report.add(Severity::Warning, Some(&top.id), pos, "media", msg);
+                            } else {
+                                top.media.push(media);
+                            }
+                        }
                     }
                     if name.local_name == "pos" || name.local_name == "gram"
                         && attributes.iter().any(|x| x.name.local_name == "type" &&
                                                  x.value == "pos") {
-                        state = State::Pos;
-                        part_of_speech.clear();
+                        top.in_pos = true;
+                        top.part_of_speech.clear();
+                        top.tags.clear();
                         if let Some(norm) = attributes.iter().find(|x| x.name.local_name == "norm") {
                             if let Ok(p) = PartOfSpeech::from_str(&norm.value) {
-                                part_of_speech = vec![p];
+                                top.part_of_speech = vec![p];
                             } else {
-                                eprintln!("Bad normalization: {}", norm.value);
+                                report.add(Severity::Warning, Some(&top.id), pos, "pos-norm",
+                                    format!("Part of speech normalization is not a UD category: {}", norm.value));
                             }
                         } 
-                        pos_string = String::new();
+                        top.pos_string = String::new();
                     }
-
-                    match attributes.iter().find(|x| x.name.local_name == "lang") {
-                        Some(attr) => { target_language.push(attr.value.to_string()); },
-                        None => {}
-                    };
-                    extend_content_tag(&mut content, name, attributes, namespace);
+                } else if name.local_name == "superEntry" {
+                    super_entries.push(Vec::new());
+                } else {
+                    if name.local_name == "licence" && 
+                        !attributes.iter().any(|x| x.name.local_name == "target") {
+                        report.add(Severity::Error, None, pos, "licence-target",
+                            "<licence> without target".to_string());
+                    }
+                    header.start(&name, attributes);
                 }
             },
             Ok(XmlEvent::EndElement { name }) => {
-                if name.local_name == "author" {
-                    creators.push(creator);
-                    creator = Agent::new();
-                    state = State::Empty;
-                } else if name.local_name == "publisher" {
-                    publishers.push(publisher);
-                    publisher = Agent::new();
-                    state = State::Empty;
-                } else if name.local_name == "entry" {
-                    extend_content_endtag(&mut content, name);
-                    let lang = match language {
-                        Some(ref language) => language,
-                        None => {
-                            eprintln!("No language on entry");
-                            "und"
+                if !stack.is_empty() {
+                    for b in stack.iter_mut() {
+                        extend_content_endtag(&mut b.content, &name);
+                    }
+                    if stack.last().unwrap().element == name.local_name {
+                        let builder = stack.pop().unwrap();
+                        if let Some(parent) = stack.last_mut() {
+                            parent.relations.children.push(RelatedEntry {
+                                id : builder.id.clone(),
+                                relation : builder.relations.parent.as_ref().unwrap().relation.clone()
+                            });
+                        } else if let Some(members) = super_entries.last_mut() {
+                            members.push(entries.len());
                         }
-                    };
-                    let id = match entry_id {
-                        Some(ref entry_id) => entry_id.to_string(),
-                        None => {
-                            eprintln!("No id on entry");
-                            anon_count += 1;
-                            format!("unidentified_entry_{}", anon_count)
+                        entries.push(builder.build(&release, report));
+                    } else {
+                        let top = stack.last_mut().unwrap();
+                        top.senses.end(&name);
+                        top.forms.end(&name);
+                        top.etymology.end(&name);
+                        if name.local_name == "usg" {
+                            if let Some(text) = top.frequency.take() {
+                                top.frequencies.push(Frequency::from_text(&normalize_space(&text), None));
+                            }
+                        }
+                        if (name.local_name == "gram"  || name.local_name == "pos") && top.in_pos {
+                            let tag = top.pos_string.trim().to_string();
+                            let mut mapping = config.map_tag(&tag).unwrap_or_default();
+                            if top.part_of_speech.is_empty() { // we did not get a pos from the normalization
+                                let p = match mapping.pos {
+                                    Some(ref p) => p.clone(),
+                                    None => {
+                                        report.add(Severity::Warning, Some(&top.id), pos, "pos-mapping",
+                                            format!("Part of speech could not be mapped: {}", tag));
+                                        PartOfSpeech::X
+                                    }
+                                };
+                                top.part_of_speech.push(p);
+                            } else {
+                                mapping.pos = top.part_of_speech.first().cloned();
+                            }
+                            if !tag.is_empty() {
+                                top.tags.push(SourceTag { tag, mapping });
+                            }
+                            top.in_pos = false;
                         }
-                    };
-                    if part_of_speech.is_empty() {
-                        part_of_speech.push(PartOfSpeech::X)
                     }
-                    if lemma == "" {
-                        lemma.push_str("<Empty lemma>");
+                } else if name.local_name == "superEntry" {
+                    if let Some(members) = super_entries.pop() {
+                        let ids : Vec<String> = members.iter().map(|i| entries[*i].entry.id.clone()).collect();
+                        for i in members.iter() {
+                            let entry : &mut ReadEntry = &mut entries[*i];
+                            entry.tei.relations.homographs = ids.iter().filter(|id| **id != entry.entry.id).cloned().collect();
+                        }
                     }
-                    entries.push((
-                            lang.to_string(),
-                            Entry::new(
-                                release.clone(), 
-                                lemma.to_string(),
-                                id,
-                                part_of_speech.clone(),
-                                vec![Format::tei]),
-                            variants.clone(),
-                            content.clone()));
-                    lemma = String::new();
-                    entry_id = None;
-                    part_of_speech = Vec::new();
-                    content = String::new();
-                    language = None;
-                    variants.clear();
-                } else if name.local_name == "form" && state == State::Lemma {
-                    extend_content_endtag(&mut content, name);
-                    state = State::Entry;
-                } else if name.local_name == "form" && state == State::Lemma {
-                    extend_content_endtag(&mut content, name);
-                    variants.push(variant.clone());
-                    state = State::Entry;
-                } else if (name.local_name == "gram"  || name.local_name == "pos") 
-                    && state == State::Pos {
-                    extend_content_endtag(&mut content, name);
-                    if part_of_speech.is_empty() { // we did not get a pos from the normalization
-                        part_of_speech.push(convert_pos(&pos_string.trim(), config));
+                } else {
+                    if name.local_name == "teiHeader" {
+                        header_read = true;
                     }
-                    state = State::Entry;
-                } else if state == State::Entry || state == State::Lemma || state == State::Pos {
-                    extend_content_endtag(&mut content, name);
+                    header.end();
                 }
             },
             Ok(XmlEvent::Characters(s)) => {
-                if state == State::Author {
-                    creator.name.push_str(&s);
-                } else if state == State::Publisher {
-                    publisher.name.push_str(&s);
-                } else if state == State::Entry {
-                    content.push_str(&s);
-                } else if state == State::Lemma {
-                    content.push_str(&s);
-                    lemma.push_str(&s);
-                } else if state == State::Variant {
-                    content.push_str(&s);
-                    variant.push_str(&s);
-                } else if state == State::Pos {
-                    content.push_str(&s);
-                    pos_string.push_str(&s);
+                if let Some(top) = stack.last_mut() {
+                    if top.in_pos {
+                        top.pos_string.push_str(&s);
+                    } else {
+                        top.senses.characters(&s);
+                        top.forms.characters(&s);
+                        top.etymology.characters(&s);
+                        if let Some(ref mut text) = top.frequency {
+                            text.push_str(&s);
+                        }
+                    }
+                    for b in stack.iter_mut() {
+                        b.content.push_str(&s);
+                    }
+                } else {
+                    header.characters(&s);
                 }
             },
             Ok(XmlEvent::Whitespace(s)) => {
-                content.push_str(&s);
+                if let Some(top) = stack.last_mut() {
+                    top.senses.characters(&s);
+                    top.forms.characters(&s);
+                    top.etymology.characters(&s);
+                } else {
+                    header.characters(&s);
+                }
+                for b in stack.iter_mut() {
+                    b.content.push_str(&s);
+                }
+            },
+            Ok(XmlEvent::EndDocument) => {
+                parts.pop();
+                report.file = parts.last().and_then(|p| p.file.clone());
             },
             Err(e) => {
-                eprintln!("Failed to load TEI file: {:?}", e);
+                report.add(Severity::Error, stack.last().map(|b| b.id.as_str()), e.position(), "xml",
+                    e.msg().to_string());
+                break;
             },
             Ok(_) => {}
         }
     }
 
+    assign_ids(&mut entries, &anonymous, config, report);
+
+    if header.licence.is_none() {
+        report.add(Severity::Error, None, TextPosition::new(), "licence",
+            "No <licence> given".to_string());
+    }
+    if strict && report.errors() > 0 {
+        return None;
+    }
+
     let mut dictionaries = HashMap::new();
     let mut dict_entries = HashMap::new();
-    let src_langs = entries.iter().map(|x| x.0.clone()).collect::<HashSet<String>>();
+    let src_langs = entries.iter().map(|x| x.language.clone()).collect::<HashSet<String>>();
+    // The target languages are those the entries are translated into
+    let target_language = |src_lang : &str| target_languages(src_lang,
+        entries.iter().filter(|x| x.language == src_lang)
+            .flat_map(|x| x.tei.translations().into_iter().map(|t| t.language)));
     if src_langs.len() > 1 {
         for src_lang in src_langs {
             let dict_id = format!("{}-{}", id, src_lang);
            dictionaries.insert(dict_id.clone(),
-            Dictionary::new(
-                release.clone(), src_lang.to_string(),
-                target_language.clone(),
-                genre.clone(), licence.clone().unwrap_or("unknown".to_string()),
-                creators.clone(), publishers.clone()));
+            header.dictionary(release.clone(), src_lang.to_string(),
+                target_language(&src_lang), genre.clone()));
            build_entries(&dict_id, &mut dict_entries, &entries, &src_lang);
         }
     } else if src_langs.len() == 1 {
         let src_lang = src_langs.iter().next().unwrap();
         dictionaries.insert(id.to_string(),
-        Dictionary::new(
-            release.clone(), src_lang.to_string(),
-            target_language,
-            genre.clone(), licence.unwrap_or("unknown".to_string()),
-            creators.clone(), publishers.clone()));
+            header.dictionary(release.clone(), src_lang.to_string(),
+                target_language(src_lang), genre.clone()));
        build_entries(&id, &mut dict_entries, &entries, &src_lang);
     }
  
-    foo(release, dictionaries, dict_entries)
+    Some(foo(release, dictionaries, dict_entries))
+}
+
+/// Replace the provisional ids given to entries without an `xml:id` by the
+/// ids of the configured strategy
+fn assign_ids(entries : &mut [ReadEntry],
+    anonymous : &[(String, TextPosition, Option<String>)], config : &Config, report : &mut ValidationReport) {
+    if anonymous.is_empty() || IdGenerator::new(config, HashSet::new()).is_sequential() {
+        return;
+    }
+    let provisional : HashSet<&str> = anonymous.iter().map(|a| a.0.as_str()).collect();
+    let used = entries.iter().map(|e| e.entry.id.clone())
+        .filter(|id| !provisional.contains(id.as_str()))
+        .collect();
+    let mut ids = IdGenerator::new(config, used);
+    let mut renamed = HashMap::new();
+    let file = report.file.take();
+    for (old, pos, f) in anonymous.iter() {
+        if let Some(e) = entries.iter().find(|e| e.entry.id == *old) {
+            let (id, collision) = ids.generate(&e.entry.lemma, e.entry.part_of_speech.first(), e.tei.homograph,
+                &detab_content(&e.content));
+            if let Some(collision) = collision {
+                report.file = f.clone();
+                report.add(Severity::Warning, Some(&id), *pos, "entry-id-collision",
+                    format!("Generated id {} is already used", collision));
+            }
+            renamed.insert(old.clone(), id);
+        }
+    }
+    report.file = file;
+    let rename = |id : &str| renamed.get(id).cloned().unwrap_or_else(|| id.to_string());
+    for e in entries.iter_mut() {
+        e.entry.id = rename(&e.entry.id);
+        let relations = &mut e.tei.relations;
+        if let Some(ref mut parent) = relations.parent {
+            parent.id = rename(&parent.id);
+        }
+        for child in relations.children.iter_mut() {
+            child.id = rename(&child.id);
+        }
+        for homograph in relations.homographs.iter_mut() {
+            *homograph = rename(homograph);
+        }
+    }
+    for d in report.diagnostics.iter_mut() {
+        if let Some(ref mut entry) = d.entry {
+            *entry = rename(entry);
+        }
+    }
 }
 
 fn build_entries(dict_id : &str,
     dict_entries : &mut HashMap<String, Vec<EntryContent>>,
-    entries : &Vec<(String, Entry, Vec<String>, String)>,
+    entries : &[ReadEntry],
     lang : &str) {
 
     for entry in entries.iter() {
-        if entry.0 == lang {
+        if entry.language == lang {
 
             dict_entries.entry(dict_id.to_string())
                 .or_insert_with(|| Vec::new())
-                .push(EntryContent::Tei(
-                        entry.1.id.to_string(),
-                        entry.1.lemma.to_string(),
-                        entry.1.part_of_speech.clone(),
-                        entry.2.clone(),
-                        detab_content(&entry.3)));
+                .push(EntryContent::Tei {
+                    id : entry.entry.id.to_string(),
+                    lemma : entry.entry.lemma.to_string(),
+                    pos : entry.entry.part_of_speech.clone(),
+                    variants : entry.variants.clone(),
+                    content : detab_content(&entry.content),
+                    entry : entry.tei.clone()
+                });
+        }
+    }
+
+}
+
+/// How serious a problem in a TEI document is. Errors cause the document
+/// to be rejected when loading strictly
+#[derive(Clone,Debug,Serialize,PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning")
+        }
+    }
+}
+
+/// A problem found in a TEI document
+#[derive(Clone,Debug,Serialize,PartialEq)]
+pub struct Diagnostic {
+    pub severity : Severity,
+    /// The file the problem occurs in, if the dictionary is in several files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file : Option<String>,
+    /// The identifier of the entry the problem occurs in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry : Option<String>,
+    pub line : u64,
+    pub column : u64,
+    /// The rule that is broken, e.g., `entry-id`
+    pub rule : &'static str,
+    pub message : String
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref file) = self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}: {}[{}]: {}", self.line, self.column, self.severity, self.rule, self.message)?;
+        if let Some(ref entry) = self.entry {
+            write!(f, " (entry {})", entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// The problems found while reading a TEI document (or an OntoLex one)
+#[derive(Clone,Debug,Default,Serialize)]
+pub struct ValidationReport {
+    pub diagnostics : Vec<Diagnostic>,
+    /// The file currently being read
+    #[serde(skip)]
+    file : Option<String>
+}
+
+impl ValidationReport {
+    /// A report on a single file, which the diagnostics are located in
+    pub fn for_file(file : &str) -> ValidationReport {
+        ValidationReport { diagnostics : Vec::new(), file : Some(file.to_string()) }
+    }
+
+    pub(crate) fn add(&mut self, severity : Severity, entry : Option<&str>, 
+           position : TextPosition, rule : &'static str, message : String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            file : self.file.clone(),
+            entry : entry.map(|e| e.to_string()),
+            line : position.row + 1,
+            column : position.column + 1,
+            rule, message
+        });
+    }
+
+    /// The number of errors
+    pub fn errors(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error).count()
+    }
+
+    /// The number of warnings
+    pub fn warnings(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Warning).count()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        for d in self.diagnostics.iter() {
+            writeln!(f, "{}", d)?;
+        }
+        Ok(())
+    }
+}
+
+/// The structured content of a TEI entry
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct TeiEntry {
+    #[serde(default)]
+    pub forms : Vec<TeiForm>,
+    pub senses : Vec<TeiSense>,
+    #[serde(default)]
+    pub relations : TeiRelations,
+    #[serde(default)]
+    pub etymology : Etymology,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub media : Vec<Media>,
+    /// The frequencies given for the entry (`<usg type="frequency">`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub frequencies : Vec<Frequency>,
+    /// The part-of-speech tags of the entry and how they were mapped
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub tags : Vec<SourceTag>,
+    /// The homograph number (the `n` of the `<entry>` or `<hom>`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub homograph : Option<u32>
+}
+
+/// How a TEI entry is related to the entries it is nested in or contains
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct TeiRelations {
+    /// The entry this entry is nested in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent : Option<RelatedEntry>,
+    /// The entries (or `<re>`s and `<hom>`s) nested in this entry
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub children : Vec<RelatedEntry>,
+    /// The other entries of the same `<superEntry>`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub homographs : Vec<String>
+}
+
+/// A link to another entry, with the type of the relation (the `type` of the
+/// nested `<entry>` or `<re>` or else `nested` or `related`, or `homograph`
+/// for a `<hom>`)
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+pub struct RelatedEntry {
+    pub id : String,
+    pub relation : String
+}
+
+/// An entry that has been read, with the language of the dictionary it
+/// belongs to, the forms it can be looked up by and its TEI source
+struct ReadEntry {
+    language : String,
+    entry : Entry,
+    variants : Vec<String>,
+    content : String,
+    tei : TeiEntry
+}
+
+/// An entry that is being read. Entries may be nested, so the parser keeps
+/// a stack of these
+struct EntryBuilder {
+    element : String,
+    id : String,
+    language : Option<String>,
+    part_of_speech : Vec<PartOfSpeech>,
+    pos_string : String,
+    in_pos : bool,
+    content : String,
+    senses : SenseParser,
+    forms : FormParser,
+    etymology : EtymParser,
+    relations : TeiRelations,
+    media : Vec<Media>,
+    frequencies : Vec<Frequency>,
+    tags : Vec<SourceTag>,
+    /// The text of an entry-level `<usg type="frequency">` being read
+    frequency : Option<String>,
+    /// The lemma of the entry a `<hom>` is in
+    lemma : Option<String>,
+    homograph : Option<u32>,
+    nested : u32,
+    position : TextPosition
+}
+
+impl EntryBuilder {
+    fn new(element : &str, id : String, language : Option<String>, position : TextPosition) -> EntryBuilder {
+        EntryBuilder {
+            element : element.to_string(),
+            id, language,
+            part_of_speech : Vec::new(),
+            pos_string : String::new(),
+            in_pos : false,
+            content : String::new(),
+            senses : SenseParser::new(),
+            forms : FormParser::new(),
+            etymology : EtymParser::new(),
+            relations : TeiRelations::default(),
+            media : Vec::new(),
+            frequencies : Vec::new(),
+            tags : Vec::new(),
+            frequency : None,
+            lemma : None,
+            homograph : None,
+            nested : 0,
+            position
+        }
+    }
+
+    fn build(mut self, release : &Release, report : &mut ValidationReport) -> ReadEntry {
+        let lang = match self.language {
+            Some(language) => language,
+            None => {
+                report.add(Severity::Error, Some(&self.id), self.position, "entry-lang",
+                    "Entry has no xml:lang".to_string());
+                "und".to_string()
+            }
+        };
+        if self.part_of_speech.is_empty() {
+            if self.relations.parent.is_none() {
+                report.add(Severity::Warning, Some(&self.id), self.position, "entry-pos",
+                    "Entry has no part of speech".to_string());
+            }
+            self.part_of_speech.push(PartOfSpeech::X)
+        }
+        let mut forms = self.forms.finish();
+        for f in forms.iter_mut() {
+            SourceTag::add_features(&self.tags, &mut f.features);
+        }
+        // A related entry often gives its form without a type
+        let lemma = match forms.iter().find(|f| f.form_type == "lemma") {
+            Some(f) => f.written_rep.clone(),
+            None if self.element == "re" && !forms.is_empty() => forms[0].written_rep.clone(),
+            None if self.lemma.is_some() => self.lemma.clone().unwrap(),
+            None => {
+                report.add(Severity::Error, Some(&self.id), self.position, "entry-lemma",
+                    "Entry has no form[@type=lemma]".to_string());
+                "<Empty lemma>".to_string()
+            }
+        };
+        let mut variants : Vec<String> = Vec::new();
+        for f in forms.iter() {
+            if f.written_rep != lemma && !variants.contains(&f.written_rep) {
+                variants.push(f.written_rep.clone());
+            }
+        }
+        ReadEntry {
+            language : lang,
+            entry : Entry::new(release.clone(), lemma, self.id, self.part_of_speech, vec![Format::tei]),
+            variants,
+            content : self.content,
+            tei : TeiEntry { forms, senses : self.senses.finish(), relations : self.relations,
+                etymology : self.etymology.finish(), media : self.media, frequencies : self.frequencies,
+                tags : self.tags, homograph : self.homograph }
+        }
+    }
+}
+
+impl TeiEntry {
+    /// Find a sense, or a subsense, by its identifier
+    pub fn find_sense(&self, id : &str) -> Option<&TeiSense> {
+        find_sense(&self.senses, id)
+    }
+
+    /// The identifiers of all senses and subsenses
+    pub fn sense_ids(&self) -> Vec<String> {
+        let mut ids = Vec::new();
+        sense_ids(&self.senses, &mut ids);
+        ids
+    }
+
+    /// The definitions of all senses and subsenses
+    pub fn definitions(&self) -> Vec<String> {
+        let mut definitions = Vec::new();
+        sense_definitions(&self.senses, &mut definitions);
+        definitions
+    }
+
+    /// The translations of all senses and subsenses that give their language
+    pub fn translations(&self) -> Vec<Equivalent> {
+        let mut translations = Vec::new();
+        sense_translations(&self.senses, &mut translations);
+        translations
+    }
+
+    /// The frequencies of all senses and subsenses
+    pub fn sense_frequencies(&self) -> Vec<Frequency> {
+        let mut frequencies = Vec::new();
+        sense_frequencies(&self.senses, &mut frequencies);
+        frequencies
+    }
+}
+
+/// A `<form>` of a TEI entry with the grammatical features from its `<gramGrp>`
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TeiForm {
+    #[serde(rename = "type")]
+    pub form_type : String,
+    pub written_rep : String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[serde(default)]
+    pub features : BTreeMap<String, String>,
+    /// The pronunciations of the form, as normalized IPA
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub pronunciations : Vec<String>
+}
+
+impl TeiForm {
+    /// Check if this form has all of the given features, compared as UD
+    /// features
+    pub fn has_features(&self, features : &[(String, String)]) -> bool {
+        let own = ud::features(&self.features);
+        features.iter().map(|(k, v)| ud::feature(k, v)).all(|(k, v)| own.get(&k) == Some(&v))
+    }
+}
+
+fn find_sense<'a>(senses : &'a [TeiSense], id : &str) -> Option<&'a TeiSense> {
+    for sense in senses.iter() {
+        if sense.id.as_ref().map(|x| x == id).unwrap_or(false) {
+            return Some(sense);
+        }
+        if let Some(s) = find_sense(&sense.senses, id) {
+            return Some(s);
+        }
+    }
+    None
+}
+
+fn sense_ids(senses : &[TeiSense], ids : &mut Vec<String>) {
+    for sense in senses.iter() {
+        if let Some(ref id) = sense.id {
+            ids.push(id.clone());
+        }
+        sense_ids(&sense.senses, ids);
+    }
+}
+
+fn sense_definitions(senses : &[TeiSense], definitions : &mut Vec<String>) {
+    for sense in senses.iter() {
+        definitions.extend(sense.definitions.iter().cloned());
+        sense_definitions(&sense.senses, definitions);
+    }
+}
+
+fn sense_frequencies(senses : &[TeiSense], frequencies : &mut Vec<Frequency>) {
+    for sense in senses.iter() {
+        frequencies.extend(sense.frequencies());
+        sense_frequencies(&sense.senses, frequencies);
+    }
+}
+
+fn sense_translations(senses : &[TeiSense], translations : &mut Vec<Equivalent>) {
+    for sense in senses.iter() {
+        for t in sense.translations.iter() {
+            if let Some(ref language) = t.language {
+                translations.push(Equivalent {
+                    sense : sense.id.clone(),
+                    language : language.clone(),
+                    text : t.text.clone()
+                });
+            }
+        }
+        sense_translations(&sense.senses, translations);
+    }
+}
+
+/// A `<sense>` of a TEI entry
+#[derive(Clone,Debug,Default,Serialize,Deserialize,PartialEq)]
+pub struct TeiSense {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id : Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub definitions : Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub examples : Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub usage : Vec<Usage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub translations : Vec<Translation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub senses : Vec<TeiSense>
+}
+
+impl TeiSense {
+    /// The usage labels of type `frequency`
+    pub fn frequencies(&self) -> Vec<Frequency> {
+        self.usage.iter()
+            .filter(|u| u.usage_type.as_ref().map(|t| t == "frequency").unwrap_or(false))
+            .map(|u| Frequency::from_text(&u.label, None))
+            .collect()
+    }
+}
+
+/// A usage label (`<usg>`)
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+pub struct Usage {
+    #[serde(rename = "type")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage_type : Option<String>,
+    pub label : String
+}
+
+/// A translation (`<cit type="translation">`)
+#[derive(Clone,Debug,Serialize,Deserialize,PartialEq)]
+pub struct Translation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language : Option<String>,
+    pub text : String
+}
+
+// The part of a sense whose text is being read
+#[derive(Debug,PartialEq)]
+enum SenseField {
+    Def,
+    Example,
+    Translation(Option<String>),
+    Usage(Option<String>)
+}
+
+/// Collects the senses of an entry while it is being parsed
+struct SenseParser {
+    stack : Vec<TeiSense>,
+    senses : Vec<TeiSense>,
+    field : Option<SenseField>,
+    text : String,
+    quote : Option<String>,
+    in_quote : bool,
+    cit_depth : u32,
+    etym_depth : u32
+}
+
+impl SenseParser {
+    fn new() -> SenseParser {
+        SenseParser {
+            stack : Vec::new(),
+            senses : Vec::new(),
+            field : None,
+            text : String::new(),
+            quote : None,
+            in_quote : false,
+            cit_depth : 0,
+            etym_depth : 0
+        }
+    }
+
+    fn start(&mut self, name : &OwnedName, attributes : &[OwnedAttribute]) {
+        let attr = |n : &str| attributes.iter().find(|x| x.name.local_name == n).map(|x| x.value.to_string());
+        // The senses and definitions in an etymology are those of other words
+        if name.local_name == "etym" {
+            self.etym_depth += 1;
+        }
+        if self.etym_depth > 0 {
+            return;
+        }
+        if self.field.is_some() {
+            if name.local_name == "cit" {
+                self.cit_depth += 1;
+            } else if self.cit_depth == 0 {
+                if name.local_name == "quote" {
+                    self.in_quote = true;
+                    self.quote.get_or_insert_with(String::new);
+                }
+                // The language of a translation may be given on its quote or form
+                if let Some(SenseField::Translation(ref mut lang)) = self.field {
+                    if lang.is_none() {
+                        *lang = attr("lang");
+                    }
+                }
+            }
+        } else if name.local_name == "sense" {
+            self.stack.push(TeiSense { id : attr("id"), ..TeiSense::default() });
+        } else if !self.stack.is_empty() {
+            let field = match (name.local_name.as_str(), attr("type")) {
+                ("def", _) => Some(SenseField::Def),
+                ("cit", Some(ref t)) if t == "example" => Some(SenseField::Example),
+                ("cit", Some(ref t)) if t == "translation" || t == "translationEquivalent" => 
+                    Some(SenseField::Translation(attr("lang"))),
+                ("usg", t) => Some(SenseField::Usage(t)),
+                _ => None
+            };
+            if field.is_some() {
+                self.field = field;
+                self.text.clear();
+                self.quote = None;
+                self.in_quote = false;
+                self.cit_depth = 0;
+            }
+        }
+    }
+
+    fn end(&mut self, name : &OwnedName) {
+        if self.etym_depth > 0 {
+            if name.local_name == "etym" {
+                self.etym_depth -= 1;
+            }
+            return;
+        }
+        if self.field.is_some() {
+            let done = match (self.field.as_ref().unwrap(), name.local_name.as_str()) {
+                (_, "quote") if self.cit_depth == 0 => { self.in_quote = false; false },
+                (SenseField::Def, "def") => true,
+                (SenseField::Usage(_), "usg") => true,
+                (_, "cit") if self.cit_depth > 0 => { self.cit_depth -= 1; false },
+                (SenseField::Example, "cit") | (SenseField::Translation(_), "cit") => true,
+                _ => false
+            };
+            if done {
+                let text = normalize_space(self.quote.as_ref().unwrap_or(&self.text));
+                let sense = self.stack.last_mut().expect("Field outside sense");
+                match self.field.take().unwrap() {
+                    SenseField::Def => sense.definitions.push(text),
+                    SenseField::Example => sense.examples.push(text),
+                    SenseField::Translation(language) => sense.translations.push(Translation { language, text }),
+                    SenseField::Usage(usage_type) => sense.usage.push(Usage { usage_type, label : text })
+                }
+            }
+        } else if name.local_name == "sense" {
+            if let Some(sense) = self.stack.pop() {
+                match self.stack.last_mut() {
+                    Some(parent) => parent.senses.push(sense),
+                    None => self.senses.push(sense)
+                }
+            }
+        }
+    }
+
+    fn characters(&mut self, s : &str) {
+        if self.field.is_some() {
+            self.text.push_str(s);
+            if self.in_quote {
+                if let Some(ref mut quote) = self.quote {
+                    quote.push_str(s);
+                }
+            }
+        }
+    }
+
+    /// Check if a sense is being read
+    fn in_sense(&self) -> bool {
+        !self.stack.is_empty()
+    }
+
+    /// Return the senses of the entry and reset for the next entry
+    fn finish(&mut self) -> Vec<TeiSense> {
+        while !self.stack.is_empty() {
+            self.end(&OwnedName::local("sense"));
+        }
+        let senses = std::mem::take(&mut self.senses);
+        *self = SenseParser::new();
+        senses
+    }
+}
+
+/// Collects the etymology of an entry while it is being parsed. Each
+/// `cit[@type=etymon]` is an ancestor of the entry, or of the etymon it is 
+/// within, and each `cit[@type=cognate]` is a cognate
+struct EtymParser {
+    // The enclosing etym elements, with their types
+    etyms : Vec<Option<String>>,
+    // The etymons and cognates being read, with whether they are cognates
+    stack : Vec<(Etymon, bool)>,
+    // The enclosing cits, with whether each is an etymon or cognate
+    cits : Vec<bool>,
+    ancestors : Vec<Etymon>,
+    cognates : Vec<Etymon>,
+    field : Option<(String, String)>,
+    depth : u32
+}
+
+impl EtymParser {
+    fn new() -> EtymParser {
+        EtymParser {
+            etyms : Vec::new(),
+            stack : Vec::new(),
+            cits : Vec::new(),
+            ancestors : Vec::new(),
+            cognates : Vec::new(),
+            field : None,
+            depth : 0
+        }
+    }
+
+    fn start(&mut self, name : &OwnedName, attributes : &[OwnedAttribute]) {
+        let attr = |n : &str| attributes.iter().find(|x| x.name.local_name == n).map(|x| x.value.to_string());
+        match (name.local_name.as_str(), attr("type")) {
+            ("etym", t) => self.etyms.push(t),
+            ("cit", Some(ref t)) if t == "etymon" || t == "cognate" => {
+                let mut etymon = Etymon { language : attr("lang"), ..Etymon::default() };
+                if t == "etymon" {
+                    etymon.etym_type = self.etyms.last().cloned().unwrap_or(None);
+                }
+                self.stack.push((etymon, t == "cognate"));
+                self.cits.push(true);
+            },
+            ("cit", _) => self.cits.push(false),
+            ("lang", _) | ("orth", _) | ("pron", _) | ("def", _) | ("gloss", _) if !self.stack.is_empty() && self.field.is_none() => {
+                if name.local_name == "orth" {
+                    let etymon = &mut self.stack.last_mut().unwrap().0;
+                    if etymon.language.is_none() {
+                        etymon.language = attr("lang");
+                    }
+                }
+                self.field = Some((name.local_name.clone(), String::new()));
+                self.depth = 0;
+            },
+            _ => {}
+        }
+        if self.field.is_some() {
+            self.depth += 1;
+        }
+    }
+
+    fn end(&mut self, name : &OwnedName) {
+        if self.field.is_some() {
+            self.depth -= 1;
+            if self.depth == 0 {
+                let (field, text) = self.field.take().unwrap();
+                let text = normalize_space(&text);
+                let etymon = &mut self.stack.last_mut().unwrap().0;
+                if !text.is_empty() {
+                    match field.as_str() {
+                        "lang" => etymon.language_name = Some(text),
+                        "orth" => etymon.forms.push(text),
+                        // A pronunciation is only used if there is no written form
+                        "pron" => if etymon.forms.is_empty() { etymon.forms.push(text) },
+                        _ => etymon.glosses.push(text)
+                    }
+                }
+            }
+            return;
+        }
+        match name.local_name.as_str() {
+            "etym" => { self.etyms.pop(); },
+            "cit" => {
+                if self.cits.pop() == Some(true) {
+                    self.close_cit();
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn close_cit(&mut self) {
+        let (etymon, cognate) = self.stack.pop().unwrap();
+        if cognate {
+            self.cognates.push(etymon);
+        } else if let Some(parent) = self.stack.iter_mut().rev().find(|e| !e.1) {
+            parent.0.ancestors.push(etymon);
+        } else {
+            self.ancestors.push(etymon);
+        }
+    }
+
+    fn characters(&mut self, s : &str) {
+        if let Some((_, ref mut text)) = self.field {
+            text.push_str(s);
+        }
+    }
+
+    /// Return the etymology of the entry and reset for the next entry
+    fn finish(&mut self) -> Etymology {
+        while !self.stack.is_empty() {
+            self.close_cit();
+        }
+        let etym = std::mem::replace(self, EtymParser::new());
+        Etymology::new(etym.ancestors, etym.cognates)
+    }
+}
+
+/// Collects the forms of an entry while it is being parsed. Forms within
+/// citations or etymologies belong to other words and are skipped
+struct FormParser {
+    stack : Vec<(TeiForm, Option<String>, String)>,
+    forms : Vec<TeiForm>,
+    in_orth : bool,
+    gram : Option<(String, Option<String>, String)>,
+    pron : Option<String>,
+    skip_depth : u32
+}
+
+impl FormParser {
+    fn new() -> FormParser {
+        FormParser {
+            stack : Vec::new(),
+            forms : Vec::new(),
+            in_orth : false,
+            gram : None,
+            pron : None,
+            skip_depth : 0
+        }
+    }
+
+    /// The lemma, if its form has been read
+    fn lemma(&self) -> Option<String> {
+        self.forms.iter().find(|f| f.form_type == "lemma").map(|f| f.written_rep.clone())
+    }
+
+    fn start(&mut self, name : &OwnedName, attributes : &[OwnedAttribute]) {
+        let attr = |n : &str| attributes.iter().find(|x| x.name.local_name == n).map(|x| x.value.to_string());
+        if name.local_name == "cit" || name.local_name == "etym" {
+            self.skip_depth += 1;
+            return;
+        }
+        if self.skip_depth > 0 {
+            return;
+        }
+        if name.local_name == "form" {
+            // Nested forms inherit the features of the enclosing form
+            let features = self.stack.last().map(|f| f.0.features.clone()).unwrap_or_default();
+            self.stack.push((TeiForm {
+                form_type : attr("type").unwrap_or_else(|| "variant".to_string()),
+                written_rep : String::new(),
+                features,
+                pronunciations : Vec::new()
+            }, None, String::new()));
+        } else if !self.stack.is_empty() {
+            if name.local_name == "orth" {
+                self.in_orth = true;
+                let top = self.stack.last_mut().unwrap();
+                top.1.get_or_insert_with(String::new);
+            } else if name.local_name == "pron" {
+                // Only IPA is read, which is the default notation
+                if attr("notation").map(|n| n.to_lowercase().contains("ipa")).unwrap_or(true) {
+                    self.pron = Some(String::new());
+                }
+            } else if name.local_name == "gram" {
+                match attr("type") {
+                    Some(ref t) if t == "pos" => {},
+                    Some(t) => self.gram = Some((t, attr("norm"), String::new())),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    fn end(&mut self, name : &OwnedName) {
+        if name.local_name == "cit" || name.local_name == "etym" {
+            self.skip_depth = self.skip_depth.saturating_sub(1);
+            return;
+        }
+        if self.skip_depth > 0 {
+            return;
+        }
+        if name.local_name == "orth" {
+            self.in_orth = false;
+        } else if name.local_name == "pron" {
+            if let Some(pron) = self.pron.take() {
+                let pron = ipa::normalize(&pron);
+                if let Some(top) = self.stack.last_mut() {
+                    if !pron.is_empty() && !top.0.pronunciations.contains(&pron) {
+                        top.0.pronunciations.push(pron);
+                    }
+                }
+            }
+        } else if name.local_name == "gram" {
+            if let Some((t, norm, text)) = self.gram.take() {
+                if let Some(top) = self.stack.last_mut() {
+                    top.0.features.insert(t, norm.unwrap_or_else(|| normalize_space(&text)));
+                }
+            }
+        } else if name.local_name == "form" {
+            if let Some((mut form, orth, text)) = self.stack.pop() {
+                form.written_rep = normalize_space(orth.as_ref().unwrap_or(&text));
+                if !form.written_rep.is_empty() {
+                    self.forms.push(form);
+                }
+            }
+        }
+    }
+
+    fn characters(&mut self, s : &str) {
+        if self.skip_depth > 0 {
+            return;
+        }
+        if let Some(ref mut gram) = self.gram {
+            gram.2.push_str(s);
+        } else if let Some(ref mut pron) = self.pron {
+            pron.push_str(s);
+        } else if let Some(top) = self.stack.last_mut() {
+            if self.in_orth {
+                if let Some(ref mut orth) = top.1 {
+                    orth.push_str(s);
+                }
+            } else {
+                top.2.push_str(s);
+            }
+        }
+    }
+
+    /// Return the forms of the entry and reset for the next entry
+    fn finish(&mut self) -> Vec<TeiForm> {
+        let forms = std::mem::take(&mut self.forms);
+        *self = FormParser::new();
+        forms
+    }
+}
+
+/// Reads the metadata from the teiHeader. Each element outside the entries
+/// collects its text, and is mapped to Dublin Core by its name and that of 
+/// its parent when it is closed
+struct HeaderParser {
+    open : Vec<(String, Vec<OwnedAttribute>, String)>,
+    title : Option<String>,
+    alternative : Option<String>,
+    creators : Vec<Agent>,
+    publishers : Vec<Agent>,
+    contributors : Vec<Agent>,
+    identifier : Option<String>,
+    issued : Option<String>,
+    created : Option<String>,
+    modified : Option<String>,
+    licence : Option<String>,
+    rights : Option<String>,
+    edition : Option<String>,
+    extent : Option<String>,
+    source : Option<String>,
+    description : Option<String>
+}
+
+impl HeaderParser {
+    fn new() -> HeaderParser {
+        HeaderParser {
+            open : Vec::new(),
+            title : None,
+            alternative : None,
+            creators : Vec::new(),
+            publishers : Vec::new(),
+            contributors : Vec::new(),
+            identifier : None,
+            issued : None,
+            created : None,
+            modified : None,
+            licence : None,
+            rights : None,
+            edition : None,
+            extent : None,
+            source : None,
+            description : None
         }
     }
 
+    fn start(&mut self, name : &OwnedName, attributes : Vec<OwnedAttribute>) {
+        self.open.push((name.local_name.clone(), attributes, String::new()));
+    }
+
+    fn characters(&mut self, s : &str) {
+        for e in self.open.iter_mut() {
+            e.2.push_str(s);
+        }
+    }
+
+    fn end(&mut self) {
+        let (name, attributes, text) = match self.open.pop() {
+            Some(e) => e,
+            None => return
+        };
+        let parent = self.open.last().map(|e| e.0.as_str()).unwrap_or("");
+        let attr = |n : &str| attributes.iter().find(|x| x.name.local_name == n).map(|x| x.value.to_string());
+        let text = normalize_space(&text);
+        let value = if text.is_empty() { None } else { Some(text.clone()) };
+        match (name.as_str(), parent) {
+            ("title", "titleStmt") => {
+                match attr("type").as_deref() {
+                    None | Some("main") | Some("full") if self.title.is_none() => self.title = value,
+                    _ => if self.alternative.is_none() { self.alternative = value }
+                }
+            },
+            ("author", "titleStmt") => self.creators.push(agent(text)),
+            ("publisher", "publicationStmt") => self.publishers.push(agent(text)),
+            ("editor", "titleStmt") => self.contributors.push(agent(text)),
+            ("name", "respStmt") | ("persName", "respStmt") | ("orgName", "respStmt") =>
+                self.contributors.push(agent(text)),
+            ("idno", "publicationStmt") => if self.identifier.is_none() { self.identifier = value },
+            ("date", "publicationStmt") => self.issued = attr("when").or(value),
+            ("date", "creation") => self.created = attr("when").or(value),
+            ("change", "revisionDesc") => {
+                if let Some(when) = attr("when") {
+                    if self.modified.as_ref().map(|m| *m < when).unwrap_or(true) {
+                        self.modified = Some(when);
+                    }
+                }
+            },
+            ("edition", _) => self.edition = attr("n").or(value),
+            ("extent", "fileDesc") => self.extent = value,
+            ("licence", _) => {
+                if let Some(target) = attr("target") {
+                    self.licence = Some(target);
+                }
+                if value.is_some() {
+                    self.rights = value;
+                }
+            },
+            ("sourceDesc", _) => self.source = value,
+            ("projectDesc", _) => self.description = value,
+            _ => {}
+        }
+    }
+
+    /// Create the metadata for a dictionary from the header
+    fn dictionary(&self, release : Release, source_language : String,
+        target_language : Vec<String>, genre : Vec<Genre>) -> Dictionary {
+        let mut dict = Dictionary::new(release, source_language, target_language, genre,
+            self.licence.clone().unwrap_or("unknown".to_string()),
+            self.creators.clone(), self.publishers.clone());
+        dict.title = self.title.clone();
+        dict.alternative = self.alternative.clone();
+        dict.contributor = self.contributors.clone();
+        dict.identifier = self.identifier.clone();
+        dict.issued = self.issued.clone();
+        dict.created = self.created.clone();
+        dict.modified = self.modified.clone();
+        dict.rights = self.rights.clone();
+        dict.has_version = self.edition.clone();
+        dict.extent = self.extent.clone();
+        dict.source = self.source.clone();
+        dict.description = self.description.clone();
+        dict
+    }
+}
+
+fn agent(name : String) -> Agent {
+    Agent { name, email : None, url : None }
+}
+
+/// Write the metadata of a dictionary as a TEI header
+pub fn header(dict : &Dictionary) -> String {
+    let mut xml = String::new();
+    xml.push_str("<teiHeader>\n  <fileDesc>\n    <titleStmt>\n");
+    if let Some(ref title) = dict.title {
+        xml.push_str(&format!("      <title>{}</title>\n", escape_str_pcdata(title)));
+    }
+    if let Some(ref alternative) = dict.alternative {
+        xml.push_str(&format!("      <title type=\"sub\">{}</title>\n", escape_str_pcdata(alternative)));
+    }
+    for creator in dict.creator.iter() {
+        xml.push_str(&format!("      <author>{}</author>\n", escape_str_pcdata(&creator.name)));
+    }
+    for contributor in dict.contributor.iter() {
+        xml.push_str(&format!("      <editor>{}</editor>\n", escape_str_pcdata(&contributor.name)));
+    }
+    xml.push_str("    </titleStmt>\n");
+    if let Some(ref edition) = dict.has_version {
+        xml.push_str(&format!("    <editionStmt>\n      <edition>{}</edition>\n    </editionStmt>\n", 
+                escape_str_pcdata(edition)));
+    }
+    if let Some(ref extent) = dict.extent {
+        xml.push_str(&format!("    <extent>{}</extent>\n", escape_str_pcdata(extent)));
+    }
+    xml.push_str("    <publicationStmt>\n");
+    for publisher in dict.publisher.iter() {
+        xml.push_str(&format!("      <publisher>{}</publisher>\n", escape_str_pcdata(&publisher.name)));
+    }
+    if let Some(ref identifier) = dict.identifier {
+        xml.push_str(&format!("      <idno>{}</idno>\n", escape_str_pcdata(identifier)));
+    }
+    if let Some(ref issued) = dict.issued {
+        xml.push_str(&format!("      <date when=\"{}\"/>\n", escape_str_attribute(issued)));
+    }
+    xml.push_str("      <availability>\n");
+    xml.push_str(&format!("        <licence target=\"{}\">{}</licence>\n", 
+            escape_str_attribute(&dict.license),
+            escape_str_pcdata(dict.rights.as_deref().unwrap_or(""))));
+    xml.push_str("      </availability>\n    </publicationStmt>\n");
+    xml.push_str("    <sourceDesc>\n");
+    xml.push_str(&format!("      <p>{}</p>\n", 
+            escape_str_pcdata(dict.source.as_deref().unwrap_or("Born digital"))));
+    xml.push_str("    </sourceDesc>\n  </fileDesc>\n");
+    if let Some(ref description) = dict.description {
+        xml.push_str(&format!("  <encodingDesc>\n    <projectDesc>\n      <p>{}</p>\n    </projectDesc>\n  </encodingDesc>\n",
+                escape_str_pcdata(description)));
+    }
+    if let Some(ref created) = dict.created {
+        xml.push_str(&format!("  <profileDesc>\n    <creation>\n      <date when=\"{}\"/>\n    </creation>\n  </profileDesc>\n",
+                escape_str_attribute(created)));
+    }
+    if let Some(ref modified) = dict.modified {
+        xml.push_str(&format!("  <revisionDesc>\n    <change when=\"{}\"/>\n  </revisionDesc>\n",
+                escape_str_attribute(modified)));
+    }
+    xml.push_str("</teiHeader>\n");
+    xml
+}
+
+fn normalize_space(s : &str) -> String {
+    s.split_whitespace().collect::<Vec<&str>>().join(" ")
 }
 
-fn extend_content_tag(content : &mut String, name : OwnedName, 
-                      attributes : Vec<OwnedAttribute>, _namespace : Namespace) {
+fn extend_content_tag(content : &mut String, name : &OwnedName, 
+                      attributes : &Vec<OwnedAttribute>) {
     content.push_str("<");
     match name.prefix {
-        Some(n) => {
+        Some(ref n) => {
             content.push_str(&n);
             content.push_str(":");
         },
@@ -262,7 +1487,7 @@ fn extend_content_tag(content : &mut String, name : OwnedName,
     for attr in attributes {
         content.push_str(" ");
         match attr.name.prefix {
-            Some(n) => {
+            Some(ref n) => {
                 content.push_str(&n);
                 content.push_str(":");
             },
@@ -276,10 +1501,10 @@ fn extend_content_tag(content : &mut String, name : OwnedName,
     content.push_str(">");
 }
 
-fn extend_content_endtag(content : &mut String, name : OwnedName) {
+fn extend_content_endtag(content : &mut String, name : &OwnedName) {
     content.push_str("</");
     match name.prefix {
-        Some(n) => {
+        Some(ref n) => {
             content.push_str(&n);
             content.push_str(":");
         },
@@ -319,52 +1544,22 @@ fn detab_content(content : &str) -> String {
     }
 }
 
-fn convert_pos(pos : &str, config : &Config) -> PartOfSpeech {
-    if let Some(ref mapping) = config.pos_mapping {
-        if let Some(pos) = mapping.get(pos) {
-            pos.clone()
-        } else {
-            PartOfSpeech::X
-        }
-    } else {
-        match pos {
-            "adjective" => PartOfSpeech::ADJ,
-            "adposition" => PartOfSpeech::ADP,
-            "adverb" => PartOfSpeech::ADV,
-            "auxiliary" => PartOfSpeech::AUX,
-            "coordinatingConjunction" => PartOfSpeech::CCONJ,
-            "determiner" => PartOfSpeech::DET,
-            "interjection" => PartOfSpeech::INTJ,
-            "commonNoun" => PartOfSpeech::NOUN,
-            "numeral" => PartOfSpeech::NUM,
-            "particle" => PartOfSpeech::PART,
-            "properNoun" => PartOfSpeech::PROPN,
-            "punctuation" => PartOfSpeech::PUNCT,
-            "subordinatingConjunction" => PartOfSpeech::SCONJ,
-            "symbol" => PartOfSpeech::SYM,
-            "verb" => PartOfSpeech::VERB,
-            _ => PartOfSpeech::X
-        }
-    }
-}
-
-
-// What the parse is currently doing
-#[derive(Debug,PartialEq)]
-enum State {
-    Empty,
-    Author,
-    Publisher,
-    Entry,
-    Lemma,
-    Pos,
-    Variant
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{EDSState, Backend};
+    use crate::model::{EDSState, Backend, FrequencyFilter, LookupQuery, TagMapping};
+    use crate::ids::IdStrategy;
+
+    fn parse<R : Read,F>(input : R, id : &str, release : Release,
+                       genre : Vec<Genre>, config : &Config, foo : F) -> BackendImpl 
+        where F : FnOnce(Release, HashMap<String,Dictionary>, HashMap<String, Vec<EntryContent>>) -> BackendImpl {
+        let mut report = ValidationReport::default();
+        parse_with_report(input, None, ParseOptions { id, release, genre, config, strict : false }, &mut report, foo).unwrap()
+    }
+
+    fn options(config : &Config, strict : bool) -> ParseOptions<'_> {
+        ParseOptions { id : "test-dict", release : Release::PUBLIC, genre : Vec::new(), config, strict }
+    }
 
     #[test]
     fn test_load_tei() {
@@ -530,10 +1725,692 @@ mod tests {
         let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &config, |r,d,e| {
             BackendImpl::Mem(EDSState::new(r,d,e)) 
         });
-        let result = dict.lookup("test-dict", "girl", None, None, Some(PartOfSpeech::NOUN), false);
+        let result = dict.lookup("test-dict", "girl", &LookupQuery { part_of_speech : Some(PartOfSpeech::NOUN), ..LookupQuery::default() });
         assert_eq!(result.unwrap().len(), 1);
     }
 
  
 
+    #[test]
+    fn test_senses() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"bank-en\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <gramGrp><gram type=\"pos\" norm=\"NOUN\">noun</gram></gramGrp>
+        <sense xml:id=\"bank-1\">
+          <usg type=\"domain\">finance</usg>
+          <def>An institution that
+             keeps money</def>
+          <cit type=\"example\"><quote>She went to the bank</quote><bibl>BNC</bibl></cit>
+          <cit type=\"translation\" xml:lang=\"de\"><quote>Bank</quote></cit>
+          <sense xml:id=\"bank-1a\">
+            <def>The building of such an institution</def>
+          </sense>
+        </sense>
+        <sense xml:id=\"bank-2\">
+          <def>The side of a river</def>
+          <cit type=\"translationEquivalent\"><form><orth xml:lang=\"de\">Ufer</orth></form></cit>
+        </sense>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        let senses = dict.entry_senses("test-dict", "bank-en").unwrap();
+        assert_eq!(senses.len(), 2);
+        assert_eq!(senses[0].id, Some("bank-1".to_string()));
+        assert_eq!(senses[0].definitions, vec!["An institution that keeps money"]);
+        assert_eq!(senses[0].examples, vec!["She went to the bank"]);
+        assert_eq!(senses[0].usage, vec![Usage { usage_type : Some("domain".to_string()), label : "finance".to_string() }]);
+        assert_eq!(senses[0].translations, vec![Translation { language : Some("de".to_string()), text : "Bank".to_string() }]);
+        assert_eq!(senses[0].senses[0].definitions, vec!["The building of such an institution"]);
+        assert_eq!(senses[1].translations, vec![Translation { language : Some("de".to_string()), text : "Ufer".to_string() }]);
+        assert_eq!(dict.sense("test-dict", "bank-1a").unwrap().definitions, vec!["The building of such an institution"]);
+        assert!(dict.sense("test-dict", "bank-3").is_err());
+        let json = dict.entry_json("test-dict", "bank-en").unwrap();
+        assert_eq!(json.senses.len(), 3);
+    }
+
+    #[test]
+    fn test_translations() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"bank\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <sense xml:id=\"bank-1\"><cit type=\"translation\" xml:lang=\"de\"><quote>Bank</quote></cit></sense>
+        <sense xml:id=\"bank-2\">
+          <cit type=\"translation\" xml:lang=\"de\"><quote>Ufer</quote></cit>
+          <cit type=\"translation\" xml:lang=\"fr\"><quote>rive</quote></cit>
+        </sense>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"shore\">
+        <form type=\"lemma\"><orth xml:lang=\"en-GB\">shore</orth></form>
+        <sense><cit type=\"translation\" xml:lang=\"de-AT\"><quote>Ufer</quote></cit></sense>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        assert_eq!(dict.about("test-dict").unwrap().target_language, vec!["de", "fr", "de-AT"]);
+        let matches = dict.lookup_translation("test-dict", "de", "Ufer", None, None).unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].entry.id, "bank");
+        assert_eq!(matches[0].sense, Some("bank-2".to_string()));
+        assert_eq!(matches[1].entry.id, "shore");
+        assert_eq!(matches[1].sense, None);
+        assert_eq!(dict.lookup_translation("test-dict", "de-AT", "Ufer", None, None).unwrap().len(), 1);
+        assert_eq!(dict.lookup_translation("test-dict", "de", "Ufer", Some(1), None).unwrap().len(), 1);
+        assert!(dict.lookup_translation("test-dict", "fr", "Bank", None, None).unwrap().is_empty());
+        assert!(dict.lookup_translation("other-dict", "de", "Ufer", None, None).is_err());
+    }
+
+    #[test]
+    fn test_variants() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"girl-en\">
+        <form type=\"lemma\">
+          <orth>girl</orth>
+          <form type=\"inflected\">
+            <orth>girls</orth>
+            <gramGrp><gram type=\"number\">plural</gram></gramGrp>
+          </form>
+        </form>
+        <form type=\"variant\"><orth>gurl</orth></form>
+        <gramGrp><gram type=\"pos\" norm=\"NOUN\">noun</gram></gramGrp>
+        <etym>
+          <cit type=\"cognate\">
+            <form type=\"variant\"><orth xml:lang=\"gsw\">gurre</orth></form>
+          </cit>
+        </etym>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        let plural = vec![("number".to_string(), "plural".to_string())];
+        let singular = vec![("number".to_string(), "singular".to_string())];
+        assert_eq!(dict.lookup("test-dict", "girls", &LookupQuery { inflected : true, ..LookupQuery::default() }).unwrap()[0].lemma, "girl");
+        assert_eq!(dict.lookup("test-dict", "gurl", &LookupQuery { inflected : true, ..LookupQuery::default() }).unwrap().len(), 1);
+        assert!(dict.lookup("test-dict", "girls", &LookupQuery::default()).is_err());
+        assert!(dict.lookup("test-dict", "gurre", &LookupQuery { inflected : true, ..LookupQuery::default() }).is_err());
+        assert_eq!(dict.lookup("test-dict", "girls", &LookupQuery { inflected : true, features : plural.clone(), ..LookupQuery::default() }).unwrap().len(), 1);
+        assert_eq!(dict.lookup("test-dict", "girls", &LookupQuery { inflected : true, features : singular.clone(), ..LookupQuery::default() }).unwrap().len(), 0);
+        let ud_plural = ud::parse("Number=Plur").unwrap();
+        assert_eq!(dict.lookup("test-dict", "girls", &LookupQuery { inflected : true, features : ud_plural.clone(), ..LookupQuery::default() }).unwrap().len(), 1);
+        let analyses = dict.analyse("test-dict", "girls", None, None).unwrap();
+        assert_eq!(analyses.len(), 1);
+        assert_eq!(analyses[0].entry.lemma, "girl");
+        assert_eq!(analyses[0].features, "Number=Plur");
+        assert_eq!(dict.lookup("test-dict", "girl", &LookupQuery { part_of_speech : Some(PartOfSpeech::NOUN), ..LookupQuery::default() }).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_nested_entries() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <superEntry>
+        <entry xml:lang=\"en\" xml:id=\"bank-1\">
+          <form type=\"lemma\"><orth>bank</orth></form>
+          <sense xml:id=\"bank-1-1\"><def>A financial institution</def></sense>
+          <re type=\"compound\">
+            <form><orth>bank holiday</orth></form>
+            <sense><def>A public holiday</def></sense>
+          </re>
+          <entry xml:id=\"banker\" type=\"derivative\">
+            <form type=\"lemma\"><orth>banker</orth></form>
+          </entry>
+        </entry>
+        <entry xml:lang=\"en\" xml:id=\"bank-2\">
+          <form type=\"lemma\"><orth>bank</orth></form>
+        </entry>
+      </superEntry>
+    </body>
+  </text>
+</TEI>";
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        assert_eq!(dict.lookup("test-dict", "bank", &LookupQuery::default()).unwrap().len(), 2);
+        assert_eq!(dict.lookup("test-dict", "bank holiday", &LookupQuery::default()).unwrap()[0].id, "bank-1_1");
+        assert_eq!(dict.lookup("test-dict", "banker", &LookupQuery::default()).unwrap().len(), 1);
+        assert_eq!(dict.entry_senses("test-dict", "bank-1").unwrap().len(), 1);
+        assert_eq!(dict.entry_senses("test-dict", "bank-1_1").unwrap()[0].definitions, vec!["A public holiday"]);
+        let relations = dict.entry_relations("test-dict", "bank-1").unwrap();
+        assert_eq!(relations.children, vec![
+            RelatedEntry { id : "bank-1_1".to_string(), relation : "compound".to_string() },
+            RelatedEntry { id : "banker".to_string(), relation : "derivative".to_string() }]);
+        assert_eq!(relations.homographs, vec!["bank-2"]);
+        assert_eq!(dict.entry_relations("test-dict", "banker").unwrap().parent,
+            Some(RelatedEntry { id : "bank-1".to_string(), relation : "derivative".to_string() }));
+        assert!(dict.entry_tei("test-dict", "bank-1").unwrap().contains("<orth>banker</orth>"));
+        assert!(dict.entry_tei("test-dict", "bank-1_1").unwrap().starts_with("<re type=\"compound\">"));
+    }
+
+    #[test]
+    fn test_generated_ids() {
+        let doc = |extra : &str| format!("<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>{}
+      <entry xml:lang=\"en\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <gramGrp><gram type=\"pos\">noun</gram></gramGrp>
+        <re type=\"compound\"><form><orth>bank holiday</orth></form></re>
+      </entry>
+      <entry xml:lang=\"en\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <gramGrp><gram type=\"pos\">noun</gram></gramGrp>
+        <sense><def>The side of a river</def></sense>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"cat-noun-1\">
+        <form type=\"lemma\"><orth>cat</orth></form>
+      </entry>
+      <entry xml:lang=\"en\">
+        <form type=\"lemma\"><orth>cat</orth></form>
+        <gramGrp><gram type=\"pos\">noun</gram></gramGrp>
+      </entry>
+    </body>
+  </text>
+</TEI>", extra);
+        let ids = |doc : &str, config : &Config, report : &mut ValidationReport| {
+            let dict = parse_with_report(doc.as_bytes(), None, options(config, false), report, |r,d,e| BackendImpl::Mem(EDSState::new(r,d,e))).unwrap();
+            let mut ids : Vec<String> = dict.list("test-dict", None, None, &FrequencyFilter::default()).unwrap()
+                .into_iter().map(|e| e.id).collect();
+            ids.sort();
+            ids
+        };
+        let mut config = Config::blank();
+        config.id_strategy = Some(IdStrategy::Lemma);
+        let mut report = ValidationReport::default();
+        assert_eq!(ids(&doc(""), &config, &mut report), vec!["bank-noun-1", "bank-noun-2", "bank_holiday-x-1", "cat-noun-1", "cat-noun-1_2"]);
+        assert_eq!(report.diagnostics.iter().filter(|d| d.rule == "entry-id-collision")
+            .map(|d| d.entry.clone().unwrap()).collect::<Vec<_>>(), vec!["cat-noun-1_2"]);
+        assert!(report.diagnostics.iter().any(|d| d.entry == Some("bank-noun-2".to_string())));
+        // The homograph number of the source is used if there is one
+        let numbered = "<entry xml:lang=\"en\" n=\"7\"><form type=\"lemma\"><orth>bank</orth></form>
+        <gramGrp><gram type=\"pos\">noun</gram></gramGrp></entry>";
+        assert_eq!(ids(&doc(numbered), &config, &mut ValidationReport::default()),
+            vec!["bank-noun-1", "bank-noun-2", "bank-noun-7", "bank_holiday-x-1", "cat-noun-1", "cat-noun-1_2"]);
+        config.id_strategy = Some(IdStrategy::Hash);
+        let before = ids(&doc(""), &config, &mut ValidationReport::default());
+        let after = ids(&doc("<entry xml:lang=\"en\"><form type=\"lemma\"><orth>ant</orth></form></entry>"), 
+            &config, &mut ValidationReport::default());
+        assert_eq!(after.len(), 6);
+        assert!(before.iter().all(|id| after.contains(id)));
+    }
+
+    #[test]
+    fn test_generated_nested_ids() {
+        let doc = |extra : &str| format!("<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"bank\">
+        <form type=\"lemma\"><orth>bank</orth></form>{}
+        <re type=\"compound\"><form><orth>bank holiday</orth></form></re>
+      </entry>
+    </body>
+  </text>
+</TEI>", extra);
+        let mut config = Config::blank();
+        config.id_strategy = Some(IdStrategy::Lemma);
+        let children = |doc : &str| {
+            let dict = parse_with_report(doc.as_bytes(), None, options(&config, false), &mut ValidationReport::default(), |r,d,e| BackendImpl::Mem(EDSState::new(r,d,e))).unwrap();
+            let mut ids : Vec<String> = dict.list("test-dict", None, None, &FrequencyFilter::default()).unwrap()
+                .into_iter().map(|e| e.id).filter(|id| id != "bank").collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(children(&doc("")), vec!["bank_holiday-x-1"]);
+        // Adding a sibling before it does not change its id
+        assert_eq!(children(&doc("<re type=\"compound\"><form><orth>bank account</orth></form></re>")),
+            vec!["bank_account-x-1", "bank_holiday-x-1"]);
+    }
+
+    #[test]
+    fn test_generated_ids_strict() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <teiHeader><fileDesc><publicationStmt><availability>
+    <licence target=\"http://www.example.com/licence\"/>
+  </availability></publicationStmt></fileDesc></teiHeader>
+  <text>
+    <body>
+      <entry xml:lang=\"en\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <gramGrp><gram type=\"pos\">noun</gram></gramGrp>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let parse_strict = |config : &Config, report : &mut ValidationReport| {
+            parse_with_report(doc.as_bytes(), None, options(config, true), report, |r,d,e| BackendImpl::Mem(EDSState::new(r,d,e)))
+        };
+        let mut config = Config::blank();
+        let mut report = ValidationReport::default();
+        assert!(parse_strict(&config, &mut report).is_none());
+        assert_eq!(report.diagnostics.iter().find(|d| d.rule == "entry-id").unwrap().severity, Severity::Error);
+        config.id_strategy = Some(IdStrategy::Lemma);
+        let mut report = ValidationReport::default();
+        let dict = parse_strict(&config, &mut report).unwrap();
+        assert_eq!(report.errors(), 0);
+        assert_eq!(report.diagnostics.iter().find(|d| d.rule == "entry-id").unwrap().severity, Severity::Warning);
+        assert!(dict.entry_json("test-dict", "bank-noun-1").is_ok());
+    }
+
+    #[test]
+    fn test_etymology() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"girl\">
+        <form type=\"lemma\"><orth>girl</orth></form>
+        <etym type=\"borrowing\">
+          <cit type=\"etymon\">
+            <lang>Middle English</lang>
+            <form><orth xml:lang=\"enm\">gurle</orth></form>
+            <gloss>child</gloss>
+            <etym type=\"inheritance\">
+              <cit type=\"etymon\" xml:lang=\"ang\">
+                <form><orth>gyrela</orth></form>
+                <sense><def>dress</def></sense>
+              </cit>
+            </etym>
+          </cit>
+          <cit type=\"cognate\" xml:lang=\"nds\">
+            <form><orth>Göre</orth></form>
+          </cit>
+        </etym>
+        <sense><def>A female child</def></sense>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"boy\">
+        <form type=\"lemma\"><orth>boy</orth></form>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        let etymology = dict.entry_etymology("test-dict", "girl").unwrap();
+        assert_eq!(etymology.ancestors.len(), 1);
+        let ancestor = &etymology.ancestors[0];
+        assert_eq!(ancestor.language, Some("enm".to_string()));
+        assert_eq!(ancestor.language_name, Some("Middle English".to_string()));
+        assert_eq!(ancestor.forms, vec!["gurle"]);
+        assert_eq!(ancestor.glosses, vec!["child"]);
+        assert_eq!(ancestor.etym_type, Some("borrowing".to_string()));
+        assert_eq!(ancestor.ancestors[0].forms, vec!["gyrela"]);
+        assert_eq!(ancestor.ancestors[0].glosses, vec!["dress"]);
+        assert_eq!(ancestor.ancestors[0].etym_type, Some("inheritance".to_string()));
+        assert_eq!(etymology.cognates[0].language, Some("nds".to_string()));
+        assert_eq!(etymology.cognates[0].forms, vec!["Göre"]);
+        assert_eq!(etymology.languages, vec!["enm", "ang", "nds"]);
+        assert_eq!(dict.entry_senses("test-dict", "girl").unwrap().len(), 1);
+        assert!(dict.entry_etymology("test-dict", "boy").unwrap().is_empty());
+        assert!(dict.entry_etymology("test-dict", "man").is_err());
+
+        let x : &[u8] = include_bytes!("../examples/new-tei-etymology-examples.xml");
+        let dict = parse(x, "ety", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        let etymology = dict.entry_etymology("ety-en", "girl-en").unwrap();
+        assert_eq!(etymology.cognates.len(), 6);
+        assert_eq!(etymology.cognates[3].forms, vec!["gurre", "gurrli"]);
+        assert_eq!(etymology.cognates[3].language_name, Some("Swiss".to_string()));
+    }
+
+    #[test]
+    fn test_pronunciation() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"cat\">
+        <form type=\"lemma\"><orth>cat</orth><pron>/kæt/</pron><pron notation=\"x-sampa\">k{t</pron></form>
+        <form type=\"inflected\"><orth>cats</orth><pron>kæts</pron></form>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"cut\">
+        <form type=\"lemma\"><orth>cut</orth><pron>[kʌt]</pron></form>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"gate\">
+        <form type=\"lemma\"><orth>gate</orth><pron>'geɪt</pron></form>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"dog\">
+        <form type=\"lemma\"><orth>dog</orth><pron>dɒɡ</pron></form>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        let exact = dict.lookup_pron("test-dict", "kæt", 0.0, None, None).unwrap();
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].entry.id, "cat");
+        assert_eq!(exact[0].form, "cat");
+        let close = dict.lookup_pron("test-dict", "/kæt/", 1.0, None, None).unwrap();
+        assert_eq!(close.iter().map(|m| m.entry.id.as_str()).collect::<Vec<&str>>(), vec!["cat", "cut"]);
+        assert!(close[1].distance > 0.0 && close[1].distance < 0.5);
+        let plural = dict.lookup_pron("test-dict", "kʌts", 1.0, None, None).unwrap();
+        assert_eq!(plural[0].form, "cats");
+        assert_eq!(plural[1].entry.id, "cut");
+        assert_eq!(dict.lookup_pron("test-dict", "ɡeɪt", 0.0, None, None).unwrap()[0].ipa, "ˈɡeɪt");
+        assert_eq!(dict.lookup_pron("test-dict", "kæt", 2.0, Some(1), Some(1)).unwrap().len(), 1);
+        assert!(dict.lookup_pron("other-dict", "kæt", 1.0, None, None).is_err());
+        let json = serde_json::to_value(dict.entry_json("test-dict", "cat").unwrap()).unwrap();
+        assert_eq!(json["canonicalForm"]["phoneticRep"], "kæt");
+        assert_eq!(json["otherForm"][0]["phoneticRep"], "kæts");
+    }
+
+    #[test]
+    fn test_media() {
+        let dir = Path::new("test-tmp-media");
+        std::fs::create_dir_all(dir.join("audio")).unwrap();
+        std::fs::write(dir.join("audio/cat.mp3"), b"ID3").unwrap();
+        std::fs::write(dir.join("audio/dog.mp3"), b"ID3").unwrap();
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"cat\">
+        <form type=\"lemma\"><orth>cat</orth><media mimeType=\"audio/mpeg\" url=\"audio/cat.mp3\"/></form>
+        <sense><def>A small feline</def><figure><graphic url=\"http://example.com/cat.jpg\"/></figure></sense>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"cats\">
+        <form type=\"lemma\"><orth>cats</orth><media url=\"audio/cat.mp3\"/><media url=\"audio/cats.mp3\"/>
+          <media url=\"../Cargo.toml\"/><media url=\"file:///etc/passwd\"/></form>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"dog\">
+        <form type=\"lemma\"><orth>dog</orth><media url=\"audio/dog.mp3\"/></form>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let mut report = ValidationReport::default();
+        // A file that is removed before the entries are stored is dropped too
+        let dict = parse_with_report(doc.as_bytes(), Some(dir), options(&Config::blank(), false), &mut report, |r,d,e| {
+            std::fs::remove_file(dir.join("audio/dog.mp3")).unwrap();
+            BackendImpl::Mem(EDSState::new(r,d,e))
+        }).unwrap();
+        let media = dict.media("test-dict", "cat.mp3");
+        std::fs::remove_dir_all(dir).unwrap();
+        let media = media.unwrap();
+        assert_eq!(media.mime_type, "audio/mpeg");
+        assert_eq!(media.path, Some(dir.join("audio/cat.mp3")));
+        let json = dict.entry_json("test-dict", "cat").unwrap();
+        assert_eq!(json.media.len(), 2);
+        assert_eq!(json.media[0].url, "/media/test-dict/cat.mp3");
+        assert_eq!(json.media[1].url, "http://example.com/cat.jpg");
+        assert_eq!(json.media[1].mime_type, "image/jpeg");
+        let cats = dict.entry_json("test-dict", "cats").unwrap();
+        assert_eq!(cats.media[0].id, Some("cat.mp3".to_string()));
+        assert_eq!(cats.media.len(), 1);
+        assert!(dict.entry_json("test-dict", "dog").unwrap().media.is_empty());
+        assert_eq!(report.diagnostics.iter().filter(|d| d.rule == "media").map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            vec!["Media file not found: audio/cats.mp3",
+                 "Media file is not in the data directory or the media root: ../Cargo.toml",
+                 "Media file is not in the data directory or the media root: file:///etc/passwd"]);
+    }
+
+    #[test]
+    fn test_frequency() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"bank-1\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <usg type=\"frequency\">120</usg>
+        <sense><usg type=\"frequency\">rare</usg><def>A slope</def></sense>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"bank-2\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <sense><usg type=\"frequency\">4500</usg><def>A financial institution</def></sense>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"bank-3\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        let json = dict.entry_json("test-dict", "bank-1").unwrap();
+        assert_eq!(json.frequencies, vec![Frequency { count : Some(120), label : None, corpus : None }]);
+        assert_eq!(json.senses[0].frequencies, vec![Frequency { count : None, label : Some("rare".to_string()), corpus : None }]);
+        let sorted = FrequencyFilter { min : None, sort : true };
+        let entries = dict.lookup("test-dict", "bank", &LookupQuery { frequency : sorted.clone(), ..LookupQuery::default() }).unwrap();
+        assert_eq!(entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["bank-2", "bank-1", "bank-3"]);
+        assert_eq!(entries[0].frequency, Some(4500));
+        let frequent = FrequencyFilter { min : Some(1000), sort : false };
+        assert_eq!(dict.list("test-dict", None, None, &frequent).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tagset() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"fr\" xml:id=\"maison\">
+        <form type=\"lemma\"><orth>maison</orth></form>
+        <form type=\"inflected\"><orth>maisons</orth><gramGrp><gram type=\"number\">pl</gram></gramGrp></form>
+        <gramGrp><gram type=\"pos\">nf</gram></gramGrp>
+      </entry>
+      <entry xml:lang=\"fr\" xml:id=\"chat\">
+        <form type=\"lemma\"><orth>chat</orth></form>
+        <gramGrp><gram type=\"pos\">subst.</gram></gramGrp>
+      </entry>
+      <entry xml:lang=\"fr\" xml:id=\"chien\">
+        <form type=\"lemma\"><orth>chien</orth></form>
+        <gramGrp><gram type=\"pos\">subst.</gram></gramGrp>
+      </entry>
+      <entry xml:lang=\"fr\" xml:id=\"vite\">
+        <form type=\"lemma\"><orth>vite</orth></form>
+        <gramGrp><gram type=\"pos\">avv.</gram></gramGrp>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let mut config = Config::blank();
+        let mut tagset = HashMap::new();
+        tagset.insert("avv.".to_string(), TagMapping { pos : Some(PartOfSpeech::ADV), lexinfo : Some("adverb".to_string()), features : None });
+        config.tagset = Some(tagset);
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &config, |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        assert_eq!(dict.lookup("test-dict", "vite", &LookupQuery { part_of_speech : Some(PartOfSpeech::ADV), ..LookupQuery::default() }).unwrap().len(), 1);
+        let tags = dict.tagset("test-dict").unwrap();
+        assert_eq!(tags.iter().map(|t| (t.tag.as_str(), t.entries)).collect::<Vec<_>>(), vec![("avv.", 1), ("nf", 1), ("subst.", 2)]);
+        assert_eq!(tags[1].mapping.pos, Some(PartOfSpeech::NOUN));
+        assert_eq!(tags[1].mapping.features, Some("Gender=Fem".to_string()));
+        let plural = ud::parse("Gender=Fem|Number=Plur").unwrap();
+        assert_eq!(dict.lookup("test-dict", "maisons", &LookupQuery { inflected : true, features : plural.clone(), ..LookupQuery::default() }).unwrap().len(), 1);
+        let json = dict.entry_json("test-dict", "chat").unwrap();
+        assert_eq!(json.tags[0].tag, "subst.");
+    }
+
+    #[test]
+    fn test_homographs() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <text>
+    <body>
+      <entry xml:lang=\"en\" xml:id=\"bank-n\" n=\"2\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <gramGrp><pos>noun</pos></gramGrp>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"bank-v\">
+        <form type=\"lemma\"><orth>bank</orth></form>
+        <gramGrp><pos>verb</pos></gramGrp>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"bass\">
+        <form type=\"lemma\"><orth>bass</orth></form>
+        <hom n=\"1\"><gramGrp><pos>noun</pos></gramGrp><sense><def>a fish</def></sense></hom>
+        <hom n=\"2\"><gramGrp><pos>adj</pos></gramGrp><sense><def>low in pitch</def></sense></hom>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"cat\" n=\"one\">
+        <form type=\"lemma\"><orth>cat</orth></form>
+        <gramGrp><pos>noun</pos></gramGrp>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let mut report = ValidationReport::default();
+        let dict = parse_with_report(doc.as_bytes(), None, options(&Config::blank(), false), &mut report, |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        }).unwrap();
+        let homographs = |lemma : &str| {
+            let mut entries : Vec<(String, Option<u32>)> = dict.lookup("test-dict", lemma, &LookupQuery::default())
+                .unwrap().into_iter().map(|e| (e.id, e.homograph)).collect();
+            entries.sort();
+            entries
+        };
+        assert_eq!(homographs("bank"), vec![("bank-n".to_string(), Some(2)), ("bank-v".to_string(), Some(1))]);
+        assert_eq!(homographs("bass"), vec![("bass".to_string(), None), ("bass_1".to_string(), Some(1)), ("bass_2".to_string(), Some(2))]);
+        assert_eq!(homographs("cat"), vec![("cat".to_string(), None)]);
+        let second = dict.lookup("test-dict", "bass", &LookupQuery { homograph : Some(2), ..LookupQuery::default() }).unwrap();
+        assert_eq!(second[0].part_of_speech, vec![PartOfSpeech::ADJ]);
+        assert_eq!(dict.entry_json("test-dict", "bank-v").unwrap().homograph, Some(1));
+        assert!(report.diagnostics.iter().any(|d| d.rule == "homograph-number"));
+    }
+
+    #[test]
+    fn test_validation() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <teiHeader><fileDesc><publicationStmt><availability><licence/></availability></publicationStmt></fileDesc></teiHeader>
+  <text>
+    <body>
+      <entry xml:lang=\"en\">
+        <form type=\"lemma\"><orth>cat</orth></form>
+        <gramGrp><gram type=\"pos\" norm=\"NOMEN\">nomen</gram></gramGrp>
+      </entry>
+      <entry xml:lang=\"en\" xml:id=\"dog\">
+        <gramGrp><gram type=\"pos\">nomen</gram></gramGrp>
+      </entry>
+    </body>
+  </text>
+</TEI>";
+        let mut report = ValidationReport::default();
+        let result = parse_with_report(doc.as_bytes(), None, options(&Config::blank(), true), &mut report, |r,d,e| BackendImpl::Mem(EDSState::new(r,d,e)));
+        assert!(result.is_none());
+        let rules : Vec<(u64, &str)> = report.diagnostics.iter().map(|d| (d.line, d.rule)).collect();
+        assert_eq!(rules, vec![(2, "licence-target"), (5, "entry-id"), (7, "pos-norm"), (7, "pos-mapping"),
+            (10, "pos-mapping"), (9, "entry-lemma"), (1, "licence")]);
+        assert_eq!(report.errors(), 4);
+        assert_eq!(report.diagnostics[1].entry, Some("unidentified_entry_1".to_string()));
+        assert_eq!(report.diagnostics[1].column, 7);
+        assert_eq!(format!("{}", report.diagnostics[5]), 
+            "9:7: error[entry-lemma]: Entry has no form[@type=lemma] (entry dog)");
+
+        let mut report = ValidationReport::default();
+        let result = parse_with_report("<TEI><text><body><entry xml:id=\"a\"></body></TEI>".as_bytes(), None, options(&Config::blank(), false), &mut report, |r,d,e| BackendImpl::Mem(EDSState::new(r,d,e)));
+        assert!(result.is_some());
+        assert_eq!(report.diagnostics[0].rule, "xml");
+        assert_eq!(report.diagnostics[0].entry, Some("a".to_string()));
+    }
+
+    #[test]
+    fn test_header() {
+        let doc = "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <teiHeader>
+    <fileDesc>
+      <titleStmt>
+        <title>A Test Dictionary</title>
+        <title type=\"sub\">of English &amp; Irish</title>
+        <author>Joe Bloggs</author>
+        <editor>Jane Doe</editor>
+        <respStmt><resp>encoded by</resp><name>John Smith</name></respStmt>
+      </titleStmt>
+      <editionStmt><edition n=\"2\">Second edition</edition></editionStmt>
+      <extent>2 entries</extent>
+      <publicationStmt>
+        <publisher>Publisher</publisher>
+        <idno type=\"DOI\">10.1234/test</idno>
+        <date when=\"2020-01-01\">1 January 2020</date>
+        <availability><licence target=\"http://www.example.com/licence\">Open for all</licence></availability>
+      </publicationStmt>
+      <sourceDesc><bibl><author>A. Lexicographer</author>, <title>The Old Dictionary</title>, <publisher>Old Press</publisher>, 1900</bibl></sourceDesc>
+    </fileDesc>
+    <encodingDesc><projectDesc><p>A dictionary for testing</p></projectDesc></encodingDesc>
+    <profileDesc><creation><date when=\"2019\"/></creation></profileDesc>
+    <revisionDesc><change when=\"2020-03-01\"/><change when=\"2020-02-01\"/></revisionDesc>
+  </teiHeader>
+  <text><body>
+    <entry xml:id=\"cat\" xml:lang=\"en\"><form type=\"lemma\"><orth>cat</orth></form></entry>
+  </body></text>
+</TEI>";
+        let dict = parse(doc.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        let about = dict.about("test-dict").unwrap();
+        assert_eq!(about.title, Some("A Test Dictionary".to_string()));
+        assert_eq!(about.alternative, Some("of English & Irish".to_string()));
+        assert_eq!(about.creator, vec![agent("Joe Bloggs".to_string())]);
+        assert_eq!(about.contributor, vec![agent("Jane Doe".to_string()), agent("John Smith".to_string())]);
+        assert_eq!(about.publisher, vec![agent("Publisher".to_string())]);
+        assert_eq!(about.has_version, Some("2".to_string()));
+        assert_eq!(about.extent, Some("2 entries".to_string()));
+        assert_eq!(about.identifier, Some("10.1234/test".to_string()));
+        assert_eq!(about.issued, Some("2020-01-01".to_string()));
+        assert_eq!(about.license, "http://www.example.com/licence");
+        assert_eq!(about.rights, Some("Open for all".to_string()));
+        assert_eq!(about.source, Some("A. Lexicographer, The Old Dictionary, Old Press, 1900".to_string()));
+        assert_eq!(about.description, Some("A dictionary for testing".to_string()));
+        assert_eq!(about.created, Some("2019".to_string()));
+        assert_eq!(about.modified, Some("2020-03-01".to_string()));
+
+        let doc2 = format!("<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">{}<text><body>
+    <entry xml:id=\"cat\" xml:lang=\"en\"><form type=\"lemma\"><orth>cat</orth></form></entry>
+  </body></text></TEI>", header(&about));
+        let dict2 = parse(doc2.as_bytes(), "test-dict", Release::PUBLIC, Vec::new(), &Config::blank(), |r,d,e| {
+            BackendImpl::Mem(EDSState::new(r,d,e)) 
+        });
+        assert_eq!(serde_json::to_value(dict2.about("test-dict").unwrap()).unwrap(), 
+                   serde_json::to_value(about).unwrap());
+    }
+
+    #[test]
+    fn test_xinclude() {
+        let dir = Path::new("test-tmp-tei");
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("master.xml"), "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\" xmlns:xi=\"http://www.w3.org/2001/XInclude\">
+  <teiHeader><fileDesc>
+    <titleStmt><title>Master</title></titleStmt>
+    <publicationStmt><availability><licence target=\"http://www.example.com/licence\"/></availability></publicationStmt>
+  </fileDesc></teiHeader>
+  <text><body>
+    <xi:include href=\"a.xml\"/>
+    <xi:include href=\"b.xml\"><xi:fallback><entry xml:id=\"fallback\"/></xi:fallback></xi:include>
+  </body></text>
+</TEI>").unwrap();
+        std::fs::write(dir.join("a.xml"), "<div xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <entry xml:id=\"apple\" xml:lang=\"en\"><form type=\"lemma\"><orth>apple</orth></form></entry>
+</div>").unwrap();
+        std::fs::write(dir.join("b.xml"), "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">
+  <teiHeader><fileDesc><titleStmt><title>Part B</title></titleStmt></fileDesc></teiHeader>
+  <text><body>
+    <entry xml:id=\"banana\" xml:lang=\"en\"><form type=\"lemma\"><orth>banana</orth></form></entry>
+    <entry xml:lang=\"en\"><form type=\"lemma\"><orth>berry</orth></form></entry>
+  </body></text>
+</TEI>").unwrap();
+
+        let mut report = ValidationReport::default();
+        let dict = parse_files(&[dir.join("master.xml"), dir.join("a.xml"), dir.join("b.xml")], options(&Config::blank(), false), &mut report, |r,d,e| BackendImpl::Mem(EDSState::new(r,d,e))).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        assert_eq!(dict.dictionaries().unwrap(), vec!["test-dict"]);
+        assert_eq!(dict.about("test-dict").unwrap().title, Some("Master".to_string()));
+        let mut lemmas : Vec<String> = dict.list("test-dict", None, None, &FrequencyFilter::default()).unwrap().into_iter().map(|e| e.lemma).collect();
+        lemmas.sort();
+        assert_eq!(lemmas, vec!["apple", "banana", "berry"]);
+        assert_eq!(report.diagnostics.iter().filter(|d| d.rule == "entry-id").map(|d| (d.file.clone(), d.line)).collect::<Vec<_>>(),
+            vec![(Some(dir.join("b.xml").display().to_string()), 5)]);
+    }
+
 }
@@ -0,0 +1,122 @@
+//! Universal Dependencies morphological features. The grammatical features
+//! of forms are given by TEI `gram` elements, lexinfo properties or UD
+//! feature strings, and are mapped to UD features (e.g., `number=plural`
+//! and `num=pl` both become `Number=Plur`) so they can be compared.
+use std::collections::BTreeMap;
+
+/// A UD name or value with the names it is given as
+type Names = (&'static str, &'static [&'static str]);
+
+/// The UD features and the names and values they are given as. A name or
+/// value is matched ignoring case, and the UD name or value itself always
+/// matches
+static FEATURES : &[(&str, &[&str], &[Names])] = &[
+    ("Case", &["case", "grammaticalCase"], &[
+        ("Nom", &["nominative", "nominativeCase", "nom"]),
+        ("Gen", &["genitive", "genitiveCase", "gen"]),
+        ("Dat", &["dative", "dativeCase", "dat"]),
+        ("Acc", &["accusative", "accusativeCase", "acc"]),
+        ("Voc", &["vocative", "vocativeCase", "voc"]),
+        ("Loc", &["locative", "locativeCase", "loc"]),
+        ("Ins", &["instrumental", "instrumentalCase", "instr", "inst"]),
+        ("Abl", &["ablative", "ablativeCase", "abl"]),
+        ("Par", &["partitive", "partitiveCase", "part"]),
+        ("Ess", &["essive", "essiveCase", "ess"]),
+        ("Ill", &["illative", "illativeCase", "ill"]),
+        ("Ela", &["elative", "elativeCase", "ela"]),
+        ("Ine", &["inessive", "inessiveCase", "ine"]),
+        ("All", &["allative", "allativeCase", "all"]),
+        ("Ade", &["adessive", "adessiveCase", "ade"])]),
+    ("Number", &["number", "num"], &[
+        ("Sing", &["singular", "sg", "sing"]),
+        ("Plur", &["plural", "pl", "plur"]),
+        ("Dual", &["dual", "du"])]),
+    ("Gender", &["gender", "gen"], &[
+        ("Masc", &["masculine", "masc", "m"]),
+        ("Fem", &["feminine", "fem", "f"]),
+        ("Neut", &["neuter", "neut", "n"]),
+        ("Com", &["commonGender", "common", "c"])]),
+    ("Person", &["person", "per", "pers"], &[
+        ("1", &["firstPerson", "first", "1st"]),
+        ("2", &["secondPerson", "second", "2nd"]),
+        ("3", &["thirdPerson", "third", "3rd"])]),
+    ("Tense", &["tense", "tns"], &[
+        ("Pres", &["present", "pres"]),
+        ("Past", &["past", "preterite"]),
+        ("Fut", &["future", "fut"]),
+        ("Imp", &["imperfect", "impf"]),
+        ("Pqp", &["pluperfect", "plupf"])]),
+    ("Mood", &["mood", "verbFormMood"], &[
+        ("Ind", &["indicative", "ind"]),
+        ("Sub", &["subjunctive", "subj"]),
+        ("Imp", &["imperative", "imper"]),
+        ("Cnd", &["conditional", "cond"])]),
+    ("VerbForm", &["verbForm", "verbFormMood"], &[
+        ("Inf", &["infinitive", "inf"]),
+        ("Part", &["participle", "part", "ptcp"]),
+        ("Ger", &["gerund", "ger"]),
+        ("Sup", &["supine"])]),
+    ("Degree", &["degree", "deg"], &[
+        ("Pos", &["positive", "pos"]),
+        ("Cmp", &["comparative", "comp", "cmp"]),
+        ("Sup", &["superlative", "sup", "superl"])]),
+    ("Definite", &["definiteness", "definite", "def"], &[
+        ("Def", &["definite"]),
+        ("Ind", &["indefinite", "indef"])]),
+    ("Voice", &["voice"], &[
+        ("Act", &["active", "activeVoice", "act"]),
+        ("Pass", &["passive", "passiveVoice", "pass"])])
+];
+
+/// Map a feature and its value to a UD feature. A feature that is not known
+/// is kept as it is
+pub fn feature(name : &str, value : &str) -> (String, String) {
+    for (ud_name, names, values) in FEATURES.iter() {
+        if ud_name.eq_ignore_ascii_case(name) || names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            for (ud_value, aliases) in values.iter() {
+                if ud_value.eq_ignore_ascii_case(value) || aliases.iter().any(|a| a.eq_ignore_ascii_case(value)) {
+                    return (ud_name.to_string(), ud_value.to_string());
+                }
+            }
+        }
+    }
+    (name.to_string(), value.to_string())
+}
+
+/// Map all of the features of a form to UD features
+pub fn features<'a, I>(features : I) -> BTreeMap<String, String>
+    where I : IntoIterator<Item=(&'a String, &'a String)> {
+    features.into_iter().map(|(k, v)| feature(k, v)).collect()
+}
+
+/// Parse a UD feature string, e.g., `Case=Gen|Number=Plur`. `_` is the
+/// empty feature string
+pub fn parse(feats : &str) -> Result<Vec<(String, String)>, String> {
+    if feats.trim().is_empty() || feats.trim() == "_" {
+        return Ok(Vec::new());
+    }
+    feats.split('|').map(|f| match f.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() && !value.trim().is_empty()
+            => Ok(feature(name.trim(), value.trim())),
+        _ => Err(format!("Bad feature {} in {}", f, feats))
+    }).collect()
+}
+
+/// Write features as a UD feature string, sorted by name
+pub fn format(features : &BTreeMap<String, String>) -> String {
+    let mut feats : Vec<String> = features.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    feats.sort_by_key(|f| f.to_lowercase());
+    feats.join("|")
+}
+
+#[test]
+fn test_features() {
+    assert_eq!(feature("number", "plural"), ("Number".to_string(), "Plur".to_string()));
+    assert_eq!(feature("case", "genitiveCase"), ("Case".to_string(), "Gen".to_string()));
+    assert_eq!(feature("Number", "Plur"), ("Number".to_string(), "Plur".to_string()));
+    assert_eq!(feature("register", "formal"), ("register".to_string(), "formal".to_string()));
+    let feats = parse("Case=Gen|number=plural").unwrap();
+    assert_eq!(format(&feats.into_iter().collect()), "Case=Gen|Number=Plur");
+    assert!(parse("Case").is_err());
+    assert!(parse("_").unwrap().is_empty());
+}